@@ -0,0 +1,262 @@
+//! Playing engines against each other.
+
+use std::{fmt, str::FromStr};
+
+use rotchess_core::{
+    chess960,
+    engine::Engine,
+    notation,
+    piece::{Pieces, Side},
+    record::{GameRecord, GameResult},
+    rng::Rng,
+    search::SearchLimits,
+    turn::Turns,
+};
+
+use crate::args::EngineSpec;
+
+/// Where games start from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Start {
+    Standard,
+    /// Chess960 position with the given number, or picked by the seed if `None`.
+    /// See [`rotchess_core::chess960`].
+    Chess960(Option<u16>),
+    /// Any shuffle of the back row, picked by the seed. See [`Pieces::shuffled_board`].
+    Shuffle,
+}
+
+impl Start {
+    /// The starting board, and its Chess960 number for Chess960 starts. `seed` picks
+    /// the back row when it isn't fixed.
+    pub fn board(&self, seed: u64) -> (Pieces, Option<u16>) {
+        match self {
+            Start::Standard => (Pieces::standard_board(), None),
+            Start::Chess960(Some(number)) => (Pieces::chess960_board(*number), Some(*number)),
+            Start::Chess960(None) => {
+                let (pieces, number) = Pieces::seeded_chess960_board(seed);
+                (pieces, Some(number))
+            }
+            Start::Shuffle => {
+                let pieces = Pieces::shuffled_board(|| {
+                    let mut ordering = [0, 1, 2, 3, 4, 5, 6, 7];
+                    Rng::new(seed).shuffle(&mut ordering);
+                    ordering
+                });
+                (pieces, None)
+            }
+        }
+    }
+}
+
+impl FromStr for Start {
+    type Err = String;
+
+    /// `standard`, `chess960`, `chess960:N` or `shuffle`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.split_once(':') {
+            None if text == "standard" => Ok(Start::Standard),
+            None if text == "chess960" => Ok(Start::Chess960(None)),
+            None if text == "shuffle" => Ok(Start::Shuffle),
+            Some(("chess960", number)) => match number.parse() {
+                Ok(number) if number < chess960::POSITIONS => Ok(Start::Chess960(Some(number))),
+                _ => Err(format!(
+                    "chess960 positions are numbered 0..960, not {number}"
+                )),
+            },
+            _ => Err(format!(
+                "unknown start {text:?}, expected standard, chess960, chess960:N or shuffle"
+            )),
+        }
+    }
+}
+
+/// An engine and the limits it searches with.
+pub struct Player {
+    pub engine: Box<dyn Engine>,
+    pub limits: SearchLimits,
+}
+
+impl Player {
+    /// A fresh engine as described by `spec`.
+    pub fn with(spec: &EngineSpec, seed: u64) -> Self {
+        Self {
+            engine: spec.kind.build(seed),
+            limits: spec.limits.clone(),
+        }
+    }
+}
+
+/// Why a game ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ending {
+    /// The winner captured the other king.
+    KingCaptured,
+    /// The side to move couldn't move. A draw.
+    NoMoves,
+    /// The game ran out of turns. A draw.
+    TurnLimit,
+}
+
+impl Ending {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Ending::KingCaptured => "king captured",
+            Ending::NoMoves => "no moves",
+            Ending::TurnLimit => "turn limit",
+        }
+    }
+}
+
+pub struct PlayedGame {
+    pub record: GameRecord,
+    pub result: GameResult,
+    pub ending: Ending,
+}
+
+/// Play a game from `start`, white to move, until a king is captured or `max_turns`
+/// engine moves have been played.
+pub fn play_game(
+    white: &mut Player,
+    black: &mut Player,
+    start: Pieces,
+    max_turns: usize,
+) -> PlayedGame {
+    let mut record = GameRecord::with(start.clone(), Side::White);
+    record.set_tag("White", white.engine.name());
+    record.set_tag("Black", black.engine.name());
+
+    let mut turns = Turns::with(start);
+    let (result, ending) = loop {
+        if let Some(side) = turns.winner() {
+            break (GameResult::win_for(side), Ending::KingCaptured);
+        }
+        if turns.curr_turn() >= max_turns {
+            break (GameResult::Draw, Ending::TurnLimit);
+        }
+
+        let player = match turns.to_move() {
+            Side::Black => &mut *black,
+            Side::White => &mut *white,
+        };
+        let Some(move_) = player.engine.choose_move(&mut turns, &player.limits) else {
+            break (GameResult::Draw, Ending::NoMoves);
+        };
+        record.push_turn(notation::move_actions(&move_, turns.working_board_ref()).to_vec());
+        turns.play(&move_);
+    };
+
+    record.set_result(result);
+    record.set_tag("Ending", ending.as_str());
+    PlayedGame {
+        record,
+        result,
+        ending,
+    }
+}
+
+/// Wins, draws and losses from one player's point of view.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Tally {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Tally {
+    /// Count a game where our player played `side`.
+    pub fn add(&mut self, result: GameResult, side: Side) {
+        match (result, side) {
+            (GameResult::Draw, _) => self.draws += 1,
+            (GameResult::WhiteWins, Side::White) | (GameResult::BlackWins, Side::Black) => {
+                self.wins += 1
+            }
+            _ => self.losses += 1,
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Points per game, from 0 to 1. Draws are worth half.
+    pub fn score(&self) -> Option<f64> {
+        (self.games() > 0)
+            .then(|| (self.wins as f64 + self.draws as f64 / 2.) / self.games() as f64)
+    }
+
+    /// Estimated Elo difference of our player over the other.
+    ///
+    /// `None` without games. Infinite after a clean sweep either way.
+    pub fn elo_difference(&self) -> Option<f64> {
+        self.score().map(|score| -400. * (1. / score - 1.).log10())
+    }
+}
+
+impl fmt::Display for Tally {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "+{} ={} -{} over {} games",
+            self.wins,
+            self.draws,
+            self.losses,
+            self.games()
+        )?;
+        if let (Some(score), Some(elo)) = (self.score(), self.elo_difference()) {
+            write!(f, ", score {:.1}%, elo {elo:+.0}", score * 100.)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Player, Start, Tally, play_game};
+    use crate::args::EngineSpec;
+    use rotchess_core::{
+        piece::Side,
+        record::{GameRecord, GameResult},
+    };
+
+    #[test]
+    fn games_are_recorded_and_replay() {
+        let spec: EngineSpec = "random".parse().unwrap();
+        let game = play_game(
+            &mut Player::with(&spec, 1),
+            &mut Player::with(&spec, 2),
+            Start::Chess960(None).board(3).0,
+            20,
+        );
+        let record: GameRecord = game.record.to_string().parse().unwrap();
+        assert_eq!(record.result(), Some(game.result));
+        assert!(record.turns().len() <= 20);
+        assert!(record.replay().is_ok());
+    }
+
+    #[test]
+    fn starts_parse() {
+        assert_eq!("chess960:518".parse(), Ok(Start::Chess960(Some(518))));
+        assert!("chess960:960".parse::<Start>().is_err());
+        let (_, number) = Start::Chess960(None).board(7);
+        assert_eq!(number, Start::Chess960(None).board(7).1);
+        assert_eq!(Start::Shuffle.board(7).1, None);
+    }
+
+    #[test]
+    fn elo_follows_the_score() {
+        let mut tally = Tally::default();
+        assert_eq!(tally.elo_difference(), None);
+        tally.add(GameResult::WhiteWins, Side::White);
+        tally.add(GameResult::WhiteWins, Side::Black);
+        assert_eq!(tally.elo_difference(), Some(0.));
+        tally.add(GameResult::Draw, Side::Black);
+        tally.add(GameResult::BlackWins, Side::Black);
+        // 2.5 out of 4.
+        assert!((tally.elo_difference().unwrap() - 88.7).abs() < 0.1);
+        assert_eq!(
+            tally.to_string(),
+            "+2 =1 -1 over 4 games, score 62.5%, elo +89"
+        );
+    }
+}
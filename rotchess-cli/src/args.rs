@@ -0,0 +1,153 @@
+//! Tiny command line parsing.
+//!
+//! Options look like `--name=value`, flags like `--name`, and everything else is
+//! positional. That's all our tools need, so we don't pull in a parser crate.
+
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use rotchess_core::{engine::EngineKind, search::SearchLimits};
+
+/// Parsed command line arguments.
+#[derive(Debug, Default)]
+pub struct Args {
+    options: HashMap<String, Option<String>>,
+    positional: Vec<String>,
+}
+
+impl Args {
+    /// Parse the arguments this process was started with, skipping the program name.
+    pub fn from_env() -> Self {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        let mut ans = Self::default();
+        for arg in args {
+            match arg.strip_prefix("--") {
+                Some(option) => match option.split_once('=') {
+                    Some((name, value)) => {
+                        ans.options
+                            .insert(name.to_string(), Some(value.to_string()));
+                    }
+                    None => {
+                        ans.options.insert(option.to_string(), None);
+                    }
+                },
+                None => ans.positional.push(arg),
+            }
+        }
+        ans
+    }
+
+    pub fn positional(&self) -> &[String] {
+        &self.positional
+    }
+
+    /// Whether `--name` was given, with or without a value.
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+
+    /// The value of `--name=value` parsed as a `T`, or `default` if it wasn't given.
+    pub fn get_or<T: FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        Ok(self.get(name)?.unwrap_or(default))
+    }
+
+    /// The value of `--name=value` parsed as a `T`, if it was given.
+    pub fn get<T: FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        match self.options.get(name) {
+            None => Ok(None),
+            Some(None) => Err(format!("--{name} needs a value, like --{name}=...")),
+            Some(Some(value)) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("can't understand --{name}={value}")),
+        }
+    }
+}
+
+/// An engine and how long it may think, written like `alpha-beta:depth=3:time=0.5`.
+///
+/// The kind is an [`EngineKind::name`]. It's followed by any of `depth=N`,
+/// `nodes=N` and `time=SECONDS`, which become [`SearchLimits`]. Limits that
+/// aren't given keep their default values.
+#[derive(Clone)]
+pub struct EngineSpec {
+    pub kind: EngineKind,
+    pub limits: SearchLimits,
+}
+
+impl FromStr for EngineSpec {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parts = text.split(':');
+        let name = parts.next().unwrap_or_default();
+        let kind = EngineKind::from_name(name).ok_or_else(|| {
+            let names: Vec<_> = EngineKind::ALL.iter().map(EngineKind::name).collect();
+            format!(
+                "unknown engine {name:?}, expected one of {}",
+                names.join(", ")
+            )
+        })?;
+
+        let mut limits = SearchLimits::default();
+        for part in parts {
+            let bad = || format!("can't understand {part:?} in engine {text:?}");
+            let (key, value) = part.split_once('=').ok_or_else(bad)?;
+            limits = match key {
+                "depth" => limits.with_depth(value.parse().map_err(|_| bad())?),
+                "nodes" => limits.with_nodes(value.parse().map_err(|_| bad())?),
+                "time" => limits.with_time(value.parse().map_err(|_| bad())?),
+                _ => return Err(bad()),
+            };
+        }
+        Ok(Self { kind, limits })
+    }
+}
+
+impl fmt::Display for EngineSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind.name())?;
+        if let Some(depth) = self.limits.depth {
+            write!(f, ":depth={depth}")?;
+        }
+        if let Some(nodes) = self.limits.nodes {
+            write!(f, ":nodes={nodes}")?;
+        }
+        if let Some(time) = self.limits.time {
+            write!(f, ":time={time}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Args, EngineSpec};
+    use rotchess_core::engine::EngineKind;
+
+    #[test]
+    fn options_flags_and_positionals() {
+        let args = Args::parse(
+            ["--games=4", "--verbose", "out"]
+                .into_iter()
+                .map(String::from),
+        );
+        assert_eq!(args.get::<usize>("games"), Ok(Some(4)));
+        assert!(args.flag("verbose"));
+        assert!(args.get::<usize>("verbose").is_err());
+        assert_eq!(args.get_or("missing", 7), Ok(7));
+        assert_eq!(args.positional(), ["out"]);
+    }
+
+    #[test]
+    fn engine_specs_round_trip() {
+        let spec: EngineSpec = "greedy:depth=3:time=0.5".parse().unwrap();
+        assert_eq!(spec.kind, EngineKind::Greedy);
+        assert_eq!(spec.limits.depth, Some(3));
+        assert_eq!(spec.to_string(), "greedy:depth=3:time=0.5");
+        assert!("greedy:speed=9".parse::<EngineSpec>().is_err());
+        assert!("deep-blue".parse::<EngineSpec>().is_err());
+    }
+}
@@ -0,0 +1,170 @@
+//! One of our built-in engines, speaking the [protocol](rotchess_core::protocol)
+//! on stdin and stdout.
+//!
+//! ```text
+//! rotchess-engine --engine=alpha-beta
+//! ```
+//!
+//! Searches run on their own thread, so that `stop` and `isready` are answered
+//! while thinking.
+
+use std::{
+    io::{self, BufRead},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+};
+
+use rotchess_cli::args::Args;
+use rotchess_core::{
+    engine::{Engine, EngineKind},
+    notation,
+    protocol::{Command, Go, Info, PositionSetup, Reply},
+};
+
+const USAGE: &str = "\
+usage: rotchess-engine [--engine=KIND] [--seed=N]
+
+  --engine=KIND       random, greedy or alpha-beta (default alpha-beta)
+  --seed=N            seed for the random engine (default 0)
+
+then talk to it on stdin, starting with `rotchess`.";
+
+/// A search in progress. Hands the engine back when it's done.
+struct Search {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Box<dyn Engine>>,
+}
+
+impl Search {
+    fn finish(self) -> Box<dyn Engine> {
+        self.handle.join().expect("search thread panicked")
+    }
+
+    fn stop(self) -> Box<dyn Engine> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.finish()
+    }
+}
+
+/// Either the engine is idle, or a search owns it.
+enum State {
+    Idle(Box<dyn Engine>),
+    Searching(Search),
+}
+
+impl State {
+    /// Wait for the search to end on its own, if there is one.
+    fn idle(self) -> Box<dyn Engine> {
+        match self {
+            State::Idle(engine) => engine,
+            State::Searching(search) => search.finish(),
+        }
+    }
+}
+
+fn start_search(mut engine: Box<dyn Engine>, setup: &PositionSetup, go: Go) -> State {
+    let stop = Arc::new(AtomicBool::new(false));
+    let limits = go
+        .limits()
+        .with_stop(stop.clone())
+        .with_on_iteration(Arc::new(|turns, result| {
+            println!("{}", Reply::Info(Info::from_result(turns, result)));
+        }));
+    let setup = setup.clone();
+    let handle = thread::spawn(move || {
+        let mut turns = setup
+            .turns()
+            .expect("positions are checked when they're set");
+        let best_move = engine
+            .choose_move(&mut turns, &limits)
+            .map(|move_| notation::move_actions(&move_, turns.working_board_ref()).to_vec());
+        println!("{}", Reply::BestMove(best_move));
+        engine
+    });
+    State::Searching(Search { stop, handle })
+}
+
+fn main() {
+    let args = Args::from_env();
+    if args.flag("help") {
+        println!("{USAGE}");
+        return;
+    }
+    let (kind, seed) = match (
+        args.get_or("engine", "alpha-beta".to_string()),
+        args.get_or("seed", 0),
+    ) {
+        (Ok(name), Ok(seed)) => match EngineKind::from_name(&name) {
+            Some(kind) => (kind, seed),
+            None => {
+                eprintln!("unknown engine {name:?}\n\n{USAGE}");
+                std::process::exit(1);
+            }
+        },
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{e}\n\n{USAGE}");
+            std::process::exit(1);
+        }
+    };
+
+    let mut state = State::Idle(kind.build(seed));
+    let mut setup = PositionSetup {
+        start: None,
+        moves: vec![],
+    };
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let command = match Command::parse(&line) {
+            Ok(command) => command,
+            Err(e) => {
+                eprintln!("ignoring {line:?}: {e}");
+                continue;
+            }
+        };
+
+        state = match (command, state) {
+            (Command::Hello, state) => {
+                println!(
+                    "{}",
+                    Reply::Id {
+                        name: kind.name().to_string()
+                    }
+                );
+                println!("{}", Reply::HelloOk);
+                state
+            }
+            (Command::IsReady, state) => {
+                println!("{}", Reply::ReadyOk);
+                state
+            }
+            (Command::NewGame, state) => {
+                state.idle();
+                State::Idle(kind.build(seed))
+            }
+            (Command::Position(new_setup), state) => {
+                match new_setup.turns() {
+                    Ok(_) => setup = new_setup,
+                    Err(e) => eprintln!("ignoring position: {e}"),
+                }
+                state
+            }
+            (Command::Go(go), state) => start_search(state.idle(), &setup, go),
+            (Command::Stop, State::Searching(search)) => State::Idle(search.stop()),
+            (Command::Stop, state) => state,
+            (Command::Quit, State::Searching(search)) => {
+                search.stop();
+                return;
+            }
+            (Command::Quit, _) => return,
+        };
+    }
+
+    if let State::Searching(search) = state {
+        search.stop();
+    }
+}
@@ -0,0 +1,121 @@
+//! Play engines against each other without a window.
+//!
+//! ```text
+//! rotchess-match --a=alpha-beta:depth=2 --b=greedy --games=10 --start=chess960 --out=games
+//! ```
+//!
+//! Engines swap colors every game, and each pair of games shares a start, so
+//! neither engine gets a luckier back row. Prints the result of every game, then
+//! the score and Elo difference from engine A's point of view.
+
+use std::{fs, path::PathBuf, process::ExitCode};
+
+use rotchess_cli::{
+    arena::{Player, Start, Tally, play_game},
+    args::{Args, EngineSpec},
+};
+use rotchess_core::piece::Side;
+
+const USAGE: &str = "\
+usage: rotchess-match [options]
+
+  --a=ENGINE          engine A (default alpha-beta)
+  --b=ENGINE          engine B (default greedy)
+  --games=N           number of games (default 2)
+  --start=START       standard, chess960, chess960:N (a numbered position)
+                      or shuffle (any back row) (default standard)
+  --seed=N            seed for chess960 and shuffle starts, and random engines
+                      (default 0)
+  --max-turns=N       engine moves before a game is drawn (default 200)
+  --out=DIR           write each game's record to DIR/game-NNNN.rec
+
+ENGINE is a kind (random, greedy, alpha-beta) optionally followed by
+:depth=N, :nodes=N and :time=SECONDS, like alpha-beta:depth=3:time=1.";
+
+struct Options {
+    a: EngineSpec,
+    b: EngineSpec,
+    games: usize,
+    start: Start,
+    seed: u64,
+    max_turns: usize,
+    out: Option<PathBuf>,
+}
+
+impl Options {
+    fn from(args: &Args) -> Result<Self, String> {
+        Ok(Self {
+            a: args.get_or("a", "alpha-beta".parse()?)?,
+            b: args.get_or("b", "greedy".parse()?)?,
+            games: args.get_or("games", 2)?,
+            start: args.get_or("start", Start::Standard)?,
+            seed: args.get_or("seed", 0)?,
+            max_turns: args.get_or("max-turns", 200)?,
+            out: args.get("out")?,
+        })
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::from_env();
+    if args.flag("help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    let options = match Options::from(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(out) = &options.out
+        && let Err(e) = fs::create_dir_all(out)
+    {
+        eprintln!("can't create {}: {e}", out.display());
+        return ExitCode::FAILURE;
+    }
+
+    println!("A: {}\nB: {}", options.a, options.b);
+    let mut tally = Tally::default();
+    for i in 0..options.games {
+        let game_seed = options.seed.wrapping_add(i as u64);
+        let mut a = Player::with(&options.a, game_seed);
+        let mut b = Player::with(&options.b, game_seed.wrapping_add(1 << 32));
+        let a_side = if i % 2 == 0 { Side::White } else { Side::Black };
+        let (white, black) = match a_side {
+            Side::White => (&mut a, &mut b),
+            Side::Black => (&mut b, &mut a),
+        };
+
+        let (start, number) = options.start.board(options.seed.wrapping_add(i as u64 / 2));
+        let mut game = play_game(white, black, start, options.max_turns);
+        game.record.set_tag("Game", &(i + 1).to_string());
+        let start = match number {
+            Some(number) => {
+                game.record.set_tag("Chess960", &number.to_string());
+                format!(" from chess960 #{number}")
+            }
+            None => String::new(),
+        };
+        tally.add(game.result, a_side);
+        println!(
+            "game {}: A as {a_side:?}{start}, {} ({}) after {} turns",
+            i + 1,
+            game.result.as_str(),
+            game.ending.as_str(),
+            game.record.turns().len()
+        );
+
+        if let Some(out) = &options.out {
+            let path = out.join(format!("game-{:04}.rec", i + 1));
+            if let Err(e) = fs::write(&path, game.record.to_string()) {
+                eprintln!("can't write {}: {e}", path.display());
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    println!("A vs B: {tally}");
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,73 @@
+//! Count move sequences, to check move generation after a refactor.
+//!
+//! ```text
+//! rotchess-perft --depth=2 --divide
+//! rotchess-perft --depth=3 --position="w WK@4.5,7.5:0 BK@4.5,0.5:3.1415927"
+//! ```
+//!
+//! See [`Turns::perft`] for what's being counted.
+
+use std::{process::ExitCode, time::Instant};
+
+use rotchess_cli::{arena::Start, args::Args};
+use rotchess_core::{notation, turn::Turns};
+
+const USAGE: &str = "\
+usage: rotchess-perft [options]
+
+  --depth=N           how many engine moves deep to count (default 2)
+  --divide            list the count below every root move
+  --start=START       standard, chess960, chess960:N or shuffle (default standard)
+  --seed=N            picks the chess960 or shuffle start (default 0)
+  --position=POS      start from a position in notation instead";
+
+fn turns(args: &Args) -> Result<Turns, String> {
+    if let Some(position) = args.get::<String>("position")? {
+        let (pieces, to_move) = notation::parse_position(&position).map_err(|e| e.to_string())?;
+        let mut turns = Turns::with(pieces);
+        turns.set_to_move(to_move);
+        return Ok(turns);
+    }
+    let start: Start = args.get_or("start", Start::Standard)?;
+    let (pieces, number) = start.board(args.get_or("seed", 0)?);
+    if let Some(number) = number {
+        println!("chess960 #{number}");
+    }
+    Ok(Turns::with(pieces))
+}
+
+fn main() -> ExitCode {
+    let args = Args::from_env();
+    if args.flag("help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    let (mut turns, depth) = match (turns(&args), args.get_or("depth", 2)) {
+        (Ok(turns), Ok(depth)) => (turns, depth),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let started = Instant::now();
+    let total = if args.flag("divide") && depth > 0 {
+        let board = turns.working_board_ref().clone();
+        let divided = turns.divide(depth);
+        for (move_, count) in &divided {
+            println!("{} {count}", notation::write_move(move_, &board));
+        }
+        println!();
+        divided.iter().map(|(_, count)| count).sum()
+    } else {
+        turns.perft(depth)
+    };
+    let seconds = started.elapsed().as_secs_f64();
+
+    println!("perft({depth}) = {total}");
+    println!(
+        "{seconds:.2}s, {:.0} nodes/s",
+        total as f64 / seconds.max(f64::EPSILON)
+    );
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,169 @@
+//! Draw a position or a whole game as SVG, for docs and bug reports.
+//!
+//! ```text
+//! rotchess-render --select=4.5,6.5 --out=board.svg
+//! rotchess-render --position="w WK@4.5,7.5:0 BK@4.5,0.5:3.1415927" --hit-circles
+//! rotchess-render --record=games/game-0001.rec --out=game.svg
+//! rotchess-render --record=games/game-0001.rec --frames=frames --fps=30
+//! ```
+//!
+//! See [`rotchess_svg`] for what's drawn, and [`rotchess_svg::animation`] for how
+//! games move.
+
+use std::{fs, path::PathBuf, process::ExitCode};
+
+use rotchess_cli::{arena::Start, args::Args};
+use rotchess_core::{notation, piece::Pieces, record::GameRecord};
+use rotchess_svg::{
+    Options,
+    animation::{self, Timing},
+};
+
+const USAGE: &str = "\
+usage: rotchess-render [options]
+
+  --start=START       standard, chess960, chess960:N or shuffle (default standard)
+  --seed=N            picks the chess960 or shuffle start (default 0)
+  --position=POS      draw a position in notation instead
+  --select=X,Y        select the piece at X,Y, showing its travel points
+  --pointer=X,Y       highlight the travel point under X,Y, like a mouse would
+  --hit-circles       outline every piece's hit circle
+  --tile-size=PX      pixels per tile (default 64)
+  --out=FILE          write to FILE instead of stdout
+
+games:
+  --record=FILE       draw the game in a record as an animated svg
+  --turn=N            draw only the board after turn N instead
+  --frames=DIR        write a still per frame to DIR/frame-NNNNN.svg instead
+  --hold=SECONDS      how long each board is shown (default 1)
+  --travel=SECONDS    how long pieces take to move (default 0.5)
+  --fps=N             frames per second for --frames (default 20)
+  --once              don't loop the animation";
+
+fn point(args: &Args, name: &str) -> Result<Option<(f32, f32)>, String> {
+    args.get::<String>(name)?
+        .map(|point| notation::parse_point(&point).map_err(|e| format!("--{name}: {e}")))
+        .transpose()
+}
+
+fn options(args: &Args) -> Result<Options, String> {
+    let mut options = Options::default()
+        .with_tile_size(args.get_or("tile-size", 64.)?)
+        .with_hit_circles(args.flag("hit-circles"));
+    if let Some((x, y)) = point(args, "pointer")? {
+        options = options.with_pointer(x, y);
+    }
+    Ok(options)
+}
+
+/// Every board of the game in `--record`.
+fn history(args: &Args, path: &PathBuf) -> Result<Vec<Pieces>, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+    let record: GameRecord = text
+        .parse()
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    let turns = match record.replay() {
+        Ok(turns) => turns,
+        Err((turns, e)) => {
+            eprintln!(
+                "{}: stopping at turn {}: {e}",
+                path.display(),
+                turns.curr_turn() + 1
+            );
+            *turns
+        }
+    };
+    let mut history = turns.history().to_vec();
+    if let Some(turn) = args.get::<usize>("turn")? {
+        let board = history
+            .get(turn)
+            .ok_or_else(|| format!("the game has turns 0 to {}, not {turn}", history.len() - 1))?;
+        history = vec![board.clone()];
+    }
+    Ok(history)
+}
+
+fn render_position(args: &Args, pieces: &Pieces) -> Result<String, String> {
+    let options = options(args)?;
+    let Some(at) = point(args, "select")? else {
+        return Ok(rotchess_svg::render(pieces, None, &options));
+    };
+    let id = notation::resolve_piece(pieces, at).map_err(|e| e.to_string())?;
+    let travel_points = rotchess_svg::travel_points(pieces, id).expect("the piece was found");
+    let selected = pieces.get(id).expect("the piece was found");
+    Ok(rotchess_svg::render(
+        pieces,
+        Some((selected, &travel_points)),
+        &options,
+    ))
+}
+
+fn write(out: Option<&PathBuf>, svg: &str) -> Result<(), String> {
+    match out {
+        Some(out) => fs::write(out, svg).map_err(|e| format!("can't write {}: {e}", out.display())),
+        None => {
+            print!("{svg}");
+            Ok(())
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let out: Option<PathBuf> = args.get("out")?;
+
+    let Some(record) = args.get::<PathBuf>("record")? else {
+        let pieces = match args.get::<String>("position")? {
+            Some(position) => {
+                notation::parse_position(&position)
+                    .map_err(|e| e.to_string())?
+                    .0
+            }
+            None => {
+                let start: Start = args.get_or("start", Start::Standard)?;
+                start.board(args.get_or("seed", 0)?).0
+            }
+        };
+        return write(out.as_ref(), &render_position(args, &pieces)?);
+    };
+
+    let history = history(args, &record)?;
+    if let [board] = history.as_slice() {
+        return write(out.as_ref(), &render_position(args, board)?);
+    }
+    let timing = Timing::default()
+        .with_hold(args.get_or("hold", 1.)?)
+        .with_travel(args.get_or("travel", 0.5)?)
+        .with_frame_rate(args.get_or("fps", 20.)?)
+        .with_repeat(!args.flag("once"));
+    let options = options(args)?;
+
+    let Some(dir) = args.get::<PathBuf>("frames")? else {
+        return write(
+            out.as_ref(),
+            &animation::animated_svg(&history, &timing, &options),
+        );
+    };
+    fs::create_dir_all(&dir).map_err(|e| format!("can't create {}: {e}", dir.display()))?;
+    let mut count = 0;
+    for (i, frame) in animation::frames(&history, &timing, &options).enumerate() {
+        let path = dir.join(format!("frame-{i:05}.svg"));
+        fs::write(&path, frame).map_err(|e| format!("can't write {}: {e}", path.display()))?;
+        count += 1;
+    }
+    println!("wrote {count} frames to {}", dir.display());
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = Args::from_env();
+    if args.flag("help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    if let Err(e) = run(&args) {
+        eprintln!("{e}\n\n{USAGE}");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
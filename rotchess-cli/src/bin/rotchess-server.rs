@@ -0,0 +1,82 @@
+//! Referee online games for anyone who connects.
+//!
+//! ```text
+//! rotchess-server --addr=0.0.0.0:7777 --records=games
+//! ```
+//!
+//! Players join with a lobby key made of the server's address and any secret they
+//! agree on, which names their game, like `10.0.0.2:7777/c0ffee`. See
+//! [`rotchess_net::server`] for how games are run.
+
+use std::{process::ExitCode, time::Duration};
+
+use rotchess_cli::{arena::Start, args::Args};
+use rotchess_net::{Rules, server::Server};
+
+const USAGE: &str = "\
+usage: rotchess-server [options]
+
+  --addr=ADDR         listen here (default 0.0.0.0:7777)
+  --records=DIR       write each finished game's record into DIR
+  --start=START       every game's start: standard, chess960, chess960:N or
+                      shuffle (default standard)
+  --seed=N            picks the chess960 or shuffle start (default 0)
+  --position=POS      start every game from a position in notation instead
+  --delay=SECONDS     show spectators each turn this long after it's played
+                      (default 0)
+
+players join with lobby keys like ADDR/SECRET, where SECRET is any hex
+number; everyone with the same SECRET is in the same game.";
+
+fn server(args: &Args) -> Result<Server, String> {
+    let rules = match args.get::<Rules>("position")? {
+        Some(rules) => rules,
+        None => {
+            let start: Start = args.get_or("start", Start::Standard)?;
+            let (start, number) = start.board(args.get_or("seed", 0)?);
+            if let Some(number) = number {
+                println!("chess960 #{number}");
+            }
+            Rules {
+                start,
+                ..Rules::default()
+            }
+        }
+    };
+    let addr: String = args.get_or("addr", String::from("0.0.0.0:7777"))?;
+    let mut server =
+        Server::bind(&addr, rules).map_err(|e| format!("can't listen on {addr}: {e}"))?;
+    if let Some(dir) = args.get::<String>("records")? {
+        server = server.with_records(dir);
+    }
+    let delay = args.get_or("delay", 0.0)?;
+    let delay = Duration::try_from_secs_f64(delay)
+        .map_err(|_| format!("--delay takes a number of seconds, not {delay}"))?;
+    server = server.with_delay(delay);
+    Ok(server)
+}
+
+fn main() -> ExitCode {
+    let args = Args::from_env();
+    if args.flag("help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    let server = match server(&args) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Ok(addr) = server.local_addr() {
+        println!("serving on {addr}");
+    }
+    match server.run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Play rotchess in a terminal, say over SSH.
+//!
+//! ```text
+//! rotchess-term --engine=alpha-beta:depth=3 --start=chess960
+//! ```
+//!
+//! Turns are typed in notation and the board is redrawn as text after each one.
+//! See [`rotchess_cli::term`] for what the board shows; type `help` for commands.
+//!
+//! With `--join`, play one side of an online game instead, hosted by another
+//! player or on `rotchess-server`. With `--watch`, watch one on a server.
+
+use std::{
+    io::{self, BufRead, Write},
+    process::ExitCode,
+};
+
+use rotchess_cli::{
+    arena::{Player, Start},
+    args::{Args, EngineSpec},
+    term::{Response, Terminal},
+};
+use rotchess_core::{notation, piece::Side};
+use rotchess_event_api::RotchessEmulator;
+use rotchess_net::{Link, LobbyKey};
+
+const USAGE: &str = "\
+usage: rotchess-term [options]
+
+  --engine=ENGINE     engine used by go (default alpha-beta:depth=3)
+  --start=START       standard, chess960, chess960:N or shuffle (default standard)
+  --seed=N            picks the chess960 or shuffle start, and seeds random
+                      engines (default 0)
+  --position=POS      start from a position in notation instead
+  --join=KEY          join an online game with a lobby key, like
+                      10.0.0.2:7777/9f86d081884c7d65; the host or server
+                      picks the start
+  --side=w|b          the side to ask for when joining (default either)
+  --watch=KEY         watch a game on a server, printing each turn as it's
+                      played
+
+ENGINE is a kind (random, greedy, alpha-beta) optionally followed by
+:depth=N, :nodes=N and :time=SECONDS, like alpha-beta:depth=3:time=1.";
+
+fn terminal(args: &Args) -> Result<Terminal, String> {
+    let spec: EngineSpec = args.get_or("engine", "alpha-beta:depth=3".parse()?)?;
+    let seed = args.get_or("seed", 0)?;
+    let player = Player::with(&spec, seed);
+
+    if let Some(key) = args.get::<LobbyKey>("watch")? {
+        let link = Link::watch(&key).map_err(|e| format!("can't watch: {e}"))?;
+        let emulator = link.new_emulator();
+        return Ok(Terminal::with(emulator, player.limits).with_link(link));
+    }
+    if let Some(key) = args.get::<LobbyKey>("join")? {
+        let side = match args.get::<String>("side")?.as_deref() {
+            None => None,
+            Some("w") => Some(Side::White),
+            Some("b") => Some(Side::Black),
+            Some(side) => return Err(format!("--side takes w or b, not {side}")),
+        };
+        let link = Link::join(&key, side).map_err(|e| format!("can't join: {e}"))?;
+        let mut emulator = link.new_emulator();
+        emulator.set_engine(player.engine);
+        return Ok(Terminal::with(emulator, player.limits).with_link(link));
+    }
+
+    let mut emulator = if let Some(position) = args.get::<String>("position")? {
+        let (pieces, to_move) = notation::parse_position(&position).map_err(|e| e.to_string())?;
+        let mut emulator = RotchessEmulator::with(pieces);
+        emulator.set_to_move(to_move);
+        emulator
+    } else {
+        let start: Start = args.get_or("start", Start::Standard)?;
+        let (pieces, number) = start.board(seed);
+        if let Some(number) = number {
+            println!("chess960 #{number}");
+        }
+        RotchessEmulator::with(pieces)
+    };
+    emulator.set_engine(player.engine);
+    Ok(Terminal::with(emulator, player.limits))
+}
+
+fn main() -> ExitCode {
+    let args = Args::from_env();
+    if args.flag("help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    let mut terminal = match terminal(&args) {
+        Ok(terminal) => terminal,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!("{}\ntype help for commands", terminal.board());
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        while terminal.waiting() {
+            println!("waiting for {:?}...", terminal.emulator().to_move());
+            print(terminal.wait());
+        }
+        print!("> ");
+        _ = io::stdout().flush();
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+        match terminal.handle(&line) {
+            Response::Quit => break,
+            response => print(response),
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn print(response: Response) {
+    match response {
+        Response::Print(text) => println!("{text}"),
+        Response::Error(e) => println!("error: {e}"),
+        Response::Quit => {}
+    }
+}
@@ -0,0 +1,8 @@
+//! Shared code for the command line rotchess tools in `src/bin`.
+//!
+//! None of these need a window. They're for running engines against each other,
+//! debugging move generation, playing over SSH, and the like.
+
+pub mod arena;
+pub mod args;
+pub mod term;
@@ -0,0 +1,461 @@
+//! A text frontend on [`RotchessEmulator`], for playing without a window.
+//!
+//! [`Terminal`] takes one line of input at a time and returns what to print, so
+//! `rotchess-term` is just a read-print loop around it, and tests can type at it.
+//!
+//! The board is drawn a tile per cell, each showing the side (`w` or `b`), the
+//! kind (`PRNBQK`) and an arrow for where the piece faces. Pieces sit anywhere in
+//! rotchess, so a piece that isn't on its tile's center gets a `~`, and a tile with
+//! more than one piece gets a `+`. `pieces` lists exact coordinates.
+//!
+//! With a [`Link`], the terminal plays one side of an online game, against a host or
+//! on a [server](rotchess_net::server). Our turns are sent as they're played, and
+//! [`Terminal::wait`] waits for the other side's. A spectator's link just waits,
+//! printing each turn as it comes.
+
+use std::{f32::consts::PI, fmt::Write};
+
+use rotchess_core::{
+    notation,
+    piece::{Piece, Side},
+    search::SearchLimits,
+};
+use rotchess_event_api::{Control, Event, RotchessEmulator};
+use rotchess_net::{Link, NetError};
+
+const HELP: &str = "\
+enter a turn in notation to play it:
+  4.5,6.5>4.5,4.5        travel the piece at 4.5,6.5 to 4.5,4.5
+  4.5,6.5:0.785          rotate it to 0.785 radians (counterclockwise)
+  4.5,6.5>4.5,4.5;4.5,4.5:0
+                         both, as one turn
+tile x,y on the board has its center at x.5,y.5.
+
+commands:
+  go                     let the engine move for the side to move
+  first, prev, next, last
+                         step through history (also <<, <, >, >>)
+  side w|b               hand the turn to a side, when offline
+  flip                   view the board from the other side
+  pieces                 list every piece with its exact place
+  position               print the position in notation
+  board                  print the board again
+  help                   this
+  quit";
+
+/// Arrows for the eight compass directions, clockwise from up.
+const ARROWS: [char; 8] = ['↑', '↗', '→', '↘', '↓', '↙', '←', '↖'];
+
+/// The arrow closest to the way a piece with `angle` faces on screen.
+///
+/// Angle 0 faces up, and angles grow counterclockwise.
+pub fn arrow(angle: f32) -> char {
+    let eighths = (-angle / (PI / 4.)).round() as i64;
+    ARROWS[eighths.rem_euclid(8) as usize]
+}
+
+/// A piece's side, kind and facing, like `wN↑`. Flipped boards are upside down, so
+/// their arrows are too.
+fn piece_label(piece: &Piece, flipped: bool) -> String {
+    let angle = if flipped {
+        piece.angle() + PI
+    } else {
+        piece.angle()
+    };
+    format!(
+        "{}{}{}",
+        notation::side_letter(piece.side()).to_ascii_lowercase(),
+        notation::kind_letter(piece.kind()),
+        arrow(angle)
+    )
+}
+
+/// Draw `pieces` as text, as seen by White, or by Black if `flipped`.
+pub fn render<'a>(pieces: impl IntoIterator<Item = &'a Piece>, flipped: bool) -> String {
+    let mut tiles: [[Vec<&Piece>; 8]; 8] = Default::default();
+    for piece in pieces {
+        let tile = |coord: f32| (coord.floor() as i64).clamp(0, 7) as usize;
+        tiles[tile(piece.y())][tile(piece.x())].push(piece);
+    }
+    let order = |i: usize| if flipped { 7 - i } else { i };
+
+    let mut ans = String::from("   ");
+    for col in (0..8).map(order) {
+        _ = write!(ans, "  {col} ");
+    }
+    ans.push('\n');
+    for row in (0..8).map(order) {
+        _ = write!(ans, "{row}  ");
+        for col in (0..8).map(order) {
+            let cell = match tiles[row][col].as_slice() {
+                [] => String::from(" .  "),
+                [piece] => {
+                    let centered = piece.x() == col as f32 + 0.5 && piece.y() == row as f32 + 0.5;
+                    let mark = if centered { ' ' } else { '~' };
+                    format!("{}{mark}", piece_label(piece, flipped))
+                }
+                [piece, ..] => format!("{}+", piece_label(piece, flipped)),
+            };
+            ans.push_str(&cell);
+        }
+        _ = writeln!(ans, " {row}");
+    }
+    ans
+}
+
+/// What to do after a line of input.
+#[derive(Debug, PartialEq)]
+pub enum Response {
+    /// Print this.
+    Print(String),
+    /// Something went wrong. Print this, then carry on.
+    Error(String),
+    Quit,
+}
+
+/// A game played by typing.
+pub struct Terminal {
+    emulator: RotchessEmulator,
+    /// How long [`Self::handle`] lets the engine think on `go`.
+    limits: SearchLimits,
+    flipped: bool,
+    /// Our end of an online game, if we're playing one.
+    link: Option<Link>,
+}
+
+impl Terminal {
+    pub fn with(emulator: RotchessEmulator, limits: SearchLimits) -> Self {
+        Self {
+            emulator,
+            limits,
+            flipped: false,
+            link: None,
+        }
+    }
+
+    /// Play one side of an online game, or watch one. The emulator should be the
+    /// link's [new emulator](Link::new_emulator), and the board is flipped for Black.
+    pub fn with_link(mut self, link: Link) -> Self {
+        self.flipped = link.side() == Some(Side::Black);
+        self.link = Some(link);
+        self
+    }
+
+    /// Whether it's the other side's turn in an online game, or we're watching one
+    /// that isn't over.
+    pub fn waiting(&self) -> bool {
+        self.link.as_ref().is_some_and(|link| {
+            Some(self.emulator.to_move()) != link.side() && self.emulator.turns().winner().is_none()
+        })
+    }
+
+    /// Wait for the other side of an online game to play, then show what they did.
+    pub fn wait(&mut self) -> Response {
+        let Some(link) = self.link.as_mut() else {
+            return Response::Error(String::from("we're not online"));
+        };
+        let side = self.emulator.to_move();
+        match link.wait(&mut self.emulator) {
+            Ok(turns) if turns.is_empty() => Response::Print(self.board()),
+            Ok(turns) => {
+                let turns: Vec<_> = turns.iter().map(|t| notation::write_actions(t)).collect();
+                Response::Print(format!(
+                    "{side:?} played {}\n{}",
+                    turns.join(", "),
+                    self.board()
+                ))
+            }
+            Err(e) => self.go_offline(e),
+        }
+    }
+
+    /// Leave an online game that can't go on, and say why. A dropped connection is
+    /// tried again first.
+    fn go_offline(&mut self, why: NetError) -> Response {
+        if let (NetError::Dropped, Some(link)) = (&why, self.link.as_mut())
+            && link.reconnect(&mut self.emulator).is_ok()
+        {
+            return Response::Print(format!("reconnected\n{}", self.board()));
+        }
+        self.link = None;
+        self.emulator.set_control(Control::Anyone);
+        Response::Error(format!("{why}, so we're offline now"))
+    }
+
+    pub fn emulator(&self) -> &RotchessEmulator {
+        &self.emulator
+    }
+
+    /// The board, and whose turn it is at which point in history.
+    pub fn board(&self) -> String {
+        let turns = self.emulator.turns();
+        let mut ans = render(self.emulator.pieces(), self.flipped);
+        _ = write!(
+            ans,
+            "\nturn {} of {}, {:?} to move",
+            turns.curr_turn(),
+            turns.turn_count() - 1,
+            self.emulator.to_move()
+        );
+        if let Some(winner) = turns.winner() {
+            _ = write!(ans, ", {winner:?} has won");
+        }
+        match self.link.as_ref().map(Link::side) {
+            Some(Some(side)) => _ = write!(ans, ", we're {side:?}"),
+            Some(None) => _ = write!(ans, ", we're watching"),
+            None => {}
+        }
+        ans
+    }
+
+    /// Act on a line of input.
+    pub fn handle(&mut self, line: &str) -> Response {
+        let mut words = line.split_whitespace();
+        let (Some(command), rest) = (words.next(), words.next()) else {
+            return Response::Print(self.board());
+        };
+
+        let history = match command {
+            "first" | "<<" => Some(Event::FirstTurn),
+            "prev" | "<" => Some(Event::PrevTurn),
+            "next" | ">" => Some(Event::NextTurn),
+            "last" | ">>" => Some(Event::LastTurn),
+            _ => None,
+        };
+        if let Some(event) = history {
+            self.emulator.handle_event(event);
+            return Response::Print(self.board());
+        }
+
+        match command {
+            "quit" | "exit" | "q" => Response::Quit,
+            "help" | "?" => Response::Print(String::from(HELP)),
+            "board" => Response::Print(self.board()),
+            "flip" => {
+                self.flipped = !self.flipped;
+                Response::Print(self.board())
+            }
+            "side" if self.link.is_some() => {
+                Response::Error(String::from("sides are fixed online"))
+            }
+            "side" => {
+                let side = match rest {
+                    Some("w") => Side::White,
+                    Some("b") => Side::Black,
+                    _ => return Response::Error(String::from("side takes w or b")),
+                };
+                self.emulator.set_to_move(side);
+                Response::Print(self.board())
+            }
+            "pieces" => {
+                let mut ans = String::new();
+                for piece in self.emulator.pieces() {
+                    _ = writeln!(
+                        ans,
+                        "{:>2} {} {},{}:{}",
+                        piece.id(),
+                        piece_label(piece, false),
+                        piece.x(),
+                        piece.y(),
+                        piece.angle()
+                    );
+                }
+                ans.pop();
+                Response::Print(ans)
+            }
+            "position" => Response::Print(notation::write_position(
+                self.emulator.turns().working_board_ref(),
+                self.emulator.to_move(),
+            )),
+            "go" => self.engine_move(),
+            _ => self.play(line),
+        }
+    }
+
+    fn engine_move(&mut self) -> Response {
+        let side = self.emulator.to_move();
+        let before = self.emulator.turns().working_board_ref().clone();
+        let limits = self.limits.clone();
+        let played = match self.link.as_mut() {
+            Some(link) => match link.play_engine_move(&mut self.emulator, &limits) {
+                Ok(played) => played,
+                Err(NetError::Illegal(e)) => return Response::Error(e.to_string()),
+                Err(e) => return self.go_offline(e),
+            },
+            None => self.emulator.make_best_move_with(&limits),
+        };
+        match played {
+            Some(move_) => Response::Print(format!(
+                "{} played {} ({side:?})\n{}",
+                self.emulator
+                    .engine()
+                    .map_or("engine", |engine| engine.name()),
+                notation::write_move(&move_, &before),
+                self.board()
+            )),
+            None => Response::Error(format!("{side:?} has no moves")),
+        }
+    }
+
+    fn play(&mut self, line: &str) -> Response {
+        let actions = match notation::parse_actions(line.trim()) {
+            Ok(actions) => actions,
+            Err(e) => return Response::Error(format!("{e}, try help")),
+        };
+        let played = match self.link.as_mut() {
+            Some(link) => link.play(&mut self.emulator, &actions),
+            None => self
+                .emulator
+                .play_actions(&actions)
+                .map_err(NetError::Illegal),
+        };
+        match played {
+            Ok(()) => Response::Print(self.board()),
+            Err(NetError::Illegal(e)) => Response::Error(e.to_string()),
+            Err(e) => self.go_offline(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use std::thread;
+
+    use super::{Response, Terminal, arrow, render};
+    use rotchess_core::{
+        notation,
+        piece::{Pieces, Side},
+        search::SearchLimits,
+    };
+    use rotchess_event_api::RotchessEmulator;
+    use rotchess_net::{Host, Link, Rules};
+
+    fn terminal() -> Terminal {
+        Terminal::with(
+            RotchessEmulator::with(Pieces::standard_board()),
+            SearchLimits::default().with_depth(1),
+        )
+    }
+
+    #[test]
+    fn arrows_follow_the_angle() {
+        assert_eq!(arrow(0.), '↑');
+        assert_eq!(arrow(PI), '↓');
+        assert_eq!(arrow(PI / 2.), '←');
+        assert_eq!(arrow(-PI / 4.), '↗');
+        assert_eq!(arrow(2. * PI + 0.1), '↑');
+    }
+
+    #[test]
+    fn the_board_is_drawn_from_either_side() {
+        let pieces = Pieces::standard_board();
+        let board = render(pieces.board_pieces(), false);
+        let lines: Vec<_> = board.lines().collect();
+        assert_eq!(lines[0], "     0   1   2   3   4   5   6   7 ");
+        assert!(
+            lines[1].starts_with("0  bR↓ bN↓ bB↓ bQ↓ bK↓"),
+            "{}",
+            lines[1]
+        );
+        assert!(lines[4].starts_with("3   .   . "), "{}", lines[4]);
+        assert!(
+            lines[8].starts_with("7  wR↑ wN↑ wB↑ wQ↑ wK↑"),
+            "{}",
+            lines[8]
+        );
+
+        let flipped = render(pieces.board_pieces(), true);
+        let lines: Vec<_> = flipped.lines().collect();
+        assert!(
+            lines[1].starts_with("7  wR↓ wN↓ wB↓ wK↓ wQ↓"),
+            "{}",
+            lines[1]
+        );
+    }
+
+    #[test]
+    fn turns_are_played_and_checked() {
+        let mut term = terminal();
+        let Response::Print(board) = term.handle("4.5,6.5>4.5,4.5") else {
+            panic!("the pawn can move two");
+        };
+        assert!(board.contains("turn 1 of 1, Black to move"), "{board}");
+        assert!(board.lines().nth(5).unwrap().contains("wP↑"), "{board}");
+
+        // white can't move twice, and pawns can't move three.
+        assert!(matches!(term.handle("3.5,6.5>3.5,5.5"), Response::Error(_)));
+        assert!(matches!(term.handle("3.5,1.5>3.5,4.5"), Response::Error(_)));
+        assert!(matches!(term.handle("nonsense"), Response::Error(_)));
+
+        let Response::Print(board) = term.handle("3.5,1.5:0.7853982") else {
+            panic!("black can rotate");
+        };
+        assert!(board.lines().nth(2).unwrap().contains("bP↖"), "{board}");
+    }
+
+    #[test]
+    fn history_and_the_engine() {
+        let mut term = terminal();
+        let Response::Print(played) = term.handle("go") else {
+            panic!("white has moves");
+        };
+        assert!(played.contains("played"), "{played}");
+        term.handle("go");
+        assert!(term.board().contains("turn 2 of 2"));
+
+        term.handle("first");
+        assert!(term.board().contains("turn 0 of 2"));
+        term.handle(">");
+        assert!(term.board().contains("turn 1 of 2"));
+        term.handle(">>");
+        assert!(term.board().contains("turn 2 of 2"));
+
+        term.handle("side b");
+        assert!(term.board().contains("Black to move"));
+        assert!(matches!(term.handle("side x"), Response::Error(_)));
+        assert_eq!(term.handle("quit"), Response::Quit);
+    }
+
+    #[test]
+    fn online_turns_are_sent_and_waited_for() {
+        let host = Host::listen("127.0.0.1:0", Side::White, Rules::default()).unwrap();
+        let key = host.key();
+        let black = thread::spawn(move || {
+            let mut link = Link::join(&key, None).unwrap();
+            let mut game = link.new_emulator();
+            let played = link.wait(&mut game).unwrap();
+            let reply = notation::parse_actions("3.5,1.5>3.5,3.5").unwrap();
+            link.play(&mut game, &reply).unwrap();
+            // stay until white has heard.
+            _ = link.wait(&mut game);
+            played
+        });
+        let link = host.accept().unwrap();
+        let mut term = Terminal::with(link.new_emulator(), SearchLimits::default().with_depth(1))
+            .with_link(link);
+
+        assert!(term.board().contains("we're White"));
+        assert!(!term.board().contains("watching"));
+        assert!(!term.waiting());
+        assert!(matches!(term.handle("3.5,1.5>3.5,3.5"), Response::Error(_)));
+        assert!(matches!(term.handle("side b"), Response::Error(_)));
+        assert!(matches!(term.handle("4.5,6.5>4.5,4.5"), Response::Print(_)));
+
+        assert!(term.waiting());
+        let Response::Print(played) = term.wait() else {
+            panic!("black replies");
+        };
+        assert!(
+            played.starts_with("Black played 3.5,1.5>3.5,3.5"),
+            "{played}"
+        );
+        assert!(!term.waiting());
+        assert!(matches!(term.handle("go"), Response::Print(_)));
+        assert_eq!(
+            notation::write_actions(&black.join().unwrap()[0]),
+            "4.5,6.5>4.5,4.5"
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Drive `rotchess-engine` through the event api's protocol client.
+
+use std::{
+    io,
+    process::Command,
+    sync::{Arc, atomic::AtomicBool},
+    time::{Duration, Instant},
+};
+
+use rotchess_core::{
+    engine::Engine,
+    piece::{Pieces, Side},
+    search::SearchLimits,
+    turn::Turns,
+};
+use rotchess_event_api::{RotchessEmulator, external::ExternalEngine};
+
+fn spawn(kind: &str) -> ExternalEngine {
+    ExternalEngine::spawn(
+        Command::new(env!("CARGO_BIN_EXE_rotchess-engine")).arg(format!("--engine={kind}")),
+    )
+    .expect("engine starts")
+}
+
+#[test]
+fn external_engines_play_legal_moves() {
+    let mut engine = spawn("alpha-beta");
+    assert_eq!(engine.name(), "alpha-beta");
+
+    let mut turns = Turns::with(Pieces::standard_board());
+    for _ in 0..2 {
+        let move_ = engine
+            .choose_move(&mut turns, &SearchLimits::infinite().with_depth(1))
+            .expect("the engine answers with a legal move");
+        turns.play(&move_);
+    }
+    assert_eq!(turns.to_move(), Side::White);
+    let info = engine.last_info().expect("alpha-beta reports progress");
+    assert_eq!(info.depth, 1);
+}
+
+#[test]
+fn external_engines_can_be_stopped() {
+    let mut engine = spawn("alpha-beta");
+    let stop = Arc::new(AtomicBool::new(true));
+    let mut turns = Turns::with(Pieces::standard_board());
+    let move_ = engine.choose_move(&mut turns, &SearchLimits::infinite().with_stop(stop));
+    assert!(move_.is_some());
+}
+
+#[test]
+fn emulators_take_external_engines() {
+    let mut emulator = RotchessEmulator::with(Pieces::standard_board());
+    emulator.set_engine(Box::new(spawn("random")));
+    emulator.make_best_move();
+    assert_eq!(emulator.to_move(), Side::Black);
+}
+
+#[cfg(unix)]
+#[test]
+fn silent_engines_time_out() {
+    let started = Instant::now();
+    let Err(e) = ExternalEngine::spawn_with_timeout(
+        Command::new("sleep").arg("30"),
+        Duration::from_millis(200),
+    ) else {
+        panic!("sleep doesn't speak the protocol");
+    };
+    assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
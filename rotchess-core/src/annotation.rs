@@ -0,0 +1,175 @@
+//! Arrows and circles drawn on the board to point things out.
+//!
+//! They don't change the game. [`Turns`](crate::turn::Turns) keeps them per turn and
+//! [records](crate::record) save them. An arrow is written like `G3.5,6.5>3.5,4.5`
+//! and a circle like `R3.5,3.5`, the letter being the color.
+
+use std::{fmt, str::FromStr};
+
+use crate::notation::{self, NotationError};
+
+/// What color a mark is. Frontends usually pick one by the modifier keys held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MarkColor {
+    Green,
+    Red,
+    Blue,
+    Yellow,
+}
+
+impl MarkColor {
+    pub fn letter(self) -> char {
+        match self {
+            MarkColor::Green => 'G',
+            MarkColor::Red => 'R',
+            MarkColor::Blue => 'B',
+            MarkColor::Yellow => 'Y',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter {
+            'G' => Some(MarkColor::Green),
+            'R' => Some(MarkColor::Red),
+            'B' => Some(MarkColor::Blue),
+            'Y' => Some(MarkColor::Yellow),
+            _ => None,
+        }
+    }
+}
+
+/// A mark on the board, in rotchess units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Annotation {
+    Arrow {
+        from: (f32, f32),
+        to: (f32, f32),
+        color: MarkColor,
+    },
+    Circle {
+        at: (f32, f32),
+        color: MarkColor,
+    },
+}
+
+impl Annotation {
+    pub fn color(&self) -> MarkColor {
+        match *self {
+            Annotation::Arrow { color, .. } | Annotation::Circle { color, .. } => color,
+        }
+    }
+
+    /// Whether `other` marks the same thing, whatever its color.
+    pub fn same_place(&self, other: &Annotation) -> bool {
+        match (*self, *other) {
+            (Annotation::Arrow { from, to, .. }, Annotation::Arrow { from: f, to: t, .. }) => {
+                (from, to) == (f, t)
+            }
+            (Annotation::Circle { at, .. }, Annotation::Circle { at: a, .. }) => at == a,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let letter = self.color().letter();
+        match self {
+            Annotation::Arrow { from, to, .. } => {
+                write!(f, "{letter}{},{}>{},{}", from.0, from.1, to.0, to.1)
+            }
+            Annotation::Circle { at, .. } => write!(f, "{letter}{},{}", at.0, at.1),
+        }
+    }
+}
+
+impl FromStr for Annotation {
+    type Err = NotationError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let malformed = || NotationError::Malformed(text.to_string());
+        let mut chars = text.chars();
+        let color = chars
+            .next()
+            .and_then(MarkColor::from_letter)
+            .ok_or_else(malformed)?;
+        let points = chars.as_str();
+        Ok(match points.split_once('>') {
+            Some((from, to)) => Annotation::Arrow {
+                from: notation::parse_point(from)?,
+                to: notation::parse_point(to)?,
+                color,
+            },
+            None => Annotation::Circle {
+                at: notation::parse_point(points)?,
+                color,
+            },
+        })
+    }
+}
+
+/// Annotations separated by spaces, like `G3.5,6.5>3.5,4.5 R3.5,3.5`.
+pub fn write_annotations(annotations: &[Annotation]) -> String {
+    annotations
+        .iter()
+        .map(Annotation::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn parse_annotations(text: &str) -> Result<Vec<Annotation>, NotationError> {
+    text.split_whitespace().map(str::parse).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Annotation, MarkColor, parse_annotations, write_annotations};
+    use crate::{piece::Pieces, turn::Turns};
+
+    #[test]
+    fn annotations_round_trip() {
+        let annotations = vec![
+            Annotation::Arrow {
+                from: (3.5, 6.5),
+                to: (0.1, 4.25),
+                color: MarkColor::Green,
+            },
+            Annotation::Circle {
+                at: (3.5, 3.5),
+                color: MarkColor::Yellow,
+            },
+        ];
+        let text = write_annotations(&annotations);
+        assert_eq!(text, "G3.5,6.5>0.1,4.25 Y3.5,3.5");
+        assert_eq!(parse_annotations(&text).unwrap(), annotations);
+        assert!(parse_annotations("X3.5,3.5").is_err());
+    }
+
+    #[test]
+    fn toggling_recolors_then_rubs_out() {
+        let mut turns = Turns::with(Pieces::standard_board());
+        let green = Annotation::Circle {
+            at: (3.5, 3.5),
+            color: MarkColor::Green,
+        };
+        let red = Annotation::Circle {
+            at: (3.5, 3.5),
+            color: MarkColor::Red,
+        };
+        assert!(turns.toggle_annotation(green));
+        assert!(turns.toggle_annotation(red));
+        assert_eq!(turns.annotations(), [red]);
+        assert!(!turns.toggle_annotation(red));
+        assert!(turns.annotations().is_empty());
+
+        // each turn has its own, and playing over a turn rubs its out.
+        turns.toggle_annotation(green);
+        turns.save_turn();
+        assert!(turns.annotations().is_empty());
+        turns.toggle_annotation(red);
+        turns.first();
+        assert_eq!(turns.annotations(), [green]);
+        turns.save_turn();
+        assert!(turns.annotations().is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+//! Chess960 start positions.
+//!
+//! There are 960 back rows with the bishops on opposite colors and the king
+//! somewhere between the rooks. Each has a standard number in `0..960`
+//! (Scharnagl's numbering), with 518 being the usual RNBQKBNR. Numbers make games
+//! reproducible: "Chess960 #518" is a whole setup.
+
+use crate::{piece::PieceKind, piece::Pieces, rng::Rng};
+
+/// How many Chess960 start positions there are.
+pub const POSITIONS: u16 = 960;
+
+/// The number of the usual chess setup.
+pub const STANDARD: u16 = 518;
+
+/// Where the knights go among the five files left after placing the bishops and
+/// queen, for each remainder in `0..10`.
+const KNIGHTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+/// The back row of Chess960 position `number`, from the a-file to the h-file.
+///
+/// `None` if `number` isn't below [`POSITIONS`].
+pub fn back_rank(number: u16) -> Option<[PieceKind; 8]> {
+    if number >= POSITIONS {
+        return None;
+    }
+    let mut n = number as usize;
+    let mut files: [Option<PieceKind>; 8] = [None; 8];
+
+    // light squared bishop on b, d, f or h. then the dark squared one on a, c, e or g.
+    files[n % 4 * 2 + 1] = Some(PieceKind::Bishop);
+    n /= 4;
+    files[n % 4 * 2] = Some(PieceKind::Bishop);
+    n /= 4;
+
+    /// Put `kind` on the `i`th empty file.
+    fn place(files: &mut [Option<PieceKind>; 8], i: usize, kind: PieceKind) {
+        let file = (0..8)
+            .filter(|&file| files[file].is_none())
+            .nth(i)
+            .expect("there are enough empty files");
+        files[file] = Some(kind);
+    }
+
+    place(&mut files, n % 6, PieceKind::Queen);
+    n /= 6;
+    // place the second knight first, so that placing the first doesn't shift it.
+    let (first, second) = KNIGHTS[n];
+    place(&mut files, second, PieceKind::Knight);
+    place(&mut files, first, PieceKind::Knight);
+
+    // the last three go rook, king, rook.
+    for kind in [PieceKind::Rook, PieceKind::King, PieceKind::Rook] {
+        place(&mut files, 0, kind);
+    }
+    Some(files.map(|kind| kind.expect("every file is filled")))
+}
+
+/// Whether `order` is a Chess960 back row: the usual pieces, with the bishops on
+/// opposite colors and the king between the rooks.
+pub fn is_valid(order: &[PieceKind; 8]) -> bool {
+    let files_of = |kind| {
+        (0..8)
+            .filter(|&file| order[file] == kind)
+            .collect::<Vec<_>>()
+    };
+    let counts_match = [
+        (PieceKind::Rook, 2),
+        (PieceKind::Knight, 2),
+        (PieceKind::Bishop, 2),
+        (PieceKind::Queen, 1),
+        (PieceKind::King, 1),
+    ]
+    .into_iter()
+    .all(|(kind, count)| files_of(kind).len() == count);
+    if !counts_match {
+        return false;
+    }
+
+    let bishops = files_of(PieceKind::Bishop);
+    let rooks = files_of(PieceKind::Rook);
+    let king = files_of(PieceKind::King)[0];
+    bishops[0] % 2 != bishops[1] % 2 && rooks[0] < king && king < rooks[1]
+}
+
+/// The number of Chess960 back row `order`, or `None` if it isn't [valid](is_valid).
+pub fn number(order: &[PieceKind; 8]) -> Option<u16> {
+    if !is_valid(order) {
+        return None;
+    }
+    (0..POSITIONS).find(|&n| back_rank(n).as_ref() == Some(order))
+}
+
+/// Chess960 setups.
+impl Pieces {
+    /// Create a board with Chess960 position `number`. See the [module docs](self).
+    ///
+    /// Panics if `number` isn't below [`POSITIONS`].
+    pub fn chess960_board(number: u16) -> Self {
+        let order = back_rank(number).expect("chess960 positions are numbered 0..960");
+        Self::with_back_rank(order)
+    }
+
+    /// Create a board with a Chess960 position picked by `seed`. Returns its number too.
+    ///
+    /// Equal seeds pick equal positions.
+    pub fn seeded_chess960_board(seed: u64) -> (Self, u16) {
+        let number = Rng::new(seed).below(POSITIONS as usize) as u16;
+        (Self::chess960_board(number), number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{POSITIONS, STANDARD, back_rank, is_valid, number};
+    use crate::piece::{PieceKind, Pieces};
+
+    #[test]
+    fn numbers_match_the_standard_table() {
+        use PieceKind::*;
+        assert_eq!(
+            back_rank(STANDARD),
+            Some([Rook, Knight, Bishop, Queen, King, Bishop, Knight, Rook])
+        );
+        assert_eq!(
+            back_rank(0),
+            Some([Bishop, Bishop, Queen, Knight, Knight, Rook, King, Rook])
+        );
+        assert_eq!(
+            back_rank(959),
+            Some([Rook, King, Rook, Knight, Knight, Queen, Bishop, Bishop])
+        );
+        assert_eq!(back_rank(POSITIONS), None);
+    }
+
+    #[test]
+    fn every_number_is_valid_and_unique() {
+        for n in 0..POSITIONS {
+            let order = back_rank(n).unwrap();
+            assert!(is_valid(&order), "{n}: {order:?}");
+            assert_eq!(number(&order), Some(n));
+        }
+    }
+
+    #[test]
+    fn invalid_shuffles_are_rejected() {
+        use PieceKind::*;
+        // both bishops on dark squares.
+        assert!(!is_valid(&[
+            Bishop, Rook, Bishop, Queen, King, Knight, Knight, Rook
+        ]));
+        // king outside the rooks.
+        assert!(!is_valid(&[
+            King, Rook, Bishop, Queen, Bishop, Knight, Knight, Rook
+        ]));
+        // too many queens.
+        assert!(!is_valid(&[
+            Rook, Knight, Bishop, Queen, King, Bishop, Queen, Rook
+        ]));
+    }
+
+    #[test]
+    fn seeds_are_reproducible() {
+        let (a, n) = Pieces::seeded_chess960_board(9);
+        let (b, m) = Pieces::seeded_chess960_board(9);
+        assert_eq!(n, m);
+        assert_eq!(a.position_hash(), b.position_hash());
+        assert_eq!(
+            Pieces::chess960_board(STANDARD).position_hash(),
+            Pieces::standard_board().position_hash()
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! Pluggable move choosers.
+//!
+//! An [`Engine`] looks at a position and picks a move for the side to move. The
+//! strongest is [`AlphaBetaEngine`], which wraps [`Turns::search`]. The weaker ones
+//! are useful as difficulty levels and as sparring partners when testing.
+
+use crate::{
+    rng::Rng,
+    search::SearchLimits,
+    transposition::TranspositionTable,
+    turn::{Move, Score, Turns},
+};
+
+/// Something that can choose a rotchess move.
+pub trait Engine: Send {
+    /// A short human-readable name.
+    fn name(&self) -> &str;
+
+    /// Choose a move for the side to move in `turns`, or `None` if there are no moves.
+    ///
+    /// `turns` may be used as scratch space, but must be left as it was found.
+    /// Engines that search should respect `limits`; others may ignore them.
+    fn choose_move(&mut self, turns: &mut Turns, limits: &SearchLimits) -> Option<Move>;
+}
+
+/// Plays uniformly random moves.
+pub struct RandomEngine {
+    rng: Rng,
+}
+
+impl RandomEngine {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Engine for RandomEngine {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn choose_move(&mut self, turns: &mut Turns, _limits: &SearchLimits) -> Option<Move> {
+        let mut moves = turns.all_moves();
+        if moves.is_empty() {
+            return None;
+        }
+        let i = self.rng.below(moves.len());
+        Some(moves.swap_remove(i))
+    }
+}
+
+/// Looks one ply ahead and plays whatever evaluates best right now.
+///
+/// Material dominates the evaluation, so in practice this grabs the most valuable
+/// capture it can find, with no thought for recaptures.
+#[derive(Default)]
+pub struct GreedyEngine;
+
+impl Engine for GreedyEngine {
+    fn name(&self) -> &str {
+        "greedy"
+    }
+
+    fn choose_move(&mut self, turns: &mut Turns, _limits: &SearchLimits) -> Option<Move> {
+        let mut best: Option<(Move, Score)> = None;
+        for move_ in turns.all_moves() {
+            turns.apply(&move_);
+            let score = -turns.eval();
+            turns.unapply(&move_);
+
+            if best
+                .as_ref()
+                .is_none_or(|(_, best_score)| score > *best_score)
+            {
+                best = Some((move_, score));
+            }
+        }
+        best.map(|(move_, _)| move_)
+    }
+}
+
+/// The full iterative deepening alpha-beta search.
+///
+/// Keeps its transposition table between moves.
+#[derive(Default)]
+pub struct AlphaBetaEngine {
+    table: TranspositionTable,
+}
+
+impl Engine for AlphaBetaEngine {
+    fn name(&self) -> &str {
+        "alpha-beta"
+    }
+
+    fn choose_move(&mut self, turns: &mut Turns, limits: &SearchLimits) -> Option<Move> {
+        turns
+            .search_with_table(limits, Some(&mut self.table))
+            .best_move
+    }
+}
+
+/// The engines we ship, from weakest to strongest. Handy for difficulty pickers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EngineKind {
+    Random,
+    Greedy,
+    AlphaBeta,
+}
+
+impl EngineKind {
+    pub const ALL: [EngineKind; 3] = [
+        EngineKind::Random,
+        EngineKind::Greedy,
+        EngineKind::AlphaBeta,
+    ];
+
+    /// The name of the engines this builds. Matches [`Engine::name`].
+    pub fn name(&self) -> &'static str {
+        match self {
+            EngineKind::Random => "random",
+            EngineKind::Greedy => "greedy",
+            EngineKind::AlphaBeta => "alpha-beta",
+        }
+    }
+
+    /// Look up a kind by its [name](Self::name).
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Build a fresh engine. `seed` is used by engines that need randomness.
+    pub fn build(&self, seed: u64) -> Box<dyn Engine> {
+        match self {
+            EngineKind::Random => Box::new(RandomEngine::new(seed)),
+            EngineKind::Greedy => Box::new(GreedyEngine),
+            EngineKind::AlphaBeta => Box::new(AlphaBetaEngine::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::EngineKind;
+    use crate::{
+        piece::{Piece, PieceKind, Pieces, Side},
+        search::SearchLimits,
+        turn::Turns,
+    };
+
+    #[test]
+    fn every_engine_answers_and_restores() {
+        for kind in EngineKind::ALL {
+            let mut turns = Turns::with(Pieces::standard_board());
+            let hash = turns.position_hash();
+            let move_ = kind
+                .build(7)
+                .choose_move(&mut turns, &SearchLimits::default().with_depth(1));
+            assert!(move_.is_some(), "{} found no move", kind.name());
+            assert_eq!(hash, turns.position_hash(), "{} moved pieces", kind.name());
+        }
+    }
+
+    #[test]
+    fn greedy_takes_the_queen() {
+        const QUEEN: usize = 2;
+        let mut turns = Turns::with(Pieces::with(vec![
+            Piece::from_tile(0, (7, 7), 0., Side::White, PieceKind::King),
+            Piece::from_tile(1, (3, 7), 0., Side::White, PieceKind::Rook),
+            Piece::from_tile(QUEEN, (3, 2), -PI, Side::Black, PieceKind::Queen),
+            Piece::from_tile(3, (0, 0), -PI, Side::Black, PieceKind::King),
+        ]));
+        let move_ = EngineKind::Greedy
+            .build(0)
+            .choose_move(&mut turns, &SearchLimits::default())
+            .expect("white has moves");
+        assert_eq!(move_.travel.captures(), &[QUEEN]);
+    }
+
+    #[test]
+    fn random_is_reproducible() {
+        let limits = SearchLimits::default();
+        let mut turns = Turns::with(Pieces::standard_board());
+        let a = EngineKind::Random
+            .build(42)
+            .choose_move(&mut turns, &limits);
+        let b = EngineKind::Random
+            .build(42)
+            .choose_move(&mut turns, &limits);
+        assert_eq!(a, b);
+    }
+}
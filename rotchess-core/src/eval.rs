@@ -0,0 +1,310 @@
+//! Static evaluation of positions.
+//!
+//! The evaluation is a weighted sum of terms, each computed as white's advantage
+//! over black and then flipped to the side to move. The weights live in
+//! [`EvalWeights`] so they can be tuned without touching the search, and
+//! [`Turns::eval_breakdown`] reports every term separately for debugging.
+//!
+//! Every term is read straight off the pieces, as the search evaluates at every
+//! leaf. The breakdown also counts mobility and hanging pieces, which need every
+//! piece's travel points worked out again; they aren't weighted or part of the
+//! score, and are only there to look at.
+
+use std::{
+    collections::HashSet,
+    f32::consts::{PI, TAU},
+    fmt,
+};
+
+use crate::{
+    piece::{PieceId, PieceKind, Side, TravelKind},
+    turn::{Score, Turns},
+};
+
+/// Center of the board in rotchess units.
+const CENTER: (f32, f32) = (4.0, 4.0);
+
+/// Pieces closer than this to their king count as guarding it.
+const KING_GUARD_DISTANCE: f32 = 1.5;
+
+/// Angles closer than this (in radians) count as equal.
+const ANGLE_EPSILON: f32 = 1e-3;
+
+/// How much each evaluation term counts.
+///
+/// Each term is measured in its own natural unit (see the field docs); the weight
+/// converts it into [`Score`]. A weight of zero turns the term off, and also skips
+/// computing it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalWeights {
+    /// Per point of [`PieceKind::value`].
+    pub material: Score,
+    /// Per piece still facing the way it started, as a penalty. Pieces that have
+    /// been rotated earn the same amount as a bonus.
+    pub unrotated: Score,
+    /// Per rotchess-unit that a piece is closer than 5 to the center of the board.
+    pub center: Score,
+    /// Per friendly piece within [`KING_GUARD_DISTANCE`] of its king.
+    pub king_safety: Score,
+    /// Per rotchess-unit of [`forward_distance`](crate::piece::Piece::forward_distance)
+    /// of each pawn.
+    pub pawn_advancement: Score,
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        Self {
+            material: 100.,
+            unrotated: 1.,
+            center: 1.,
+            king_safety: 5.,
+            pawn_advancement: 2.,
+        }
+    }
+}
+
+/// The contribution of each term to an evaluation.
+///
+/// Like the evaluation itself, each term is from the perspective of the side to
+/// move, with the weights already applied. `mobility` and `hanging` are
+/// diagnostics: unweighted, and left out of the [`total`](Self::total).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvalBreakdown {
+    pub material: Score,
+    pub unrotated: Score,
+    pub center: Score,
+    pub king_safety: Score,
+    pub pawn_advancement: Score,
+    /// Points pieces could travel to, ignoring pathing.
+    pub mobility: Score,
+    /// [`PieceKind::value`] of pieces that are attacked and not defended, as a
+    /// penalty. Kings don't count; threats to them are for the search to see.
+    pub hanging: Score,
+}
+
+impl EvalBreakdown {
+    /// The evaluation the search sees: the sum of the weighted terms.
+    pub fn total(&self) -> Score {
+        self.material + self.unrotated + self.center + self.king_safety + self.pawn_advancement
+    }
+
+    fn terms(&self) -> [(&'static str, Score); 5] {
+        [
+            ("material", self.material),
+            ("unrotated", self.unrotated),
+            ("center", self.center),
+            ("king safety", self.king_safety),
+            ("pawn advancement", self.pawn_advancement),
+        ]
+    }
+}
+
+impl fmt::Display for EvalBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, score) in self.terms() {
+            writeln!(f, "{name:>16}: {score:>9.2}")?;
+        }
+        writeln!(f, "{:>16}: {:>9.2}", "total", self.total())?;
+        writeln!(f, "{:>16}: {:>9.2}", "(mobility)", self.mobility)?;
+        write!(f, "{:>16}: {:>9.2}", "(hanging)", self.hanging)
+    }
+}
+
+/// +1 for white, -1 for black.
+fn sign(side: Side) -> Score {
+    match side {
+        Side::Black => -1.,
+        Side::White => 1.,
+    }
+}
+
+/// Whether a piece of `side` at `angle` still faces the way it did at the start.
+fn is_unrotated(side: Side, angle: f32) -> bool {
+    let start = match side {
+        Side::Black => PI,
+        Side::White => 0.,
+    };
+    let diff = (angle - start).rem_euclid(TAU);
+    diff < ANGLE_EPSILON || TAU - diff < ANGLE_EPSILON
+}
+
+/// Evaluation code.
+impl Turns {
+    /// Returns the score, statically evaluated at the current position, for the search.
+    ///
+    /// A float with more positive favoring the current player from `self.to_move`, 0 even.
+    /// The same as [`eval_breakdown`](Self::eval_breakdown)'s total.
+    pub(crate) fn eval(&self) -> Score {
+        let mut ans = EvalBreakdown::default();
+        self.add_piece_terms(&mut ans);
+        ans.total()
+    }
+
+    /// Add the terms read straight off the pieces.
+    fn add_piece_terms(&self, ans: &mut EvalBreakdown) {
+        let weights = self.eval_weights();
+        let mult = sign(self.to_move());
+        let board = self.working_board_ref();
+
+        for piece in board.board_pieces() {
+            let s = mult * sign(piece.side());
+
+            ans.material += s * piece.kind().value() * weights.material;
+
+            ans.unrotated += s
+                * weights.unrotated
+                * if is_unrotated(piece.side(), piece.angle()) {
+                    -1.
+                } else {
+                    1.
+                };
+
+            ans.center += s
+                * weights.center
+                * (5.0
+                    - Score::sqrt((piece.x() - CENTER.0).powi(2) + (piece.y() - CENTER.1).powi(2)));
+
+            if weights.pawn_advancement != 0. && piece.kind() == PieceKind::Pawn {
+                ans.pawn_advancement += s * weights.pawn_advancement * piece.forward_distance();
+            }
+
+            if weights.king_safety != 0. && piece.kind() == PieceKind::King {
+                let guards = board
+                    .board_pieces()
+                    .filter(|other| other.side() == piece.side() && other.id() != piece.id())
+                    .filter(|other| {
+                        (other.x() - piece.x()).powi(2) + (other.y() - piece.y()).powi(2)
+                            < KING_GUARD_DISTANCE.powi(2)
+                    })
+                    .count();
+                ans.king_safety += s * weights.king_safety * guards as Score;
+            }
+        }
+    }
+
+    /// Evaluate the current position, reporting each term separately.
+    ///
+    /// Weighted by [`Self::eval_weights`], with mobility and hanging pieces counted as
+    /// well. Reinitializes the working board's auxiliary data to count them.
+    pub fn eval_breakdown(&mut self) -> EvalBreakdown {
+        let mut ans = EvalBreakdown::default();
+        self.add_piece_terms(&mut ans);
+
+        let mult = sign(self.to_move());
+        self.working_board_mut().init_all_auxiliary_data();
+        let board = self.working_board_ref();
+
+        for piece in board.board_pieces() {
+            ans.mobility +=
+                mult * sign(piece.side()) * piece.travel_points_unchecked().count() as Score;
+        }
+
+        let mut attacked: HashSet<PieceId> = HashSet::new();
+        let mut defended: HashSet<PieceId> = HashSet::new();
+        for piece in board.board_pieces() {
+            for (tvk, x, y) in piece.travel_points_unchecked() {
+                if tvk != TravelKind::Capture {
+                    continue;
+                }
+                if let Some(travel) = board.travelable(piece, x, y, tvk) {
+                    attacked.extend(travel.captures());
+                }
+                // we can't "capture" our own pieces, so defense ignores pathing.
+                defended.extend(
+                    board
+                        .board_pieces()
+                        .filter(|other| other.side() == piece.side() && other.id() != piece.id())
+                        .filter(|other| other.collidepiece(x, y))
+                        .map(|other| other.id()),
+                );
+            }
+        }
+
+        for id in attacked.difference(&defended) {
+            let piece = board.get(*id).expect("attacked pieces are on the board");
+            if piece.kind() == PieceKind::King {
+                continue;
+            }
+            ans.hanging -= mult * sign(piece.side()) * piece.kind().value();
+        }
+
+        ans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::{EvalWeights, is_unrotated};
+    use crate::{
+        piece::{Piece, PieceKind, Pieces, Side},
+        turn::Turns,
+    };
+
+    #[test]
+    fn unrotated_tolerates_drift() {
+        assert!(is_unrotated(Side::White, 0.));
+        assert!(is_unrotated(Side::White, 1e-5));
+        assert!(is_unrotated(Side::White, 2. * PI));
+        assert!(is_unrotated(Side::Black, -PI));
+        assert!(is_unrotated(Side::Black, PI));
+        assert!(!is_unrotated(Side::White, PI / 8.));
+        assert!(!is_unrotated(Side::White, PI));
+    }
+
+    #[test]
+    fn standard_board_is_even() {
+        let mut turns = Turns::with(Pieces::standard_board());
+        let breakdown = turns.eval_breakdown();
+        assert!(breakdown.total().abs() < 1e-3, "{breakdown}");
+    }
+
+    #[test]
+    fn terms_match_weights() {
+        let mut turns = Turns::with(Pieces::standard_board());
+        turns.set_eval_weights(EvalWeights {
+            material: 0.,
+            unrotated: 0.,
+            center: 0.,
+            king_safety: 0.,
+            pawn_advancement: 1.,
+        });
+        turns
+            .working_board_mut()
+            .get_mut(1)
+            .expect("white a-pawn")
+            .set_y(4.5);
+        let breakdown = turns.eval_breakdown();
+        assert_eq!(breakdown.total(), breakdown.pawn_advancement);
+        assert!(
+            (breakdown.pawn_advancement - 2.).abs() < 1e-4,
+            "{breakdown}"
+        );
+    }
+
+    #[test]
+    fn breakdown_totals_to_the_search_eval() {
+        let mut turns = Turns::with(Pieces::with(vec![
+            Piece::from_tile(0, (7, 7), 0., Side::White, PieceKind::King),
+            Piece::from_tile(1, (3, 7), 0., Side::White, PieceKind::Rook),
+            Piece::from_tile(2, (3, 2), -PI, Side::Black, PieceKind::Knight),
+            Piece::from_tile(3, (0, 0), -PI, Side::Black, PieceKind::King),
+        ]));
+        let breakdown = turns.eval_breakdown();
+        assert!(breakdown.mobility != 0. && breakdown.hanging != 0.);
+        assert_eq!(turns.eval(), breakdown.total());
+    }
+
+    #[test]
+    fn hanging_piece_is_penalized() {
+        let mut turns = Turns::with(Pieces::with(vec![
+            Piece::from_tile(0, (7, 7), 0., Side::White, PieceKind::King),
+            Piece::from_tile(1, (3, 7), 0., Side::White, PieceKind::Rook),
+            Piece::from_tile(2, (3, 2), -PI, Side::Black, PieceKind::Knight),
+            Piece::from_tile(3, (0, 0), -PI, Side::Black, PieceKind::King),
+        ]));
+        let breakdown = turns.eval_breakdown();
+        assert_eq!(breakdown.hanging, PieceKind::Knight.value());
+    }
+}
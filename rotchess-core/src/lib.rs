@@ -19,6 +19,17 @@
 //!   they're measured in radians, 0 at the positive x-axis, increasing anticlockwise.
 //! - rotchess-unit: an eighth of the side length of the board.
 
+pub mod annotation;
+pub mod chess960;
+pub mod engine;
+pub mod eval;
 pub mod floating_drift;
+pub mod notation;
+pub mod perft;
 pub mod piece;
+pub mod protocol;
+pub mod record;
+pub mod rng;
+pub mod search;
+pub mod transposition;
 pub mod turn;
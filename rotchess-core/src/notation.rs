@@ -0,0 +1,423 @@
+//! Text notation for positions and moves.
+//!
+//! Pieces don't live on a grid, so this is nothing like algebraic notation. Every
+//! coordinate is written out in full, in rotchess-units, with Rust's shortest
+//! round-tripping float formatting. Parsing what we wrote gives back the exact
+//! same floats.
+//!
+//! # Positions
+//!
+//! The side to move (`w` or `b`), then one token per piece id, separated by spaces.
+//! A piece is `<side><kind>@<x>,<y>:<angle>`, such as `WN@1.5,7.5:0`. Captured
+//! pieces are written `x`, so that piece ids survive the round trip. Kinds are
+//! `P`, `R`, `N`, `B`, `Q`, `K`.
+//!
+//! # Actions
+//!
+//! A turn is made of [`Action`]s separated by `;`. A travel is `<from>><to>`, such as
+//! `3.5,6.5>3.5,4.5`, and a rotation is `<at>:<angle>`, such as `3.5,7.5:0.3926991`.
+//! Points are matched to pieces and travel points with the usual hit circles, so
+//! hand-typed coordinates don't need to be exact. An engine [`Move`] is a travel
+//! followed by a rotation; both refer to the board as it was before the move.
+
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::{
+    piece::{Piece, PieceId, PieceKind, Pieces, Side},
+    turn::{Move, RotationPhase, TravelPhase, Turns},
+};
+
+/// Why some notation couldn't be understood or played.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotationError {
+    /// The text isn't notation at all.
+    Malformed(String),
+    /// There's no piece at the given point.
+    NoPiece((f32, f32)),
+    /// The piece at `from` can't travel to `to`.
+    NotTravelable { from: (f32, f32), to: (f32, f32) },
+    /// The piece at the given point doesn't belong to the side to move.
+    WrongSide((f32, f32)),
+    /// The actions don't make up the kind of turn that was expected.
+    BadTurn(String),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::Malformed(text) => write!(f, "can't parse {text:?}"),
+            NotationError::NoPiece((x, y)) => write!(f, "no piece at {x},{y}"),
+            NotationError::NotTravelable { from, to } => {
+                write!(
+                    f,
+                    "piece at {},{} can't travel to {},{}",
+                    from.0, from.1, to.0, to.1
+                )
+            }
+            NotationError::WrongSide((x, y)) => {
+                write!(f, "piece at {x},{y} doesn't belong to the side to move")
+            }
+            NotationError::BadTurn(why) => write!(f, "{why}"),
+        }
+    }
+}
+
+impl Error for NotationError {}
+
+fn malformed(text: &str) -> NotationError {
+    NotationError::Malformed(text.to_string())
+}
+
+pub fn side_letter(side: Side) -> char {
+    match side {
+        Side::Black => 'B',
+        Side::White => 'W',
+    }
+}
+
+pub fn side_from_letter(letter: char) -> Option<Side> {
+    match letter.to_ascii_uppercase() {
+        'B' => Some(Side::Black),
+        'W' => Some(Side::White),
+        _ => None,
+    }
+}
+
+pub fn kind_letter(kind: PieceKind) -> char {
+    match kind {
+        PieceKind::Pawn => 'P',
+        PieceKind::Rook => 'R',
+        PieceKind::Knight => 'N',
+        PieceKind::Bishop => 'B',
+        PieceKind::Queen => 'Q',
+        PieceKind::King => 'K',
+    }
+}
+
+pub fn kind_from_letter(letter: char) -> Option<PieceKind> {
+    match letter.to_ascii_uppercase() {
+        'P' => Some(PieceKind::Pawn),
+        'R' => Some(PieceKind::Rook),
+        'N' => Some(PieceKind::Knight),
+        'B' => Some(PieceKind::Bishop),
+        'Q' => Some(PieceKind::Queen),
+        'K' => Some(PieceKind::King),
+        _ => None,
+    }
+}
+
+fn parse_f32(text: &str) -> Result<f32, NotationError> {
+    text.trim().parse().map_err(|_| malformed(text))
+}
+
+/// Parse an `x,y` point.
+pub fn parse_point(text: &str) -> Result<(f32, f32), NotationError> {
+    let (x, y) = text.split_once(',').ok_or_else(|| malformed(text))?;
+    Ok((parse_f32(x)?, parse_f32(y)?))
+}
+
+/// Write a position. See the [module docs](self).
+pub fn write_position(pieces: &Pieces, to_move: Side) -> String {
+    let mut ans = String::from(match to_move {
+        Side::Black => "b",
+        Side::White => "w",
+    });
+    for id in pieces.ids() {
+        ans.push(' ');
+        match pieces.get(id) {
+            Some(piece) => ans.push_str(&format!(
+                "{}{}@{},{}:{}",
+                side_letter(piece.side()),
+                kind_letter(piece.kind()),
+                piece.x(),
+                piece.y(),
+                piece.angle()
+            )),
+            None => ans.push('x'),
+        }
+    }
+    ans
+}
+
+/// Parse a position written by [`write_position`].
+pub fn parse_position(text: &str) -> Result<(Pieces, Side), NotationError> {
+    let mut tokens = text.split_whitespace();
+    let to_move = match tokens.next() {
+        Some("w") => Side::White,
+        Some("b") => Side::Black,
+        _ => return Err(malformed(text)),
+    };
+
+    let mut pieces = vec![];
+    let mut captured = vec![];
+    for (id, token) in tokens.enumerate() {
+        if token == "x" {
+            // a placeholder, to keep the ids of the pieces after it.
+            pieces.push(Piece::new(id, (0., 0.), 0., Side::White, PieceKind::Pawn));
+            captured.push(id);
+            continue;
+        }
+
+        let mut chars = token.chars();
+        let side = chars.next().and_then(side_from_letter);
+        let kind = chars.next().and_then(kind_from_letter);
+        let (Some(side), Some(kind), Some('@')) = (side, kind, chars.next()) else {
+            return Err(malformed(token));
+        };
+        let (center, angle) = chars
+            .as_str()
+            .split_once(':')
+            .ok_or_else(|| malformed(token))?;
+        pieces.push(Piece::new(
+            id,
+            parse_point(center)?,
+            parse_f32(angle)?,
+            side,
+            kind,
+        ));
+    }
+
+    let mut pieces = Pieces::with(pieces);
+    for id in captured {
+        pieces.capture(id);
+    }
+    Ok((pieces, to_move))
+}
+
+/// One half of a turn, as written in notation. See the [module docs](self).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    /// The piece at `from` travels to the travel point at `to`.
+    Travel { from: (f32, f32), to: (f32, f32) },
+    /// The piece at `at` rotates to `angle`.
+    Rotate { at: (f32, f32), angle: f32 },
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Travel { from, to } => write!(f, "{},{}>{},{}", from.0, from.1, to.0, to.1),
+            Action::Rotate { at, angle } => write!(f, "{},{}:{}", at.0, at.1, angle),
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = NotationError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        if let Some((from, to)) = text.split_once('>') {
+            Ok(Action::Travel {
+                from: parse_point(from)?,
+                to: parse_point(to)?,
+            })
+        } else if let Some((at, angle)) = text.split_once(':') {
+            Ok(Action::Rotate {
+                at: parse_point(at)?,
+                angle: parse_f32(angle)?,
+            })
+        } else {
+            Err(malformed(text))
+        }
+    }
+}
+
+/// Write a turn made of `actions`, separated by `;`.
+pub fn write_actions(actions: &[Action]) -> String {
+    actions
+        .iter()
+        .map(Action::to_string)
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Parse a turn written by [`write_actions`].
+pub fn parse_actions(text: &str) -> Result<Vec<Action>, NotationError> {
+    text.trim().split(';').map(str::parse).collect()
+}
+
+/// The actions that make up an engine move, given the board before the move.
+pub fn move_actions(move_: &Move, board: &Pieces) -> [Action; 2] {
+    let at = board
+        .get(move_.rotate.piece)
+        .map_or(move_.travel.src(), |piece| piece.center());
+    [
+        Action::Travel {
+            from: move_.travel.src(),
+            to: move_.travel.dest(),
+        },
+        Action::Rotate {
+            at,
+            angle: move_.rotate.dest,
+        },
+    ]
+}
+
+/// Write an engine move, given the board before the move.
+pub fn write_move(move_: &Move, board: &Pieces) -> String {
+    write_actions(&move_actions(move_, board))
+}
+
+/// Find the piece at `at`.
+pub fn resolve_piece(board: &Pieces, at: (f32, f32)) -> Result<PieceId, NotationError> {
+    board.get_id(at.0, at.1).ok_or(NotationError::NoPiece(at))
+}
+
+/// Find the travel of the piece at `from` to the travel point at `to`, checking that it's legal.
+///
+/// Reinitializes the travelling piece's auxiliary data, so that it's never stale.
+pub fn resolve_travel(
+    board: &mut Pieces,
+    from: (f32, f32),
+    to: (f32, f32),
+) -> Result<TravelPhase, NotationError> {
+    let id = resolve_piece(board, from)?;
+    board
+        .get_mut(id)
+        .expect("we just found it")
+        .init_auxiliary_data();
+
+    let piece = board.get(id).expect("we just found it");
+    piece
+        .travel_points_unchecked()
+        .filter(|&(_, x, y)| Piece::collidepoint_generic(to.0, to.1, x, y))
+        .find_map(|(tvk, x, y)| board.travelable(piece, x, y, tvk))
+        .ok_or(NotationError::NotTravelable { from, to })
+}
+
+/// Parse an engine move for the side to move, checking that it's legal.
+pub fn parse_move(text: &str, turns: &mut Turns) -> Result<Move, NotationError> {
+    resolve_move(&parse_actions(text)?, turns)
+}
+
+/// Find the engine move made of `actions` for the side to move, checking that it's legal.
+pub fn resolve_move(actions: &[Action], turns: &mut Turns) -> Result<Move, NotationError> {
+    let &[Action::Travel { from, to }, Action::Rotate { at, angle }] = actions else {
+        return Err(NotationError::BadTurn(format!(
+            "{:?} is not a travel followed by a rotation",
+            write_actions(actions)
+        )));
+    };
+
+    let to_move = turns.to_move();
+    let board = turns.working_board_mut();
+    for point in [from, at] {
+        if board.get(resolve_piece(board, point)?).map(Piece::side) != Some(to_move) {
+            return Err(NotationError::WrongSide(point));
+        }
+    }
+
+    let rotating = board
+        .get(resolve_piece(board, at)?)
+        .expect("resolved pieces exist");
+    let rotate = RotationPhase {
+        piece: rotating.id(),
+        src: rotating.angle(),
+        dest: angle,
+    };
+    let travel = resolve_travel(board, from, to)?;
+    Ok(Move { travel, rotate })
+}
+
+/// Play one turn made of `actions` for the side to move, and save it.
+///
+/// Every action refers to the board as it was before the turn, like in an engine
+/// [`Move`]. Travels are checked with [`Pieces::travelable`], and pawns promote.
+/// Nothing is changed if any action is invalid.
+pub fn play_actions(turns: &mut Turns, actions: &[Action]) -> Result<(), NotationError> {
+    let to_move = turns.to_move();
+    let board = turns.working_board_mut();
+
+    enum Resolved {
+        Travel(TravelPhase),
+        Rotate(PieceId, f32),
+    }
+    let mut resolved = vec![];
+    for action in actions {
+        let (Action::Travel { from: at, .. } | Action::Rotate { at, .. }) = *action;
+        let id = resolve_piece(board, at)?;
+        if board.get(id).map(Piece::side) != Some(to_move) {
+            return Err(NotationError::WrongSide(at));
+        }
+        resolved.push(match *action {
+            Action::Travel { from, to } => Resolved::Travel(resolve_travel(board, from, to)?),
+            Action::Rotate { angle, .. } => Resolved::Rotate(id, angle),
+        });
+    }
+
+    for action in resolved {
+        match action {
+            Resolved::Travel(travel) => {
+                let (x, y) = travel.dest();
+                board.travel(travel.piece(), x, y);
+            }
+            Resolved::Rotate(id, angle) => board
+                .get_mut(id)
+                .expect("own pieces can't be captured")
+                .set_angle(angle),
+        }
+    }
+    turns.save_turn();
+    turns.set_to_move(to_move.toggled());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, parse_actions, parse_move, parse_position, write_move, write_position};
+    use crate::{
+        engine::EngineKind,
+        piece::{Pieces, Side},
+        search::SearchLimits,
+        turn::Turns,
+    };
+
+    #[test]
+    fn position_round_trips() {
+        let mut pieces = Pieces::standard_board();
+        pieces.capture(3);
+        let text = write_position(&pieces, Side::Black);
+        let (parsed, to_move) = parse_position(&text).unwrap();
+        assert_eq!(to_move, Side::Black);
+        assert_eq!(parsed.position_hash(), pieces.position_hash());
+        assert_eq!(write_position(&parsed, to_move), text);
+    }
+
+    #[test]
+    fn actions_round_trip() {
+        let text = "3.5,6.5>3.5,4.5;0.5,7.5:-0.3926991";
+        let actions = parse_actions(text).unwrap();
+        assert_eq!(
+            actions[0],
+            Action::Travel {
+                from: (3.5, 6.5),
+                to: (3.5, 4.5)
+            }
+        );
+        assert_eq!(super::write_actions(&actions), text);
+    }
+
+    #[test]
+    fn engine_moves_round_trip() {
+        let mut turns = Turns::with(Pieces::standard_board());
+        for kind in [EngineKind::Random, EngineKind::Greedy] {
+            let move_ = kind
+                .build(3)
+                .choose_move(&mut turns, &SearchLimits::default())
+                .unwrap();
+            let text = write_move(&move_, turns.working_board_ref());
+            assert_eq!(parse_move(&text, &mut turns), Ok(move_));
+        }
+    }
+
+    #[test]
+    fn illegal_moves_are_rejected() {
+        let mut turns = Turns::with(Pieces::standard_board());
+        // a pawn can't travel three tiles.
+        assert!(parse_move("3.5,6.5>3.5,3.5;3.5,6.5:0", &mut turns).is_err());
+        // it's not black's turn.
+        assert!(parse_move("3.5,1.5>3.5,3.5;3.5,1.5:0", &mut turns).is_err());
+        // hand-typed coordinates may be a little off.
+        assert!(parse_move("3.5,6.5>3.45,4.55;3.5,6.5:0", &mut turns).is_ok());
+    }
+}
@@ -0,0 +1,127 @@
+//! Move generation counting, for catching regressions.
+//!
+//! `perft(n)` is the number of move sequences of length `n` from a position, as
+//! generated by the engine. It doesn't mean anything by itself, but it changes as
+//! soon as move generation or [`Pieces::travelable`](crate::piece::Pieces::travelable)
+//! does, so pinned counts make good tests. [`Turns::divide`] breaks a count down
+//! per root move, to find which move went wrong.
+//!
+//! Games don't end when a king is captured here: the tree is exactly what the
+//! search would walk if it never stopped.
+
+use crate::turn::{Move, Turns};
+
+/// Perft code.
+impl Turns {
+    /// Count the leaf nodes `depth` engine moves from the current position.
+    ///
+    /// The position is left as it was found.
+    pub fn perft(&mut self, depth: usize) -> u64 {
+        match depth {
+            0 => 1,
+            // no need to play out the last moves just to count them.
+            1 => self.all_moves().len() as u64,
+            _ => {
+                let mut ans = 0;
+                for move_ in self.all_moves() {
+                    self.apply(&move_);
+                    ans += self.perft(depth - 1);
+                    self.unapply(&move_);
+                }
+                ans
+            }
+        }
+    }
+
+    /// [`Self::perft`] of each root move, in move generation order.
+    ///
+    /// The counts sum to `self.perft(depth)`. `depth` must be at least 1.
+    pub fn divide(&mut self, depth: usize) -> Vec<(Move, u64)> {
+        assert!(depth > 0, "there are no root moves at depth 0");
+        self.all_moves()
+            .into_iter()
+            .map(|move_| {
+                self.apply(&move_);
+                let count = self.perft(depth - 1);
+                self.unapply(&move_);
+                (move_, count)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use crate::{
+        notation,
+        piece::{Piece, PieceKind, Pieces, Side},
+        turn::Turns,
+    };
+
+    /// Chess960 position numbers, see [`crate::chess960`].
+    const CHESS960: [u16; 3] = [
+        0,   // BBQNNRKR
+        345, // NRKBBQRN
+        959, // RKRNNQBB
+    ];
+
+    fn perfts(turns: &mut Turns, depth: usize) -> Vec<u64> {
+        (0..=depth).map(|depth| turns.perft(depth)).collect()
+    }
+
+    #[test]
+    fn standard_counts() {
+        let mut turns = Turns::with(Pieces::standard_board());
+        assert_eq!(perfts(&mut turns, 2), [1, 640, 409600]);
+    }
+
+    #[test]
+    fn open_center_counts() {
+        // the pawns on e4 and d5 can take each other.
+        let mut turns = Turns::with(Pieces::standard_board());
+        for turn in [
+            "4.5,6.5>4.5,4.5;4.5,6.5:0",
+            "3.5,1.5>3.5,3.5;3.5,1.5:-3.1415927",
+        ] {
+            let actions = notation::parse_actions(turn).unwrap();
+            notation::play_actions(&mut turns, &actions).unwrap();
+        }
+        assert_eq!(perfts(&mut turns, 2), [1, 1024, 967936]);
+    }
+
+    #[test]
+    fn chess960_counts() {
+        let expected = [[1, 640, 409600], [1, 576, 331776], [1, 640, 409600]];
+        for (number, expected) in CHESS960.into_iter().zip(expected) {
+            let mut turns = Turns::with(Pieces::chess960_board(number));
+            assert_eq!(perfts(&mut turns, 2), expected, "chess960 #{number}");
+        }
+    }
+
+    #[test]
+    fn endgame_counts() {
+        let mut turns = Turns::with(Pieces::with(vec![
+            Piece::from_tile(0, (4, 7), 0., Side::White, PieceKind::King),
+            Piece::from_tile(1, (0, 7), 0., Side::White, PieceKind::Rook),
+            Piece::from_tile(2, (4, 0), PI, Side::Black, PieceKind::King),
+            Piece::from_tile(3, (2, 2), PI, Side::Black, PieceKind::Knight),
+            Piece::from_tile(4, (6, 3), PI, Side::Black, PieceKind::Pawn),
+        ]));
+        assert_eq!(perfts(&mut turns, 3), [1, 60, 5400, 362448]);
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let mut turns = Turns::with(Pieces::chess960_board(CHESS960[0]));
+        let hash = turns.position_hash();
+        let divided = turns.divide(2);
+        assert_eq!(divided.len() as u64, turns.perft(1));
+        assert_eq!(
+            divided.iter().map(|(_, count)| count).sum::<u64>(),
+            turns.perft(2)
+        );
+        assert_eq!(hash, turns.position_hash());
+    }
+}
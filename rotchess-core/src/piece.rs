@@ -702,6 +702,20 @@ pub struct Pieces {
 }
 
 impl Pieces {
+    /// Create a board from arbitrary pieces.
+    ///
+    /// Every piece's id must be its index in `pieces`. Panics otherwise.
+    pub fn with(pieces: Vec<Piece>) -> Self {
+        assert!(
+            pieces.iter().enumerate().all(|(i, piece)| piece.id() == i),
+            "piece ids must be their indices"
+        );
+
+        Self {
+            inner: pieces.into_iter().map(|piece| (true, piece)).collect(),
+        }
+    }
+
     /// Create a board with standard piece positions.
     pub fn standard_board() -> Self {
         let mut inner = vec![];
@@ -764,13 +778,13 @@ impl Pieces {
 
     /// Create a board with a shuffled back row.
     ///
-    /// This is known as the 960, or Fischer, variant setup.
+    /// Any shuffle goes, including ones with both bishops on the same color, or the
+    /// king outside the rooks. For proper Chess960 setups, see
+    /// [`Pieces::chess960_board`].
     ///
     /// `idx_ordering` must be a permutation of `0..8`. It is used as indices for the pieces,
     /// so it should probably be randomly generated.
-    pub fn chess960_board(idx_ordering: impl FnOnce() -> [usize; 8]) -> Self {
-        let mut inner = vec![];
-
+    pub fn shuffled_board(idx_ordering: impl FnOnce() -> [usize; 8]) -> Self {
         let pieces: [PieceKind; 8] = [
             PieceKind::Rook,
             PieceKind::Knight,
@@ -788,7 +802,13 @@ impl Pieces {
             ordering2.sort();
             ordering2 == (0..8).collect::<Vec<_>>()
         });
-        let order = ordering.map(|i| pieces[i]);
+        Self::with_back_rank(ordering.map(|i| pieces[i]))
+    }
+
+    /// Create a board with pawns in their usual places and `order` as both back rows,
+    /// from the a-file to the h-file.
+    pub fn with_back_rank(order: [PieceKind; 8]) -> Self {
+        let mut inner = vec![];
 
         for i in 0..8 {
             inner.push(Piece::from_tile(
@@ -835,6 +855,57 @@ impl Pieces {
         Self { inner }
     }
 
+    /// A hash of the position that only depends on what's on the board.
+    ///
+    /// Two boards hash the same if the same piece ids are alive with the same kinds,
+    /// sides, centers and angles. The hash is FNV-1a over a fixed byte layout, so it is
+    /// stable across runs and platforms (native and wasm agree).
+    pub fn position_hash(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const FNV_PRIME: u64 = 0x100000001b3;
+
+        let mut hash = FNV_OFFSET_BASIS;
+        let mut write = |bytes: &[u8]| {
+            for byte in bytes {
+                hash ^= *byte as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        };
+
+        for (alive, piece) in &self.inner {
+            if !alive {
+                write(&[0]);
+                continue;
+            }
+            write(&[1, piece.kind() as u8, piece.side() as u8]);
+            write(&piece.x().to_le_bytes());
+            write(&piece.y().to_le_bytes());
+            write(&piece.angle().to_le_bytes());
+        }
+        hash
+    }
+
+    /// Every piece id in this set, whether or not the piece is still on the board.
+    pub fn ids(&self) -> std::ops::Range<PieceId> {
+        0..self.inner.len()
+    }
+
+    /// Take a piece off the board, as if it were captured.
+    pub fn capture(&mut self, id: PieceId) {
+        self.inner[id].0 = false;
+    }
+
+    /// Promote the piece if it [should](Piece::should_promote). Returns its new kind if it did.
+    pub fn promote(&mut self, id: PieceId) -> Option<PieceKind> {
+        let piece = self.get_mut(id)?;
+        if !Piece::should_promote(piece.kind(), piece.side(), piece.y()) {
+            return None;
+        }
+        piece.set_kind(PieceKind::Queen);
+        piece.init_auxiliary_data();
+        Some(PieceKind::Queen)
+    }
+
     pub fn board_pieces(&self) -> impl Iterator<Item = &Piece> {
         self.inner
             .iter()
@@ -924,10 +995,7 @@ impl Pieces {
         let piece = &mut self.inner[id].1;
         piece.set_x(x);
         piece.set_y(y);
-        if Piece::should_promote(piece.kind(), piece.side(), y) {
-            piece.set_kind(PieceKind::Queen);
-            piece.init_auxiliary_data();
-        }
+        self.promote(id);
     }
 
     pub fn travelable(
@@ -953,6 +1021,8 @@ impl Pieces {
                     }
                     capture_ids[i] = piece.id();
                 }
+                // HashSet order is random. Sort so that equal travels compare equal.
+                capture_ids[0..len].sort_unstable();
                 TravelPhase::new(piece.id(), piece.center(), (x, y), (len, capture_ids))
             }};
         }
@@ -0,0 +1,415 @@
+//! A line-based text protocol for engines in other processes.
+//!
+//! It's modelled on UCI. A frontend writes [`Command`]s to the engine's stdin, one
+//! per line, and the engine answers with [`Reply`]s on stdout. Positions and moves
+//! are written in [notation](crate::notation).
+//!
+//! ```text
+//! > rotchess
+//! < id name alpha-beta
+//! < rotchessok
+//! > position startpos moves 3.5,6.5>3.5,4.5;3.5,7.5:0.3926991
+//! > go depth 3 movetime 2000
+//! < info depth 1 score cp 12 nodes 1043 pv 3.5,1.5>3.5,3.5;3.5,0.5:-2.7488937
+//! < info depth 2 score cp -3 nodes 52015 pv ...
+//! < bestmove 3.5,1.5>3.5,3.5;3.5,0.5:-2.7488937
+//! > quit
+//! ```
+//!
+//! | command                                  | meaning                                     |
+//! |------------------------------------------|---------------------------------------------|
+//! | `rotchess`                               | handshake. answered by `id name` and `rotchessok` |
+//! | `isready`                                | answered by `readyok` once the engine is idle |
+//! | `newgame`                                | forget anything learned about the last game |
+//! | `position startpos [moves M...]`         | the standard board, then moves              |
+//! | `position <position> [moves M...]`       | any [position](crate::notation), then moves |
+//! | `go [depth N] [nodes N] [movetime MS] [infinite]` | search. answered by `info`s, then `bestmove` |
+//! | `stop`                                   | finish searching now                        |
+//! | `quit`                                   | exit                                        |
+//!
+//! A `go` without limits searches with the default [`SearchLimits`]. `go infinite`
+//! searches until `stop`. The engine answers `bestmove none` when it has no moves.
+//! Both sides ignore lines they don't understand.
+
+use std::fmt;
+
+use crate::{
+    notation::{self, Action, NotationError},
+    piece::{Pieces, Side},
+    search::{SearchLimits, SearchResult},
+    turn::{Score, Turns},
+};
+
+/// A starting position and the moves played from it.
+#[derive(Clone)]
+pub struct PositionSetup {
+    /// The starting position and side to move. `None` for the standard board, white to move.
+    pub start: Option<(Pieces, Side)>,
+    /// Engine moves, each a travel followed by a rotation.
+    pub moves: Vec<Vec<Action>>,
+}
+
+impl PositionSetup {
+    /// The current position of `turns`, without its history.
+    pub fn current(turns: &Turns) -> Self {
+        Self {
+            start: Some((turns.working_board_ref().clone(), turns.to_move())),
+            moves: vec![],
+        }
+    }
+
+    /// Play the moves from the start, checking each one.
+    pub fn turns(&self) -> Result<Turns, NotationError> {
+        let (start, to_move) = self
+            .start
+            .clone()
+            .unwrap_or_else(|| (Pieces::standard_board(), Side::White));
+        let mut turns = Turns::with(start);
+        turns.set_to_move(to_move);
+        for actions in &self.moves {
+            let move_ = notation::resolve_move(actions, &mut turns)?;
+            turns.play(&move_);
+        }
+        Ok(turns)
+    }
+}
+
+/// Limits for a `go` command.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Go {
+    pub depth: Option<usize>,
+    pub nodes: Option<u64>,
+    /// In milliseconds, like UCI.
+    pub movetime: Option<u64>,
+    pub infinite: bool,
+}
+
+impl Go {
+    /// Ask for a search bounded by `limits`. The stop flag and clock stay behind.
+    pub fn from_limits(limits: &SearchLimits) -> Self {
+        let ans = Self {
+            depth: limits.depth,
+            nodes: limits.nodes,
+            movetime: limits.time.map(|seconds| (seconds * 1000.).round() as u64),
+            infinite: false,
+        };
+        Self {
+            infinite: ans == Self::default(),
+            ..ans
+        }
+    }
+
+    /// The limits to search with.
+    pub fn limits(&self) -> SearchLimits {
+        if !self.infinite && *self == Self::default() {
+            return SearchLimits::default();
+        }
+        SearchLimits {
+            depth: self.depth,
+            nodes: self.nodes,
+            time: self.movetime.map(|ms| ms as f64 / 1000.),
+            ..SearchLimits::infinite()
+        }
+    }
+}
+
+/// Something a frontend tells an engine.
+#[derive(Clone)]
+pub enum Command {
+    Hello,
+    IsReady,
+    NewGame,
+    Position(PositionSetup),
+    Go(Go),
+    Stop,
+    Quit,
+}
+
+impl Command {
+    /// Parse one line.
+    pub fn parse(line: &str) -> Result<Self, NotationError> {
+        let malformed = || NotationError::Malformed(line.to_string());
+        let mut words = line.split_whitespace();
+        match words.next().ok_or_else(malformed)? {
+            "rotchess" => Ok(Command::Hello),
+            "isready" => Ok(Command::IsReady),
+            "newgame" => Ok(Command::NewGame),
+            "stop" => Ok(Command::Stop),
+            "quit" => Ok(Command::Quit),
+            "position" => {
+                let words: Vec<&str> = words.collect();
+                let (start, moves) = match words.iter().position(|&word| word == "moves") {
+                    Some(i) => (&words[..i], &words[i + 1..]),
+                    None => (&words[..], &[][..]),
+                };
+                let start = match start {
+                    ["startpos"] => None,
+                    _ => Some(notation::parse_position(&start.join(" "))?),
+                };
+                let moves = moves
+                    .iter()
+                    .map(|move_| notation::parse_actions(move_))
+                    .collect::<Result<_, _>>()?;
+                Ok(Command::Position(PositionSetup { start, moves }))
+            }
+            "go" => {
+                let mut go = Go::default();
+                while let Some(word) = words.next() {
+                    match word {
+                        "depth" => go.depth = Some(parse_next(&mut words).ok_or_else(malformed)?),
+                        "nodes" => go.nodes = Some(parse_next(&mut words).ok_or_else(malformed)?),
+                        "movetime" => {
+                            go.movetime = Some(parse_next(&mut words).ok_or_else(malformed)?)
+                        }
+                        "infinite" => go.infinite = true,
+                        _ => return Err(malformed()),
+                    }
+                }
+                Ok(Command::Go(go))
+            }
+            _ => Err(malformed()),
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Hello => write!(f, "rotchess"),
+            Command::IsReady => write!(f, "isready"),
+            Command::NewGame => write!(f, "newgame"),
+            Command::Stop => write!(f, "stop"),
+            Command::Quit => write!(f, "quit"),
+            Command::Position(setup) => {
+                match &setup.start {
+                    None => write!(f, "position startpos")?,
+                    Some((pieces, to_move)) => {
+                        write!(f, "position {}", notation::write_position(pieces, *to_move))?
+                    }
+                }
+                if !setup.moves.is_empty() {
+                    write!(f, " moves")?;
+                    for actions in &setup.moves {
+                        write!(f, " {}", notation::write_actions(actions))?;
+                    }
+                }
+                Ok(())
+            }
+            Command::Go(go) => {
+                write!(f, "go")?;
+                if let Some(depth) = go.depth {
+                    write!(f, " depth {depth}")?;
+                }
+                if let Some(nodes) = go.nodes {
+                    write!(f, " nodes {nodes}")?;
+                }
+                if let Some(movetime) = go.movetime {
+                    write!(f, " movetime {movetime}")?;
+                }
+                if go.infinite {
+                    write!(f, " infinite")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Progress of a search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Info {
+    pub depth: usize,
+    /// From the perspective of the side to move, in hundredths of a pawn.
+    pub score: Score,
+    pub nodes: u64,
+    /// The principal variation, as engine moves.
+    pub pv: Vec<Vec<Action>>,
+}
+
+impl Info {
+    /// Describe `result`, a search of the current position of `turns`.
+    pub fn from_result(turns: &Turns, result: &SearchResult) -> Self {
+        let mut board = turns.working_board_ref().clone();
+        let pv = result
+            .pv
+            .iter()
+            .map(|move_| {
+                let actions = notation::move_actions(move_, &board).to_vec();
+                board.make_move(move_);
+                actions
+            })
+            .collect();
+        Self {
+            depth: result.depth,
+            score: result.score,
+            nodes: result.nodes,
+            pv,
+        }
+    }
+}
+
+/// Something an engine tells a frontend.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reply {
+    Id {
+        name: String,
+    },
+    HelloOk,
+    ReadyOk,
+    Info(Info),
+    /// The move chosen, or `None` if there were no moves.
+    BestMove(Option<Vec<Action>>),
+}
+
+impl Reply {
+    /// Parse one line.
+    pub fn parse(line: &str) -> Result<Self, NotationError> {
+        let malformed = || NotationError::Malformed(line.to_string());
+        let mut words = line.split_whitespace();
+        match words.next().ok_or_else(malformed)? {
+            "rotchessok" => Ok(Reply::HelloOk),
+            "readyok" => Ok(Reply::ReadyOk),
+            "id" => match words.next() {
+                Some("name") => Ok(Reply::Id {
+                    name: words.collect::<Vec<_>>().join(" "),
+                }),
+                _ => Err(malformed()),
+            },
+            "bestmove" => match words.next().ok_or_else(malformed)? {
+                "none" => Ok(Reply::BestMove(None)),
+                move_ => Ok(Reply::BestMove(Some(notation::parse_actions(move_)?))),
+            },
+            "info" => {
+                let mut info = Info {
+                    depth: 0,
+                    score: 0.,
+                    nodes: 0,
+                    pv: vec![],
+                };
+                while let Some(word) = words.next() {
+                    match word {
+                        "depth" => info.depth = parse_next(&mut words).ok_or_else(malformed)?,
+                        "nodes" => info.nodes = parse_next(&mut words).ok_or_else(malformed)?,
+                        "score" => {
+                            if words.next() != Some("cp") {
+                                return Err(malformed());
+                            }
+                            info.score = parse_next(&mut words).ok_or_else(malformed)?;
+                        }
+                        "pv" => {
+                            info.pv = words
+                                .by_ref()
+                                .map(notation::parse_actions)
+                                .collect::<Result<_, _>>()?;
+                        }
+                        _ => return Err(malformed()),
+                    }
+                }
+                Ok(Reply::Info(info))
+            }
+            _ => Err(malformed()),
+        }
+    }
+}
+
+fn parse_next<'a, T: std::str::FromStr>(words: &mut impl Iterator<Item = &'a str>) -> Option<T> {
+    words.next().and_then(|word| word.parse().ok())
+}
+
+impl fmt::Display for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reply::Id { name } => write!(f, "id name {name}"),
+            Reply::HelloOk => write!(f, "rotchessok"),
+            Reply::ReadyOk => write!(f, "readyok"),
+            Reply::BestMove(None) => write!(f, "bestmove none"),
+            Reply::BestMove(Some(actions)) => {
+                write!(f, "bestmove {}", notation::write_actions(actions))
+            }
+            Reply::Info(info) => {
+                write!(
+                    f,
+                    "info depth {} score cp {} nodes {}",
+                    info.depth, info.score, info.nodes
+                )?;
+                if !info.pv.is_empty() {
+                    write!(f, " pv")?;
+                    for actions in &info.pv {
+                        write!(f, " {}", notation::write_actions(actions))?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, Go, Info, Reply};
+    use crate::{
+        piece::{Piece, PieceKind, Pieces, Side},
+        search::SearchLimits,
+        turn::Turns,
+    };
+
+    #[test]
+    fn commands_round_trip() {
+        for line in [
+            "rotchess",
+            "position startpos",
+            "position startpos moves 3.5,6.5>3.5,4.5;3.5,7.5:0.3926991",
+            "go depth 3 movetime 1500",
+            "go infinite",
+        ] {
+            let command = Command::parse(line).unwrap();
+            assert_eq!(command.to_string(), line);
+        }
+        assert!(Command::parse("go sideways").is_err());
+    }
+
+    #[test]
+    fn positions_are_checked() {
+        let Ok(Command::Position(setup)) =
+            Command::parse("position startpos moves 3.5,6.5>3.5,4.5;3.5,7.5:0.3926991")
+        else {
+            panic!("that's a position");
+        };
+        let turns = setup.turns().unwrap();
+        assert_eq!(turns.curr_turn(), 1);
+
+        let Ok(Command::Position(setup)) =
+            Command::parse("position startpos moves 3.5,6.5>3.5,2.5;3.5,7.5:0")
+        else {
+            panic!("that's a position");
+        };
+        assert!(setup.turns().is_err());
+    }
+
+    #[test]
+    fn limits_survive_go() {
+        let limits = SearchLimits::infinite().with_depth(4).with_time(0.25);
+        let go = Go::from_limits(&limits);
+        assert_eq!(go.movetime, Some(250));
+        assert_eq!(go.limits().depth, Some(4));
+        assert_eq!(go.limits().time, Some(0.25));
+        assert!(Go::from_limits(&SearchLimits::infinite()).infinite);
+    }
+
+    #[test]
+    fn info_describes_the_search() {
+        let mut turns = Turns::with(Pieces::with(vec![
+            Piece::from_tile(0, (4, 7), 0., Side::White, PieceKind::King),
+            Piece::from_tile(1, (0, 7), 0., Side::White, PieceKind::Rook),
+            Piece::from_tile(2, (4, 0), 0., Side::Black, PieceKind::King),
+        ]));
+        let result = turns.search(&SearchLimits::infinite().with_depth(2));
+        assert_eq!(result.pv.len(), 2);
+        let info = Info::from_result(&turns, &result);
+        assert_eq!(info.pv.len(), result.pv.len());
+
+        let reply = Reply::Info(info);
+        assert_eq!(Reply::parse(&reply.to_string()), Ok(reply));
+        assert_eq!(Reply::parse("bestmove none"), Ok(Reply::BestMove(None)));
+    }
+}
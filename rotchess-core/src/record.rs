@@ -0,0 +1,327 @@
+//! Saved games.
+//!
+//! A [`GameRecord`] is a starting position and the turns played from it, written in
+//! [notation](crate::notation). The text format borrows PGN's tag pairs:
+//!
+//! ```text
+//! [White "alpha-beta"]
+//! [Black "greedy"]
+//! [Result "1-0"]
+//! [Position "w WP@0.5,6.5:0 ..."]
+//!
+//! 1. 3.5,6.5>3.5,4.5;0.5,7.5:-0.3926991
+//! 2. 3.5,1.5>3.5,3.5;3.5,1.5:3.5342917
+//! ```
+//!
+//! Every turn is on its own line. The `Position` tag holds the starting position and
+//! side to move; without it, the game starts from [`Pieces::standard_board`].
+//!
+//! [Annotations](crate::annotation) drawn after a turn follow it in braces, like
+//! `1. 3.5,6.5>3.5,4.5 {G3.5,1.5>3.5,3.5}`. Any drawn on the starting position are
+//! on a line of their own before the first turn.
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+    annotation::{self, Annotation},
+    notation::{self, Action, NotationError},
+    piece::{Pieces, Side},
+    turn::Turns,
+};
+
+/// How a game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl GameResult {
+    pub fn win_for(side: Side) -> Self {
+        match side {
+            Side::Black => GameResult::BlackWins,
+            Side::White => GameResult::WhiteWins,
+        }
+    }
+
+    /// The result as it's written in tags: `1-0`, `0-1` or `1/2-1/2`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            _ => None,
+        }
+    }
+}
+
+/// A starting position and the turns played from it.
+#[derive(Clone)]
+pub struct GameRecord {
+    /// Tag pairs, in the order they're written. `Position` is not among them.
+    tags: Vec<(String, String)>,
+    start: Pieces,
+    start_to_move: Side,
+    turns: Vec<Vec<Action>>,
+    /// The annotations drawn on the starting position, then after each turn.
+    annotations: Vec<Vec<Annotation>>,
+}
+
+impl GameRecord {
+    /// A record of a game starting from `start`, with no turns yet.
+    pub fn with(start: Pieces, start_to_move: Side) -> Self {
+        Self {
+            tags: vec![],
+            start,
+            start_to_move,
+            turns: vec![],
+            annotations: vec![vec![]],
+        }
+    }
+
+    pub fn start(&self) -> &Pieces {
+        &self.start
+    }
+
+    pub fn start_to_move(&self) -> Side {
+        self.start_to_move
+    }
+
+    pub fn turns(&self) -> &[Vec<Action>] {
+        &self.turns
+    }
+
+    /// Add a turn to the end of the game.
+    pub fn push_turn(&mut self, actions: Vec<Action>) {
+        self.turns.push(actions);
+        self.annotations.push(vec![]);
+    }
+
+    /// The annotations drawn after `turn` turns, or on the starting position for 0.
+    pub fn annotations(&self, turn: usize) -> &[Annotation] {
+        self.annotations.get(turn).map_or(&[], Vec::as_slice)
+    }
+
+    /// Replace the annotations drawn after `turn` turns, which must have been pushed.
+    pub fn set_annotations(&mut self, turn: usize, annotations: Vec<Annotation>) {
+        self.annotations[turn] = annotations;
+    }
+
+    /// Save every annotation drawn on `turns`, which should be this game.
+    pub fn copy_annotations(&mut self, turns: &Turns) {
+        let count = turns.turn_count().min(self.annotations.len());
+        for turn in 0..count {
+            self.annotations[turn] = turns.annotations_at(turn).to_vec();
+        }
+    }
+
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Set a tag, replacing its old value if it had one.
+    ///
+    /// Values can't contain `"` or newlines; they're replaced by `'` and spaces.
+    pub fn set_tag(&mut self, key: &str, value: &str) {
+        let value = value.replace('"', "'").replace('\n', " ");
+        match self.tags.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value,
+            None => self.tags.push((key.to_string(), value)),
+        }
+    }
+
+    /// The `Result` tag, if it's set to something we understand.
+    pub fn result(&self) -> Option<GameResult> {
+        self.tag("Result").and_then(GameResult::parse)
+    }
+
+    pub fn set_result(&mut self, result: GameResult) {
+        self.set_tag("Result", result.as_str());
+    }
+
+    /// Play the game out, checking every turn, with its annotations.
+    ///
+    /// Returns the turns played so far and the error if a turn couldn't be played.
+    pub fn replay(&self) -> Result<Turns, (Box<Turns>, NotationError)> {
+        let mut turns = Turns::with(self.start.clone());
+        turns.set_to_move(self.start_to_move);
+        turns.set_annotations(0, self.annotations[0].clone());
+        for (i, actions) in self.turns.iter().enumerate() {
+            if let Err(e) = notation::play_actions(&mut turns, actions) {
+                return Err((Box::new(turns), e));
+            }
+            turns.set_annotations(i + 1, self.annotations[i + 1].clone());
+        }
+        Ok(turns)
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, value) in &self.tags {
+            writeln!(f, "[{key} \"{value}\"]")?;
+        }
+        writeln!(
+            f,
+            "[Position \"{}\"]",
+            notation::write_position(&self.start, self.start_to_move)
+        )?;
+        writeln!(f)?;
+        if !self.annotations[0].is_empty() {
+            writeln!(
+                f,
+                "{{{}}}",
+                annotation::write_annotations(&self.annotations[0])
+            )?;
+        }
+        for (i, actions) in self.turns.iter().enumerate() {
+            write!(f, "{}. {}", i + 1, notation::write_actions(actions))?;
+            if !self.annotations[i + 1].is_empty() {
+                let annotations = annotation::write_annotations(&self.annotations[i + 1]);
+                write!(f, " {{{annotations}}}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = NotationError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut ans = GameRecord::with(Pieces::standard_board(), Side::White);
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Some(tag) = line.strip_prefix('[') {
+                let (key, value) = tag
+                    .strip_suffix(']')
+                    .and_then(|tag| tag.split_once(' '))
+                    .ok_or_else(|| NotationError::Malformed(line.to_string()))?;
+                let value = value.trim().trim_matches('"');
+                if key == "Position" {
+                    (ans.start, ans.start_to_move) = notation::parse_position(value)?;
+                } else {
+                    ans.set_tag(key, value);
+                }
+            } else {
+                // annotations go with the turn before them.
+                let (turn, annotations) = match line.split_once('{') {
+                    Some((turn, annotations)) => {
+                        let annotations = annotations
+                            .strip_suffix('}')
+                            .ok_or_else(|| NotationError::Malformed(line.to_string()))?;
+                        (turn.trim(), annotation::parse_annotations(annotations)?)
+                    }
+                    None => (line, vec![]),
+                };
+                if !turn.is_empty() {
+                    // turn numbers are optional.
+                    let actions = match turn.split_once(". ") {
+                        Some((number, actions)) if number.parse::<usize>().is_ok() => actions,
+                        _ => turn,
+                    };
+                    ans.push_turn(notation::parse_actions(actions)?);
+                }
+                ans.annotations
+                    .last_mut()
+                    .expect("there's always the start")
+                    .extend(annotations);
+            }
+        }
+        Ok(ans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GameRecord, GameResult};
+    use crate::{
+        annotation::{Annotation, MarkColor},
+        engine::EngineKind,
+        notation,
+        piece::{Pieces, Side},
+        search::SearchLimits,
+        turn::Turns,
+    };
+
+    #[test]
+    fn played_games_replay() {
+        let mut turns = Turns::with(Pieces::standard_board());
+        let mut record = GameRecord::with(Pieces::standard_board(), Side::White);
+        record.set_tag("White", "random");
+        let mut engine = EngineKind::Random.build(5);
+        for _ in 0..12 {
+            let move_ = engine
+                .choose_move(&mut turns, &SearchLimits::default())
+                .unwrap();
+            record.push_turn(notation::move_actions(&move_, turns.working_board_ref()).to_vec());
+            turns.play(&move_);
+        }
+        record.set_result(GameResult::Draw);
+
+        let parsed: GameRecord = record.to_string().parse().unwrap();
+        assert_eq!(parsed.tag("White"), Some("random"));
+        assert_eq!(parsed.result(), Some(GameResult::Draw));
+        let Ok(replayed) = parsed.replay() else {
+            panic!("the game was legal");
+        };
+        assert_eq!(replayed.position_hash(), turns.position_hash());
+        assert_eq!(replayed.curr_turn(), 12);
+    }
+
+    #[test]
+    fn annotations_are_saved_with_their_turns() {
+        let mut turns = Turns::with(Pieces::standard_board());
+        let circle = Annotation::Circle {
+            at: (4.5, 4.5),
+            color: MarkColor::Red,
+        };
+        turns.toggle_annotation(circle);
+        notation::play_actions(
+            &mut turns,
+            &notation::parse_actions("4.5,6.5>4.5,4.5").unwrap(),
+        )
+        .unwrap();
+        let arrow = Annotation::Arrow {
+            from: (3.5, 1.5),
+            to: (3.5, 3.5),
+            color: MarkColor::Green,
+        };
+        turns.toggle_annotation(arrow);
+
+        let mut record = GameRecord::with(Pieces::standard_board(), Side::White);
+        record.push_turn(notation::parse_actions("4.5,6.5>4.5,4.5").unwrap());
+        record.copy_annotations(&turns);
+        let text = record.to_string();
+        assert!(text.contains("\n{R4.5,4.5}\n1. 4.5,6.5>4.5,4.5 {G3.5,1.5>3.5,3.5}\n"));
+
+        let parsed: GameRecord = text.parse().unwrap();
+        let Ok(replayed) = parsed.replay() else {
+            panic!("the game was legal");
+        };
+        assert_eq!(replayed.annotations_at(0), [circle]);
+        assert_eq!(replayed.annotations(), [arrow]);
+    }
+
+    #[test]
+    fn bad_turns_stop_the_replay() {
+        let record: GameRecord = "1. 3.5,6.5>3.5,4.5\n2. 3.5,4.5>3.5,3.5".parse().unwrap();
+        // white moves twice in a row.
+        let Err((turns, _)) = record.replay() else {
+            panic!("white moved twice");
+        };
+        assert_eq!(turns.curr_turn(), 1);
+    }
+}
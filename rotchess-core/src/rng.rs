@@ -0,0 +1,41 @@
+//! A tiny seedable pseudorandom number generator.
+//!
+//! We only need randomness for things like random movers and shuffled setups, where
+//! reproducibility from a seed matters more than quality. This is SplitMix64, which
+//! is plenty for that and saves us a dependency.
+
+/// A SplitMix64 pseudorandom number generator.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Create a generator. Equal seeds give equal sequences, on every platform.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`. Panics if `n` is 0.
+    ///
+    /// Slightly biased for huge `n`, which we never use.
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "cannot pick from an empty range");
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Shuffle `slice` in place (Fisher-Yates).
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            slice.swap(i, self.below(i + 1));
+        }
+    }
+}
@@ -0,0 +1,728 @@
+//! Iterative deepening search over [`Turns`].
+//!
+//! The branching factor of rotchess is enormous (every travel can be paired with a
+//! rotation of any friendly piece), so a fixed-depth search can take arbitrarily long.
+//! Instead, we search depth 1, then depth 2, and so on, until one of the
+//! [`SearchLimits`] is hit. The best move of the deepest fully searched depth wins.
+//!
+//! Alpha-beta prunes best when good moves come first, so moves are ordered by the
+//! transposition table's best move, then captures (most valuable victim, least
+//! valuable attacker), then killer moves, then the history heuristic. At depth 0 we
+//! keep following captures in a quiescence search, so that we don't stop to evaluate
+//! in the middle of an exchange.
+
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use crate::{
+    piece::PieceId,
+    transposition::{Bound, TranspositionTable, TtEntry},
+    turn::{Move, Score, Turns},
+};
+
+/// Returns the current time in seconds, measured from any fixed point.
+///
+/// Only differences between two calls are ever used.
+pub type Clock = fn() -> f64;
+
+/// The clock used by default for time limits.
+///
+/// `std::time::Instant` panics on `wasm32-unknown-unknown`, so there we never
+/// advance. Supply a working clock with [`SearchLimits::with_clock`] (macroquad's
+/// `get_time`, for instance) to use time limits in the browser.
+#[cfg(not(target_arch = "wasm32"))]
+fn default_clock() -> f64 {
+    use std::{sync::OnceLock, time::Instant};
+
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs_f64()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn default_clock() -> f64 {
+    0.0
+}
+
+/// The deepest we will ever search, in plies.
+///
+/// Only reached if no other limit is given. We'd run out of patience long before this.
+pub const MAX_DEPTH: usize = 64;
+
+/// How many nodes we visit between checks of the clock and the stop flag.
+const CHECK_INTERVAL: u64 = 256;
+
+/// How many plies of captures quiescence search follows past the nominal depth.
+const QUIESCENCE_DEPTH: usize = 4;
+
+/// How many killer moves we remember per ply.
+const KILLER_SLOTS: usize = 2;
+
+/// Called after every iteration of a search with the position searched and the
+/// result so far. Engines speaking a [protocol](crate::protocol) report progress with it.
+pub type IterationCallback = Arc<dyn Fn(&Turns, &SearchResult) + Send + Sync>;
+
+/// Bounds on how long a search may run.
+///
+/// Every limit is optional. The search stops at whichever limit is hit first, and
+/// at [`MAX_DEPTH`] if no limits are given at all.
+#[derive(Clone)]
+pub struct SearchLimits {
+    /// Maximum depth in plies, counting the root move.
+    pub depth: Option<usize>,
+    /// Maximum number of nodes to visit.
+    pub nodes: Option<u64>,
+    /// Maximum wall-clock time in seconds, as measured by `clock`.
+    pub time: Option<f64>,
+    /// Cooperative cancellation. Set it to `true` from anywhere to stop the search.
+    pub stop: Option<Arc<AtomicBool>>,
+    /// The clock to measure `time` with.
+    pub clock: Clock,
+    /// Not a limit, but it's along for the ride: see [`IterationCallback`].
+    pub on_iteration: Option<IterationCallback>,
+}
+
+impl Default for SearchLimits {
+    /// Two plies (which is what the engine always searched before it had limits),
+    /// and at most two seconds.
+    fn default() -> Self {
+        Self {
+            depth: Some(2),
+            nodes: None,
+            time: Some(2.0),
+            stop: None,
+            clock: default_clock,
+            on_iteration: None,
+        }
+    }
+}
+
+impl SearchLimits {
+    /// No limits at all. The search will run until it is [stopped](Self::with_stop)
+    /// or reaches [`MAX_DEPTH`].
+    pub fn infinite() -> Self {
+        Self {
+            depth: None,
+            nodes: None,
+            time: None,
+            stop: None,
+            clock: default_clock,
+            on_iteration: None,
+        }
+    }
+
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    pub fn with_nodes(mut self, nodes: u64) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+
+    /// Limit the search to `seconds` of wall-clock time.
+    pub fn with_time(mut self, seconds: f64) -> Self {
+        self.time = Some(seconds);
+        self
+    }
+
+    pub fn with_stop(mut self, stop: Arc<AtomicBool>) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_on_iteration(mut self, on_iteration: IterationCallback) -> Self {
+        self.on_iteration = Some(on_iteration);
+        self
+    }
+
+    fn max_depth(&self) -> usize {
+        self.depth.unwrap_or(MAX_DEPTH).min(MAX_DEPTH)
+    }
+}
+
+/// What a search found.
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    /// The best move, or `None` if the side to move has no moves.
+    pub best_move: Option<Move>,
+    /// The score of `best_move`, from the perspective of the side to move.
+    pub score: Score,
+    /// The deepest depth that was searched to completion.
+    ///
+    /// May be 0 if we were stopped before finishing even depth 1, in which case
+    /// `best_move` is merely the best of the moves we had time to look at.
+    pub depth: usize,
+    /// Number of nodes visited.
+    pub nodes: u64,
+    /// The line we expect to be played, starting with `best_move`.
+    ///
+    /// Read back from the transposition table, so it's just `best_move` when searching
+    /// without one, and may be cut short when entries were overwritten.
+    pub pv: Vec<Move>,
+}
+
+/// The tile a point is on, as an index in `0..64`. Points off the board are clamped.
+fn tile_index((x, y): (f32, f32)) -> u8 {
+    let clamp = |v: f32| (v.floor() as i32).clamp(0, 7) as u8;
+    clamp(x) + 8 * clamp(y)
+}
+
+/// Most valuable victim, least valuable attacker.
+///
+/// Larger is better. Zero for travels that don't capture.
+fn mvv_lva(turns: &Turns, move_: &Move) -> i64 {
+    let board = turns.working_board_ref();
+    let victims: Score = move_
+        .travel
+        .captures()
+        .iter()
+        .filter_map(|&id| board.get(id))
+        .map(|piece| piece.kind().value())
+        .sum();
+    if victims == 0. {
+        return 0;
+    }
+    let attacker = board
+        .get(move_.travel.piece())
+        .map_or(0., |piece| piece.kind().value());
+    (victims * 100. - attacker) as i64
+}
+
+/// Heuristics for searching good moves first, learned over the course of a search.
+#[derive(Default)]
+struct MoveOrdering {
+    /// Per ply, quiet moves that recently caused a beta cutoff.
+    killers: Vec<[Option<Move>; KILLER_SLOTS]>,
+    /// For each (travelling piece, destination tile), how much its quiet moves have
+    /// caused cutoffs, weighted by depth.
+    history: HashMap<(PieceId, u8), u64>,
+}
+
+impl MoveOrdering {
+    const TT_MOVE: i64 = i64::MAX;
+    const CAPTURE: i64 = 1 << 50;
+    const KILLER: i64 = 1 << 40;
+
+    fn priority(&self, turns: &Turns, move_: &Move, ply: usize, tt_move: Option<&Move>) -> i64 {
+        if tt_move == Some(move_) {
+            return Self::TT_MOVE;
+        }
+        if !move_.travel.captures().is_empty() {
+            return Self::CAPTURE + mvv_lva(turns, move_);
+        }
+        if let Some(killers) = self.killers.get(ply)
+            && let Some(slot) = killers.iter().position(|k| k.as_ref() == Some(move_))
+        {
+            return Self::KILLER - slot as i64;
+        }
+        let key = (move_.travel.piece(), tile_index(move_.travel.dest()));
+        self.history.get(&key).copied().unwrap_or(0) as i64
+    }
+
+    /// Sort `moves` so that the most promising come first.
+    fn order(&self, turns: &Turns, moves: &mut [Move], ply: usize, tt_move: Option<&Move>) {
+        moves.sort_by_cached_key(|move_| Reverse(self.priority(turns, move_, ply, tt_move)));
+    }
+
+    /// Remember that `move_` caused a beta cutoff at `ply` with `depth` plies remaining.
+    fn record_cutoff(&mut self, move_: &Move, depth: usize, ply: usize) {
+        if !move_.travel.captures().is_empty() {
+            // captures are already ordered well enough by MVV-LVA.
+            return;
+        }
+
+        if self.killers.len() <= ply {
+            self.killers.resize_with(ply + 1, Default::default);
+        }
+        let killers = &mut self.killers[ply];
+        if killers[0].as_ref() != Some(move_) {
+            killers.rotate_right(1);
+            killers[0] = Some(move_.clone());
+        }
+
+        let key = (move_.travel.piece(), tile_index(move_.travel.dest()));
+        *self.history.entry(key).or_insert(0) += (depth * depth) as u64;
+    }
+}
+
+/// Bookkeeping for a single search.
+struct SearchState<'a> {
+    limits: &'a SearchLimits,
+    table: Option<&'a mut TranspositionTable>,
+    ordering: MoveOrdering,
+    started: f64,
+    nodes: u64,
+    aborted: bool,
+}
+
+impl<'a> SearchState<'a> {
+    fn new(limits: &'a SearchLimits, table: Option<&'a mut TranspositionTable>) -> Self {
+        Self {
+            limits,
+            table,
+            ordering: MoveOrdering::default(),
+            started: (limits.clock)(),
+            nodes: 0,
+            aborted: false,
+        }
+    }
+
+    /// Count a node, and return whether the search must stop.
+    ///
+    /// Once this returns true, it keeps returning true.
+    fn visit(&mut self) -> bool {
+        if self.aborted {
+            return true;
+        }
+        self.nodes += 1;
+
+        if let Some(max_nodes) = self.limits.nodes
+            && self.nodes > max_nodes
+        {
+            self.aborted = true;
+        }
+
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            if let Some(stop) = &self.limits.stop
+                && stop.load(Ordering::Relaxed)
+            {
+                self.aborted = true;
+            }
+            if let Some(time) = self.limits.time
+                && (self.limits.clock)() - self.started >= time
+            {
+                self.aborted = true;
+            }
+        }
+
+        self.aborted
+    }
+}
+
+/// Search code.
+impl Turns {
+    /// Search for the best move for `self.to_move` with iterative deepening.
+    ///
+    /// Uses a fresh [`TranspositionTable`] of the default size. The position is left
+    /// as it was found.
+    pub fn search(&mut self, limits: &SearchLimits) -> SearchResult {
+        self.search_with_table(limits, Some(&mut TranspositionTable::default()))
+    }
+
+    /// Like [`Self::search`], but with a caller-owned transposition table (which may be
+    /// reused between searches), or none at all.
+    pub fn search_with_table(
+        &mut self,
+        limits: &SearchLimits,
+        table: Option<&mut TranspositionTable>,
+    ) -> SearchResult {
+        let mut state = SearchState::new(limits, table);
+        let mut result = SearchResult {
+            best_move: None,
+            score: Score::NEG_INFINITY,
+            depth: 0,
+            nodes: 0,
+            pv: vec![],
+        };
+
+        let mut root_moves = self.all_moves();
+        if root_moves.is_empty() {
+            return result;
+        }
+        state.ordering.order(self, &mut root_moves, 0, None);
+
+        for depth in 1..=limits.max_depth() {
+            let mut alpha = Score::NEG_INFINITY;
+            let mut best: Option<(usize, Score)> = None;
+
+            for (i, move_) in root_moves.iter().enumerate() {
+                self.apply(move_);
+                let score = -self.negamax_ab(&mut state, depth - 1, 1, Score::NEG_INFINITY, -alpha);
+                self.unapply(move_);
+
+                if state.aborted {
+                    break;
+                }
+                if best.is_none_or(|(_, best_score)| score > best_score) {
+                    best = Some((i, score));
+                    alpha = alpha.max(score);
+                }
+            }
+
+            // A partially searched depth is still worth using: the previous best move is
+            // always searched first, so anything that beat it here is at least as good.
+            if let Some((i, score)) = best {
+                result.score = score;
+                let best_move = root_moves.remove(i);
+                root_moves.insert(0, best_move);
+                result.best_move = Some(root_moves[0].clone());
+            }
+            if !state.aborted {
+                result.depth = depth;
+            }
+
+            if best.is_some() {
+                result.nodes = state.nodes;
+                result.pv = self.principal_variation(state.table.as_deref(), &root_moves[0], depth);
+                if let Some(on_iteration) = &limits.on_iteration {
+                    on_iteration(self, &result);
+                }
+            }
+            if state.aborted {
+                break;
+            }
+        }
+
+        // Even if we were stopped immediately, we must answer with something.
+        if result.best_move.is_none() {
+            result.best_move = Some(root_moves[0].clone());
+            result.pv = vec![root_moves.swap_remove(0)];
+        }
+        result.nodes = state.nodes;
+        result
+    }
+
+    /// `first`, followed by the best replies stored in `table`, at most `max_len` moves.
+    fn principal_variation(
+        &mut self,
+        table: Option<&TranspositionTable>,
+        first: &Move,
+        max_len: usize,
+    ) -> Vec<Move> {
+        let mut pv = vec![first.clone()];
+        self.apply(first);
+        if let Some(table) = table {
+            while pv.len() < max_len {
+                let Some(move_) = table
+                    .probe(self.position_hash())
+                    .and_then(|entry| entry.best_move.clone())
+                else {
+                    break;
+                };
+                if !self.fits(&move_) {
+                    break;
+                }
+                self.apply(&move_);
+                pv.push(move_);
+            }
+        }
+        for move_ in pv.iter().rev() {
+            self.unapply(move_);
+        }
+        pv
+    }
+
+    /// Whether `move_` could have been generated in the current position.
+    ///
+    /// Cheap sanity checks only. Guards against playing moves from the table that
+    /// belong to another position with the same hash.
+    fn fits(&self, move_: &Move) -> bool {
+        let board = self.working_board_ref();
+        let own = |id| board.get(id).filter(|piece| piece.side() == self.to_move());
+        own(move_.travel.piece()).is_some_and(|piece| piece.center() == move_.travel.src())
+            && own(move_.rotate.piece).is_some_and(|piece| piece.angle() == move_.rotate.src)
+            && move_
+                .travel
+                .captures()
+                .iter()
+                .all(|&id| board.get(id).is_some())
+    }
+
+    /// Return the score we get in `depth` plies when minimizing our maximum loss.
+    ///
+    /// - "We" should be `self.to_move`.
+    /// - alpha is the highest score we already found. (if we see a score lower than it,
+    ///   no need to consider it.)
+    /// - beta is the best score we are able to get before the opponent is able to deny it
+    ///   with a reply we already found.
+    ///
+    /// `ply` is how far we are from the root. If the search is aborted, the returned
+    /// score is meaningless and should be thrown away.
+    fn negamax_ab(
+        &mut self,
+        state: &mut SearchState,
+        depth: usize,
+        ply: usize,
+        mut alpha: Score,
+        mut beta: Score,
+    ) -> Score {
+        if depth == 0 {
+            return self.quiesce(state, QUIESCENCE_DEPTH, alpha, beta);
+        }
+        if state.visit() {
+            return 0.;
+        }
+
+        let key = self.position_hash();
+        let alpha_orig = alpha;
+        let mut tt_move = None;
+        if let Some(entry) = state.table.as_deref().and_then(|table| table.probe(key)) {
+            if entry.depth >= depth {
+                match entry.bound {
+                    Bound::Exact => return entry.score,
+                    Bound::Lower => alpha = alpha.max(entry.score),
+                    Bound::Upper => beta = beta.min(entry.score),
+                }
+                if alpha >= beta {
+                    return entry.score;
+                }
+            }
+            tt_move = entry.best_move.clone();
+        }
+
+        let mut moves = self.all_moves();
+        state
+            .ordering
+            .order(self, &mut moves, ply, tt_move.as_ref());
+
+        let mut best_score = Score::NEG_INFINITY;
+        let mut best_move = None;
+
+        for move_ in moves {
+            self.apply(&move_);
+            let score = -self.negamax_ab(state, depth - 1, ply + 1, -beta, -alpha);
+            self.unapply(&move_);
+
+            if state.aborted {
+                return 0.;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(move_);
+                if score > alpha {
+                    alpha = score;
+                }
+            }
+            if score >= beta {
+                if let Some(move_) = &best_move {
+                    state.ordering.record_cutoff(move_, depth, ply);
+                }
+                break;
+            }
+        }
+
+        if let Some(table) = state.table.as_deref_mut() {
+            let bound = if best_score <= alpha_orig {
+                Bound::Upper
+            } else if best_score >= beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            table.store(TtEntry {
+                key,
+                depth,
+                bound,
+                score: best_score,
+                best_move,
+            });
+        }
+
+        best_score
+    }
+
+    /// Search only captures until the position is quiet, then evaluate.
+    ///
+    /// The side to move may always "stand pat" and decline to capture, so the static
+    /// evaluation is a lower bound. `qdepth` bounds how many more captures we follow.
+    fn quiesce(
+        &mut self,
+        state: &mut SearchState,
+        qdepth: usize,
+        mut alpha: Score,
+        beta: Score,
+    ) -> Score {
+        if state.visit() {
+            return 0.;
+        }
+
+        let stand_pat = self.eval();
+        if stand_pat >= beta || qdepth == 0 {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut captures = self.capture_moves();
+        captures.sort_by_cached_key(|move_| Reverse(mvv_lva(self, move_)));
+
+        let mut best_score = stand_pat;
+        for move_ in captures {
+            self.apply(&move_);
+            let score = -self.quiesce(state, qdepth - 1, -beta, -alpha);
+            self.unapply(&move_);
+
+            if state.aborted {
+                return 0.;
+            }
+
+            if score > best_score {
+                best_score = score;
+                alpha = alpha.max(score);
+            }
+            if score >= beta {
+                break;
+            }
+        }
+
+        best_score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        f32::consts::PI,
+        sync::{
+            Arc, Mutex,
+            atomic::{AtomicBool, Ordering},
+        },
+    };
+
+    use super::SearchLimits;
+    use crate::{
+        piece::{Piece, PieceKind, Pieces, Side},
+        transposition::TranspositionTable,
+        turn::Turns,
+    };
+
+    /// A small endgame, so that deeper searches finish quickly.
+    fn endgame() -> Turns {
+        Turns::with(Pieces::with(vec![
+            Piece::from_tile(0, (4, 7), 0., Side::White, PieceKind::King),
+            Piece::from_tile(1, (0, 7), 0., Side::White, PieceKind::Rook),
+            Piece::from_tile(2, (4, 0), 0., Side::Black, PieceKind::King),
+            Piece::from_tile(3, (2, 2), 0., Side::Black, PieceKind::Knight),
+            Piece::from_tile(4, (6, 3), 0., Side::Black, PieceKind::Pawn),
+        ]))
+    }
+
+    #[test]
+    fn depth_one_completes() {
+        let mut turns = Turns::with(Pieces::standard_board());
+        let result = turns.search(&SearchLimits::infinite().with_depth(1));
+        assert_eq!(result.depth, 1);
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn node_limit_still_answers() {
+        let mut turns = Turns::with(Pieces::standard_board());
+        let result = turns.search(&SearchLimits::infinite().with_nodes(10));
+        assert!(result.best_move.is_some());
+        assert!(result.nodes <= 11);
+    }
+
+    #[test]
+    fn stopped_search_still_answers() {
+        let stop = Arc::new(AtomicBool::new(true));
+        let mut turns = Turns::with(Pieces::standard_board());
+        let result = turns.search(&SearchLimits::infinite().with_stop(stop.clone()));
+        assert!(stop.load(Ordering::Relaxed));
+        assert!(result.best_move.is_some());
+        assert_eq!(result.depth, 0);
+    }
+
+    #[test]
+    fn quiescence_sees_recapture() {
+        // the pawn on d5 is defended by the rook on d8. taking it with the queen loses the queen.
+        const PAWN: usize = 3;
+        let mut turns = Turns::with(Pieces::with(vec![
+            Piece::from_tile(0, (7, 7), 0., Side::White, PieceKind::King),
+            Piece::from_tile(1, (3, 7), 0., Side::White, PieceKind::Queen),
+            Piece::from_tile(2, (0, 0), -PI, Side::Black, PieceKind::King),
+            Piece::from_tile(PAWN, (3, 3), -PI, Side::Black, PieceKind::Pawn),
+            Piece::from_tile(4, (3, 0), -PI, Side::Black, PieceKind::Rook),
+        ]));
+
+        let result = turns.search(&SearchLimits::infinite().with_depth(1));
+        let best_move = result.best_move.expect("white has moves");
+        assert!(
+            !best_move.travel.captures().contains(&PAWN),
+            "took a defended pawn with the queen: {best_move:?}"
+        );
+    }
+
+    #[test]
+    fn table_matches_no_table() {
+        for depth in 1..=3 {
+            let limits = SearchLimits::infinite().with_depth(depth);
+
+            let mut turns = endgame();
+            let without = turns.search_with_table(&limits, None);
+            let hash_before = turns.position_hash();
+
+            let mut table = TranspositionTable::new(1 << 12);
+            let with = turns.search_with_table(&limits, Some(&mut table));
+
+            assert_eq!(
+                hash_before,
+                turns.position_hash(),
+                "search must restore position"
+            );
+            assert_eq!(without.depth, depth);
+            assert_eq!(with.depth, depth);
+            assert_eq!(without.score, with.score, "scores differ at depth {depth}");
+        }
+    }
+
+    #[test]
+    fn warm_tables_save_nodes() {
+        // whether the table's moves help a cold search depends on the position, but
+        // searching again with what it learned always skips most of the tree.
+        for (mut turns, depth) in [(endgame(), 3), (Turns::with(Pieces::standard_board()), 2)] {
+            let limits = SearchLimits::infinite().with_depth(depth);
+            let mut table = TranspositionTable::new(1 << 12);
+            let cold = turns.search_with_table(&limits, Some(&mut table));
+            let warm = turns.search_with_table(&limits, Some(&mut table));
+            assert_eq!(cold.score, warm.score);
+            assert!(
+                warm.nodes < cold.nodes,
+                "the table saved nothing at depth {depth}: {} >= {}",
+                warm.nodes,
+                cold.nodes
+            );
+        }
+    }
+
+    #[test]
+    fn iterations_are_reported_with_a_pv() {
+        let depths = Arc::new(Mutex::new(vec![]));
+        let seen = depths.clone();
+        let limits = SearchLimits::infinite()
+            .with_depth(3)
+            .with_on_iteration(Arc::new(move |_, result| {
+                seen.lock().unwrap().push(result.depth);
+                assert_eq!(result.pv.first(), result.best_move.as_ref());
+                assert!(result.pv.len() <= result.depth);
+            }));
+
+        let mut turns = endgame();
+        let result = turns.search(&limits);
+        assert_eq!(*depths.lock().unwrap(), [1, 2, 3]);
+        assert!(result.pv.len() > 1, "{:?}", result.pv);
+    }
+
+    #[test]
+    fn table_survives_reuse() {
+        let limits = SearchLimits::infinite().with_depth(2);
+        let mut table = TranspositionTable::new(1 << 12);
+
+        let mut turns = endgame();
+        let first = turns.search_with_table(&limits, Some(&mut table));
+        let second = turns.search_with_table(&limits, Some(&mut table));
+        assert_eq!(first.score, second.score);
+    }
+}
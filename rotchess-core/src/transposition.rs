@@ -0,0 +1,91 @@
+//! A fixed-size transposition table for the search.
+//!
+//! The same position is often reached by different move orders (rotate-then-travel
+//! versus travel-then-rotate, for one). The table remembers what we learned about
+//! a position, keyed by [`Turns::position_hash`](crate::turn::Turns::position_hash),
+//! so we don't have to search it again.
+
+use crate::turn::{Move, Score};
+
+/// How a stored score relates to the true score of its position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bound {
+    /// The score is exact.
+    Exact,
+    /// The search failed high. The true score is at least this.
+    Lower,
+    /// The search failed low. The true score is at most this.
+    Upper,
+}
+
+/// What we remember about a searched position.
+#[derive(Debug, Clone)]
+pub struct TtEntry {
+    /// The full position hash, to tell apart positions that share a slot.
+    pub key: u64,
+    /// The depth in plies that the position was searched to.
+    pub depth: usize,
+    pub bound: Bound,
+    pub score: Score,
+    /// The best (or first refuting) move found, if any. Searched first next time.
+    pub best_move: Option<Move>,
+}
+
+/// The number of entries in a table made with [`TranspositionTable::default`].
+pub const DEFAULT_TABLE_SIZE: usize = 1 << 16;
+
+/// A fixed-size hash table of [`TtEntry`], indexed by position hash.
+///
+/// Collisions are resolved by replacement: an entry is overwritten by a different
+/// position, or by the same position searched at least as deep.
+pub struct TranspositionTable {
+    entries: Vec<Option<TtEntry>>,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_TABLE_SIZE)
+    }
+}
+
+impl TranspositionTable {
+    /// Create a table with room for `size` entries. `size` is clamped to at least 1.
+    pub fn new(size: usize) -> Self {
+        Self {
+            entries: vec![None; size.max(1)],
+        }
+    }
+
+    /// The number of entries the table has room for.
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Forget everything.
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+
+    /// Look up the entry for the position with hash `key`.
+    pub fn probe(&self, key: u64) -> Option<&TtEntry> {
+        self.entries[self.index(key)]
+            .as_ref()
+            .filter(|entry| entry.key == key)
+    }
+
+    /// Remember `entry`, possibly overwriting whatever shared its slot.
+    pub fn store(&mut self, entry: TtEntry) {
+        let idx = self.index(entry.key);
+        let slot = &mut self.entries[idx];
+        if slot
+            .as_ref()
+            .is_none_or(|old| old.key != entry.key || entry.depth >= old.depth)
+        {
+            *slot = Some(entry);
+        }
+    }
+}
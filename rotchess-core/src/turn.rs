@@ -1,16 +1,34 @@
 use std::f32::consts::PI;
 
-use crate::piece::{PieceId, Pieces, Side};
-
+use crate::{
+    annotation::Annotation,
+    eval::EvalWeights,
+    piece::{PieceId, PieceKind, Pieces, Side, TravelKind},
+    search::{SearchLimits, SearchResult},
+};
+
+#[derive(Clone)]
 pub struct Turns {
     working_board: Pieces,
     curr_turn: usize,
     turns: Vec<Pieces>,
+    /// The arrows and circles drawn on each saved turn.
+    annotations: Vec<Vec<Annotation>>,
     /// Whose turn it is.
     ///
     /// Update this manually, which is odd. Recall we have the playground style
     /// of board where turn order may not matter.
     to_move: Side,
+    /// Weights for the evaluation, in the search and [`Self::eval_breakdown`].
+    eval_weights: EvalWeights,
+}
+
+fn hash_with_side(board: &Pieces, side: Side) -> u64 {
+    const SIDE_KEY: u64 = 0x9e3779b97f4a7c15;
+    match side {
+        Side::White => board.position_hash(),
+        Side::Black => board.position_hash() ^ SIDE_KEY,
+    }
 }
 
 /// Generic turn methods.
@@ -20,18 +38,63 @@ impl Turns {
             working_board: pieces.clone(),
             curr_turn: 0,
             turns: vec![pieces],
+            annotations: vec![vec![]],
             to_move: Side::White,
+            eval_weights: EvalWeights::default(),
         }
     }
 
+    pub fn eval_weights(&self) -> &EvalWeights {
+        &self.eval_weights
+    }
+
+    /// Set the weights used to evaluate positions, and so, how the engine plays.
+    pub fn set_eval_weights(&mut self, weights: EvalWeights) {
+        self.eval_weights = weights;
+    }
+
     pub fn set_to_move(&mut self, side: Side) {
         self.to_move = side;
     }
 
+    /// Whose turn it is.
+    pub fn to_move(&self) -> Side {
+        self.to_move
+    }
+
     pub fn curr_turn(&self) -> usize {
         self.curr_turn
     }
 
+    /// How many turns are saved, counting the starting position.
+    pub fn turn_count(&self) -> usize {
+        self.turns.len()
+    }
+
+    /// Every saved turn's board, starting with the starting position.
+    pub fn history(&self) -> &[Pieces] {
+        &self.turns
+    }
+
+    /// A hash of the working board and the side to move.
+    ///
+    /// See [`Pieces::position_hash`].
+    pub fn position_hash(&self) -> u64 {
+        hash_with_side(&self.working_board, self.to_move)
+    }
+
+    /// Like [`Self::position_hash`], but of a saved turn, wherever we're looking.
+    ///
+    /// The side to move then is worked out assuming sides took turns.
+    pub fn turn_hash(&self, turn: usize) -> u64 {
+        let side = if (self.turns.len() - 1 - turn).is_multiple_of(2) {
+            self.to_move
+        } else {
+            self.to_move.toggled()
+        };
+        hash_with_side(&self.turns[turn], side)
+    }
+
     pub fn working_board_ref(&self) -> &Pieces {
         &self.working_board
     }
@@ -50,9 +113,11 @@ impl Turns {
         if self.turns.get(self.curr_turn + 1).is_some() {
             self.turns
                 .resize_with(self.curr_turn + 1, || unreachable!("see if guard"));
+            self.annotations.truncate(self.curr_turn + 1);
         }
 
         self.turns.push(self.working_board.clone());
+        self.annotations.push(vec![]);
         self.curr_turn += 1;
     }
 
@@ -90,11 +155,56 @@ impl Turns {
     }
 }
 
+/// Annotations.
+impl Turns {
+    /// The arrows and circles on the turn being looked at.
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations[self.curr_turn]
+    }
+
+    /// Like [`Self::annotations`], but of any saved turn.
+    pub fn annotations_at(&self, turn: usize) -> &[Annotation] {
+        &self.annotations[turn]
+    }
+
+    /// Draw `annotation` on the turn being looked at, or rub it out if it's already
+    /// there. One in the same place but another color is recolored instead.
+    ///
+    /// Returns whether it's there now.
+    pub fn toggle_annotation(&mut self, annotation: Annotation) -> bool {
+        let annotations = &mut self.annotations[self.curr_turn];
+        match annotations.iter().position(|a| a.same_place(&annotation)) {
+            Some(i) if annotations[i] == annotation => {
+                annotations.remove(i);
+                false
+            }
+            Some(i) => {
+                annotations[i] = annotation;
+                true
+            }
+            None => {
+                annotations.push(annotation);
+                true
+            }
+        }
+    }
+
+    /// Replace saved turn `turn`'s annotations.
+    pub fn set_annotations(&mut self, turn: usize, annotations: Vec<Annotation>) {
+        self.annotations[turn] = annotations;
+    }
+
+    /// Rub out everything drawn on the turn being looked at.
+    pub fn clear_annotations(&mut self) {
+        self.annotations[self.curr_turn].clear();
+    }
+}
+
 /// The maximum captures that might happen at once.
 pub const MAX_CAPTURES: usize = 4;
 
 /// The travel phase of a rotchess move.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct TravelPhase {
     /// The piece that travels
     piece: PieceId,
@@ -143,7 +253,7 @@ impl TravelPhase {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct RotationPhase {
     /// The piece that rotates.
     pub piece: PieceId,
@@ -156,7 +266,7 @@ pub struct RotationPhase {
 /// A rotchess move.
 ///
 /// These should capture both the forward and backward direction move.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Move {
     pub travel: TravelPhase,
     pub rotate: RotationPhase,
@@ -164,124 +274,70 @@ pub struct Move {
 
 /// Score for how good a position is as a float from positive to negative infinity.
 pub type Score = f32;
-/// depth of negamax search in plies
-const DEPTH: usize = 1;
 
 /// Engine code.
 impl Turns {
-    /// Returns the score, statically evaluated at the current position.
+    /// Make the best move where the player to move is `self.to_move`.
     ///
-    /// A float with more positive favoring the current player from `self.to_move`, 0 even.
-    fn eval(&self) -> Score {
-        let mult = match self.to_move {
-            Side::Black => -1.,
-            Side::White => 1.,
-        };
-        let mut ans = 0.0;
-        for piece in self.working_board.board_pieces() {
-            // add score value of each piece
-            ans +=
-                mult * match piece.side() {
-                    Side::Black => -1.,
-                    Side::White => 1.,
-                } * piece.kind().value()
-                    * 100.;
-
-            // penalize non-rotated pieces
-            ans +=
-                mult * match piece.side() {
-                    Side::Black => -1.,
-                    Side::White => 1.,
-                } * if piece.angle() == -PI || piece.angle() == 0. {
-                    -1.
-                } else {
-                    1.
-                };
-
-            // make pieces go toward center.
-            /// Center of the board in rotchess units.
-            const CENTER_X: f32 = 4.0;
-            /// Center of the board in rotchess units.
-            const CENTER_Y: f32 = 4.0;
-            ans +=
-                mult * match piece.side() {
-                    Side::Black => -1.,
-                    Side::White => 1.,
-                } * (5.0
-                    - Score::sqrt((piece.x() - CENTER_X).powi(2) + (piece.y() - CENTER_Y).powi(2)));
-        }
-        ans
+    /// Set `self.to_move` with [`Self::set_to_move`]. Searches with the default
+    /// [`SearchLimits`]; see [`Self::make_best_move_with`] to bound the search yourself.
+    pub fn make_best_move(&mut self) {
+        self.make_best_move_with(&SearchLimits::default());
     }
 
-    /// Return the score we get in `depth` plies when minimizing our maximum loss.
+    /// Make the best move found within `limits`, where the player to move is `self.to_move`.
     ///
-    /// - "We" should be `self.to_move`.
-    /// - alpha is the highest score we already found. (if we see a score lower than it,
-    ///   no need to consider it.)
-    /// - beta is the best score we are able to get before the opponent is able to deny it
-    ///   with a reply we already found.
-    fn negamax_ab(&mut self, depth: usize, mut alpha: Score, beta: Score) -> Score {
-        // println!("depth is {depth}");
-        if depth == 0 {
-            return self.eval();
-        }
-
-        let mut best_score = Score::NEG_INFINITY;
+    /// Returns the result of the search. If no move could be found (the side to move
+    /// has no pieces that can travel), nothing happens.
+    pub fn make_best_move_with(&mut self, limits: &SearchLimits) -> SearchResult {
+        self.turns[self.curr_turn].init_all_auxiliary_data();
 
-        for move_ in self.all_moves() {
-            self.apply(&move_);
-            let score = -self.negamax_ab(depth - 1, -beta, -alpha);
-            self.unapply(&move_);
+        let result = self.search(limits);
+        let Some(best_move) = result.best_move.as_ref() else {
+            return result;
+        };
 
-            if score > best_score {
-                best_score = score;
-                if score > alpha {
-                    alpha = score;
-                }
-            }
-            if score >= beta {
-                break;
-            }
-        }
+        self.play(best_move);
+        println!("best move was {best_move:#?}");
 
-        best_score
+        println!(
+            "best move had score {} at depth {} after {} nodes",
+            result.score, result.depth, result.nodes
+        );
+        let breakdown = self.eval_breakdown();
+        println!(
+            "current board state according to {:?}:\n{breakdown}",
+            self.to_move
+        );
+        result
     }
 
-    /// Make the best move where the player to move is `self.to_move`.
+    /// Play a move for `self.to_move` and save it as one turn.
     ///
-    /// Set `self.to_move` with [`Self::set_to_move`]
-    pub fn make_best_move(&mut self) {
-        let mut best_score: Score = Score::NEG_INFINITY;
-        let mut best_move: Option<Move> = None;
-
-        self.working_board.init_all_auxiliary_data();
-        self.turns[self.curr_turn].init_all_auxiliary_data();
-
-        for move_ in self.all_moves() {
-            self.apply(&move_);
-            let score = -self.negamax_ab(DEPTH, Score::NEG_INFINITY, Score::INFINITY);
-            self.unapply(&move_);
-
-            if score >= best_score {
-                best_score = score;
-                best_move = Some(move_);
-            }
-        }
-
-        self.apply(best_move.as_ref().expect("should've found a valid move."));
+    /// The move is trusted, not checked. Moves from [`crate::engine::Engine`]s for the
+    /// current position can always be played. Unlike in the search, pawns promote.
+    pub fn play(&mut self, move_: &Move) {
+        self.apply(move_);
+        self.working_board.promote(move_.travel.piece());
         self.save_turn();
-        println!("best move was {:#?}", best_move.unwrap());
+    }
 
-        println!("best move had score {best_score}");
-        println!(
-            "current board state has score {} according to {:?}",
-            self.eval(),
-            self.to_move
-        );
+    /// The side that has won by capturing the other side's king, if any.
+    pub fn winner(&self) -> Option<Side> {
+        let has_king = |side| {
+            self.working_board
+                .board_pieces()
+                .any(|piece| piece.side() == side && piece.kind() == PieceKind::King)
+        };
+        match (has_king(Side::White), has_king(Side::Black)) {
+            (true, false) => Some(Side::White),
+            (false, true) => Some(Side::Black),
+            _ => None,
+        }
     }
 
     /// Reverses effects of [`apply`][`Turns::apply`].
-    fn unapply(&mut self, move_: &Move) {
+    pub(crate) fn unapply(&mut self, move_: &Move) {
         self.working_board.unmake_move(move_);
 
         self.to_move = self.to_move.toggled();
@@ -294,7 +350,7 @@ impl Turns {
     /// happen if a user were to move.
     ///
     /// Also also we just trust the move. Full trust. It works.
-    fn apply(&mut self, move_: &Move) {
+    pub(crate) fn apply(&mut self, move_: &Move) {
         // println!("tomove is {:?}", self.to_move);
         debug_assert_eq!(
             self.working_board
@@ -319,7 +375,7 @@ impl Turns {
     /// Return all possible moves that the current player can make.
     ///
     /// Current player defined by `self.to_move`.
-    fn all_moves(&mut self) -> Vec<Move> {
+    pub(crate) fn all_moves(&mut self) -> Vec<Move> {
         self.working_board.init_all_auxiliary_data();
 
         let mut ans = vec![];
@@ -357,4 +413,36 @@ impl Turns {
         }
         ans
     }
+
+    /// Return the capturing travels that the current player can make.
+    ///
+    /// Used by quiescence search, where rotations are not considered: the rotation
+    /// phase of each move leaves the travelling piece's angle as it was.
+    pub(crate) fn capture_moves(&mut self) -> Vec<Move> {
+        self.working_board.init_all_auxiliary_data();
+
+        let mut ans = vec![];
+        for piece in self
+            .working_board_ref()
+            .board_pieces()
+            .filter(|piece| piece.side() == self.to_move)
+        {
+            for (tvk, x, y) in piece.travel_points_unchecked() {
+                if tvk != TravelKind::Capture {
+                    continue;
+                }
+                if let Some(travel) = self.working_board_ref().travelable(piece, x, y, tvk) {
+                    ans.push(Move {
+                        travel,
+                        rotate: RotationPhase {
+                            piece: piece.id(),
+                            src: piece.angle(),
+                            dest: piece.angle(),
+                        },
+                    });
+                }
+            }
+        }
+        ans
+    }
 }
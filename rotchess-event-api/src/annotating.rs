@@ -0,0 +1,64 @@
+//! Drawing arrows and circles with the right mouse button.
+//!
+//! Right-dragging from one point to another draws an arrow between them, and
+//! right-clicking draws a circle. Doing either again in the same color rubs it out.
+//! The color is picked by the [modifier keys](crate::Modifiers) held.
+//!
+//! Points snap to the middle of the piece under them, or otherwise the middle of
+//! their square, so that marks line up and can be found again. Annotations are kept
+//! per turn by [`Turns`](rotchess_core::turn::Turns), so they come and go as history
+//! is looked through.
+
+use rotchess_core::annotation::Annotation;
+
+use crate::{RotchessEmulator, ThingHappened};
+
+/// Annotating.
+impl RotchessEmulator {
+    /// The arrows and circles on the turn being looked at.
+    pub fn annotations(&self) -> &[Annotation] {
+        self.turns.annotations()
+    }
+
+    /// The arrow being right-dragged out, if any, from where it started to the
+    /// mouse. It's a circle until the mouse leaves the square.
+    pub fn drawing_arrow(&self) -> Option<Annotation> {
+        let from = self.annotating_from?;
+        let to = self.mark_point(self.mouse?);
+        Some(self.mark(from, to))
+    }
+
+    /// Rub out everything drawn on the turn being looked at.
+    pub fn clear_annotations(&mut self) {
+        self.turns.clear_annotations();
+    }
+
+    /// Where a mark at `point` goes.
+    pub(crate) fn mark_point(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let board = self.turns.working_board_ref();
+        match board.get_id(x, y).and_then(|id| board.get(id)) {
+            Some(piece) => piece.center(),
+            None => (x.floor() + 0.5, y.floor() + 0.5),
+        }
+    }
+
+    /// The mark dragged from `from` to `to`, in the color the modifiers pick.
+    fn mark(&self, from: (f32, f32), to: (f32, f32)) -> Annotation {
+        let color = self.modifiers.mark_color();
+        match from == to {
+            true => Annotation::Circle { at: from, color },
+            false => Annotation::Arrow { from, to, color },
+        }
+    }
+
+    /// Draw or rub out the mark dragged from `from` to `to`.
+    pub(crate) fn annotate(&mut self, from: (f32, f32), to: (f32, f32)) -> ThingHappened {
+        let annotation = self.mark(from, to);
+        let shown = self.turns.toggle_annotation(annotation);
+        ThingHappened::Annotated {
+            annotation,
+            turn: self.turns.curr_turn(),
+            shown,
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Engines in other processes.
+//!
+//! [`ExternalEngine`] starts a program that speaks the
+//! [protocol](rotchess_core::protocol) and drives it like any other [`Engine`], so a
+//! bot written in any language can be handed to [`RotchessEmulator::set_engine`].
+//!
+//! [`RotchessEmulator::set_engine`]: crate::RotchessEmulator::set_engine
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        atomic::Ordering,
+        mpsc::{self, Receiver, RecvTimeoutError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use rotchess_core::{
+    engine::Engine,
+    notation,
+    protocol::{self, Go, Info, PositionSetup, Reply},
+    search::SearchLimits,
+    turn::{Move, Turns},
+};
+
+/// How often we check the stop flag while the engine thinks.
+const STOP_POLL: Duration = Duration::from_millis(10);
+
+/// How long [`ExternalEngine::spawn`] waits for the engine to shake hands.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A child process speaking the protocol.
+///
+/// The process is told to quit when this is dropped.
+pub struct ExternalEngine {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    /// Replies, read off the child's stdout by a thread of their own so that we can
+    /// wait for them and watch the stop flag at once.
+    replies: Receiver<Reply>,
+    last_info: Option<Info>,
+}
+
+impl ExternalEngine {
+    /// Start `command` and shake hands with it, giving up after [`HANDSHAKE_TIMEOUT`].
+    ///
+    /// The command's stdin and stdout are taken over; its stderr is left alone.
+    pub fn spawn(command: &mut Command) -> io::Result<Self> {
+        Self::spawn_with_timeout(command, HANDSHAKE_TIMEOUT)
+    }
+
+    /// Like [`Self::spawn`], but waiting `timeout` for the handshake. An engine that
+    /// doesn't answer in time is killed.
+    pub fn spawn_with_timeout(command: &mut Command, timeout: Duration) -> io::Result<Self> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin was piped");
+        let stdout = child.stdout.take().expect("stdout was piped");
+
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                // lines we don't understand are ignored, per the protocol.
+                if let Ok(reply) = Reply::parse(&line)
+                    && sender.send(reply).is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let mut ans = Self {
+            name: String::from("external"),
+            child,
+            stdin,
+            replies,
+            last_info: None,
+        };
+        ans.send(&protocol::Command::Hello)?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let reply = match ans.replies.recv_timeout(left) {
+                Ok(reply) => reply,
+                Err(e) => {
+                    // or dropping it would wait on it forever.
+                    _ = ans.child.kill();
+                    return Err(match e {
+                        RecvTimeoutError::Timeout => io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            "engine didn't shake hands in time",
+                        ),
+                        RecvTimeoutError::Disconnected => {
+                            io::Error::new(io::ErrorKind::UnexpectedEof, "engine exited")
+                        }
+                    });
+                }
+            };
+            match reply {
+                Reply::Id { name } => ans.name = name,
+                Reply::HelloOk => return Ok(ans),
+                _ => {}
+            }
+        }
+    }
+
+    /// The last progress report of the last search, if the engine sent one.
+    pub fn last_info(&self) -> Option<&Info> {
+        self.last_info.as_ref()
+    }
+
+    fn send(&mut self, command: &protocol::Command) -> io::Result<()> {
+        writeln!(self.stdin, "{command}")?;
+        self.stdin.flush()
+    }
+
+    /// Ask for a move, returning the engine's answer as notation.
+    fn think(
+        &mut self,
+        turns: &Turns,
+        limits: &SearchLimits,
+    ) -> io::Result<Option<Vec<notation::Action>>> {
+        self.last_info = None;
+        self.send(&protocol::Command::Position(PositionSetup::current(turns)))?;
+        self.send(&protocol::Command::Go(Go::from_limits(limits)))?;
+
+        let mut stopped = false;
+        loop {
+            let reply = match self.replies.recv_timeout(STOP_POLL) {
+                Ok(reply) => reply,
+                Err(RecvTimeoutError::Timeout) => {
+                    if !stopped
+                        && let Some(stop) = &limits.stop
+                        && stop.load(Ordering::Relaxed)
+                    {
+                        self.send(&protocol::Command::Stop)?;
+                        stopped = true;
+                    }
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "engine exited",
+                    ));
+                }
+            };
+            match reply {
+                Reply::Info(info) => self.last_info = Some(info),
+                Reply::BestMove(actions) => return Ok(actions),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Engine for ExternalEngine {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Ask the process for a move. Moves that aren't legal, and engines that die,
+    /// are reported on stderr and count as having no move.
+    fn choose_move(&mut self, turns: &mut Turns, limits: &SearchLimits) -> Option<Move> {
+        let actions = match self.think(turns, limits) {
+            Ok(actions) => actions?,
+            Err(e) => {
+                eprintln!("{}: {e}", self.name);
+                return None;
+            }
+        };
+        match notation::resolve_move(&actions, turns) {
+            Ok(move_) => Some(move_),
+            Err(e) => {
+                eprintln!("{} sent an illegal move: {e}", self.name);
+                None
+            }
+        }
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        if self.send(&protocol::Command::Quit).is_err() {
+            _ = self.child.kill();
+        }
+        _ = self.child.wait();
+    }
+}
@@ -0,0 +1,144 @@
+//! Playing without a mouse.
+//!
+//! [`Event::SelectNextPiece`] and friends pick one of our pieces, then one of its
+//! travel points, and [`Event::Confirm`] travels there. [`Event::RotateBy`], say from
+//! a scroll wheel, and [`Event::RotateTo`] turn the selected piece instead, which
+//! [`Event::Confirm`] plays as a rotation. Any other event but mouse motion turns
+//! the piece back first.
+//!
+//! [`Event`]: crate::Event
+//! [`Event::SelectNextPiece`]: crate::Event::SelectNextPiece
+//! [`Event::Confirm`]: crate::Event::Confirm
+//! [`Event::RotateBy`]: crate::Event::RotateBy
+//! [`Event::RotateTo`]: crate::Event::RotateTo
+
+use rotchess_core::piece::PieceId;
+
+use crate::{Control, RotchessEmulator, ThingHappened, TravelPoint};
+
+/// Keyboard play.
+impl RotchessEmulator {
+    /// The travel point picked by [`Event::NextTravelPoint`](crate::Event::NextTravelPoint)
+    /// and friends, while its piece is selected.
+    pub fn focused_travelpoint(&self) -> Option<&TravelPoint> {
+        let (piece, idx) = self.focused_travelpoint?;
+        if self.selected_piece != Some(piece) {
+            return None;
+        }
+        self.travelpoints_buffer.get(idx)
+    }
+
+    /// The pieces keyboard events may select, across then down the board.
+    fn selectable(&self) -> Vec<PieceId> {
+        let side = match self.control {
+            Control::Anyone => self.turns.to_move(),
+            Control::Side(side) => side,
+            Control::Spectator => return vec![],
+        };
+        let mut pieces: Vec<_> = self
+            .pieces()
+            .filter(|piece| piece.side() == side)
+            .map(|piece| (piece.id(), piece.center()))
+            .collect();
+        pieces.sort_by(|(_, a), (_, b)| a.1.total_cmp(&b.1).then(a.0.total_cmp(&b.0)));
+        pieces.into_iter().map(|(id, _)| id).collect()
+    }
+
+    /// Select the piece after the selected one, or before it if not `forward`.
+    pub(crate) fn cycle_piece(&mut self, forward: bool) {
+        let pieces = self.selectable();
+        if pieces.is_empty() {
+            return;
+        }
+        let at = self
+            .selected_piece
+            .and_then(|selected| pieces.iter().position(|&id| id == selected));
+        let next = match (at, forward) {
+            (Some(at), true) => (at + 1) % pieces.len(),
+            (Some(at), false) => (at + pieces.len() - 1) % pieces.len(),
+            (None, true) => 0,
+            (None, false) => pieces.len() - 1,
+        };
+        self.selected_piece = Some(pieces[next]);
+        self.selected_travelpoint = None;
+        self.focused_travelpoint = None;
+        self.update_travelpoints_unchecked();
+    }
+
+    /// Whether the selected piece may be played right now, or premoved.
+    fn may_play_selected(&self) -> Option<PieceId> {
+        let id = self.selected_piece?;
+        (self.may_move(id) || self.may_premove(id)).then_some(id)
+    }
+
+    /// Pick the travel point after the picked one, or before it if not `forward`.
+    ///
+    /// Only travelable points are picked, unless we're premoving.
+    pub(crate) fn cycle_travelpoint(&mut self, forward: bool) {
+        let Some(id) = self.may_play_selected() else {
+            return;
+        };
+        let premoving = self.premoving();
+        let points: Vec<_> = (0..self.travelpoints_buffer.len())
+            .filter(|&idx| self.travelpoints_buffer[idx].travelable || premoving)
+            .collect();
+        if points.is_empty() {
+            return;
+        }
+        let at = match self.focused_travelpoint {
+            Some((piece, idx)) if piece == id => points.iter().position(|&i| i == idx),
+            _ => None,
+        };
+        let next = match (at, forward) {
+            (Some(at), true) => (at + 1) % points.len(),
+            (Some(at), false) => (at + points.len() - 1) % points.len(),
+            (None, true) => 0,
+            (None, false) => points.len() - 1,
+        };
+        self.focused_travelpoint = Some((id, points[next]));
+    }
+
+    /// Turn the selected piece to `angle`, remembering where it was.
+    pub(crate) fn rotate_selected_to(&mut self, angle: impl FnOnce(f32) -> f32) {
+        let Some(id) = self.may_play_selected() else {
+            return;
+        };
+        let piece = self.turns.working_board_mut().get_mut(id).expect("exists");
+        self.rotating_from.get_or_insert(piece.angle());
+        piece.set_angle(angle(piece.angle()));
+        self.update_travelpoints_unchecked();
+    }
+
+    /// Turn back a rotation that hasn't been confirmed, if there is one.
+    pub(crate) fn unrotate(&mut self) {
+        let (Some(angle), Some(id)) = (self.rotating_from.take(), self.selected_piece) else {
+            return;
+        };
+        if let Some(piece) = self.turns.working_board_mut().get_mut(id) {
+            piece.set_angle(angle);
+            self.update_travelpoints_unchecked();
+        }
+    }
+
+    /// Play the selected piece's rotation, or else travel it to the picked travel
+    /// point, finishing the turn.
+    pub(crate) fn confirm(&mut self) -> Option<ThingHappened> {
+        let id = self.may_play_selected()?;
+        if self.rotating_from.take().is_some() {
+            return Some(self.rotate_selected());
+        }
+        let tp = self.focused_travelpoint()?;
+        let (to, travelable) = ((tp.x, tp.y), tp.travelable);
+        let from = self
+            .turns
+            .working_board_ref()
+            .get(id)
+            .expect("exists")
+            .center();
+        self.focused_travelpoint = None;
+        if self.premoving() {
+            return Some(self.premove_selected(Some((from, to)), None));
+        }
+        travelable.then(|| self.travel_selected(from, to))
+    }
+}
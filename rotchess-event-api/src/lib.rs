@@ -3,9 +3,15 @@
 //! If you're adding rotchess to a new medium, this api is probably the fastest way
 //! to do it, as opposed to hand coding your own wrapper around `rotchess-core`.
 
+use std::collections::VecDeque;
+
 use rotchess_core::{
-    piece::{Piece, PieceId, Pieces, TravelKind},
-    turn::Turns,
+    annotation::{Annotation, MarkColor},
+    engine::{AlphaBetaEngine, Engine},
+    notation::{self, Action, NotationError},
+    piece::{Piece, PieceId, PieceKind, Pieces, Side, TravelKind},
+    search::SearchLimits,
+    turn::{Move, Turns},
 };
 
 #[derive(Debug)]
@@ -18,17 +24,56 @@ pub struct TravelPoint {
 
 pub use rotchess_core::piece;
 
+pub mod annotating;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod external;
+pub mod keyboard;
+pub mod premove;
+pub mod recording;
+pub mod thinking;
+pub mod touch;
+pub mod view;
+
+use premove::{Premove, PremoveError};
+use recording::Recorder;
+use thinking::Thinking;
+use touch::Touches;
+use view::View;
+
 /// Mouse buttons a chess board can respond to.
 ///
 /// This enum may add new variants.
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MouseButton {
     LEFT,
     RIGHT,
 }
 
+/// Modifier keys held down, which pick the color of [annotations](annotating).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    /// Green by default, red with shift, blue with ctrl and yellow with alt.
+    pub fn mark_color(&self) -> MarkColor {
+        match (self.shift, self.ctrl, self.alt) {
+            (true, _, _) => MarkColor::Red,
+            (_, true, _) => MarkColor::Blue,
+            (_, _, true) => MarkColor::Yellow,
+            _ => MarkColor::Green,
+        }
+    }
+}
+
 /// User events a chess board can respond to.
-#[derive(Clone, Copy)]
+///
+/// Points are in screen coordinates, turned into rotchess units by the emulator's
+/// [view](RotchessEmulator::set_view).
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Event {
     ButtonDown {
         x: f32,
@@ -52,6 +97,58 @@ pub enum Event {
     RotateUnchecked(PieceId, f32),
     /// We've been told to move the piece to x, y.
     MoveUnchecked(PieceId, f32, f32),
+    /// The modifier keys held changed.
+    Modifiers(Modifiers),
+    /// Select the next of our pieces, going across then down the board.
+    SelectNextPiece,
+    /// Select the previous of our pieces.
+    SelectPrevPiece,
+    /// Pick the selected piece's next travel point.
+    NextTravelPoint,
+    /// Pick the selected piece's previous travel point.
+    PrevTravelPoint,
+    /// Turn the selected piece by this many radians, say for a notch of the scroll
+    /// wheel. Nothing's played until [`Event::Confirm`].
+    RotateBy(f32),
+    /// Turn the selected piece to this angle. Nothing's played until [`Event::Confirm`].
+    RotateTo(f32),
+    /// Play the selected piece's rotation, or else travel it to the picked travel point.
+    Confirm,
+    /// Turn back the selected piece's rotation and deselect it.
+    Cancel,
+    /// A finger touched the screen. Ids tell fingers apart while they're down.
+    TouchStart {
+        id: u64,
+        x: f32,
+        y: f32,
+    },
+    /// A finger moved. See [`touch`] for what fingers do.
+    TouchMove {
+        id: u64,
+        x: f32,
+        y: f32,
+    },
+    /// A finger left the screen.
+    TouchEnd {
+        id: u64,
+        x: f32,
+        y: f32,
+    },
+}
+
+/// Whose pieces events may move. See [`RotchessEmulator::set_control`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Control {
+    /// Any piece, any time, like a playground. The default.
+    Anyone,
+    /// Only this side's pieces, on its turn, and only from the latest turn.
+    ///
+    /// For when the other side is played somewhere else, like over the network.
+    Side(Side),
+    /// Nobody's. Every event but going through history is ignored.
+    ///
+    /// For watching a game played somewhere else.
+    Spectator,
 }
 
 /// The main entrypoint for any rotchess user.
@@ -86,6 +183,32 @@ pub struct RotchessEmulator {
     selected_travelpoint: Option<(usize, f32, bool)>,
 
     turns: Turns,
+    /// The engine used by [`Self::make_best_move`]. Only `None` while it's away
+    /// [thinking](Self::start_thinking) on a thread of its own.
+    engine: Option<Box<dyn Engine>>,
+    /// The search going on in the background, if any.
+    thinking: Option<Thinking>,
+    /// Whose pieces events may move.
+    control: Control,
+    /// Where handled events are logged, if anywhere. See [`Self::start_recording`].
+    recorder: Option<Recorder>,
+    /// Where the board is on screen.
+    view: View,
+    /// Where the mouse was last seen, in rotchess units. See [`Self::hovered_piece`].
+    mouse: Option<(f32, f32)>,
+    /// Turns queued while waiting on the opponent. See [`Self::play_premove`].
+    premoves: VecDeque<Premove>,
+    /// The modifier keys held, as of the last [`Event::Modifiers`].
+    modifiers: Modifiers,
+    /// Where the right button went down, if it's still down. See [`Self::drawing_arrow`].
+    annotating_from: Option<(f32, f32)>,
+    /// The selected piece's angle before [`Event::RotateBy`] or [`Event::RotateTo`]
+    /// turned it, until it's confirmed.
+    rotating_from: Option<f32>,
+    /// The travel point picked by [`Event::NextTravelPoint`], and whose it is.
+    focused_travelpoint: Option<(PieceId, usize)>,
+    /// The fingers down, for [touch events](touch).
+    touches: Touches,
     // Uhhhh. theses should probably be abstracted in yet another struct for turn management, skull.
     // don't feel like doing it rn.
 }
@@ -105,8 +228,99 @@ impl RotchessEmulator {
             selected_piece_being_dragged: None,
             selected_travelpoint: None,
             turns: Turns::with(pieces),
+            engine: Some(Box::new(AlphaBetaEngine::default())),
+            thinking: None,
+            control: Control::Anyone,
+            recorder: None,
+            view: View::default(),
+            mouse: None,
+            premoves: VecDeque::new(),
+            modifiers: Modifiers::default(),
+            annotating_from: None,
+            rotating_from: None,
+            focused_travelpoint: None,
+            touches: Touches::default(),
         }
     }
+
+    /// Limit whose pieces mouse events may travel and rotate.
+    ///
+    /// Pieces that can't be moved can still be selected to look at their travel
+    /// points. The unchecked events, [`Self::play_actions`] and the engine methods
+    /// aren't limited, though a [`Control::Spectator`] ignores even the events.
+    pub fn set_control(&mut self, control: Control) {
+        self.control = control;
+    }
+
+    pub fn control(&self) -> Control {
+        self.control
+    }
+
+    /// Show the board some other way, say flipped for black. Event coordinates
+    /// are read through the view from then on.
+    pub fn set_view(&mut self, view: View) {
+        self.view = view;
+    }
+
+    pub fn view(&self) -> View {
+        self.view
+    }
+
+    /// Whether mouse events may move piece `id` right now.
+    fn may_move(&self, id: PieceId) -> bool {
+        match self.control {
+            Control::Anyone => true,
+            Control::Side(side) => {
+                self.turns.to_move() == side
+                    && self.turns.curr_turn() + 1 == self.turns.turn_count()
+                    && self
+                        .turns
+                        .working_board_ref()
+                        .get(id)
+                        .is_some_and(|piece| piece.side() == side)
+            }
+            Control::Spectator => false,
+        }
+    }
+
+    /// Replace the engine used by [`Self::make_best_move`] and friends, cancelling any
+    /// [thinking](Self::start_thinking) it was doing.
+    pub fn set_engine(&mut self, engine: Box<dyn Engine>) {
+        self.cancel_thinking();
+        self.engine = Some(engine);
+    }
+
+    /// Our engine, unless it's [thinking](Self::start_thinking) on a thread of its own.
+    pub fn engine(&self) -> Option<&dyn Engine> {
+        self.engine.as_deref()
+    }
+
+    /// Take our engine back, say to hand it to another game, cancelling any thinking
+    /// it was doing. The default engine is left in its place.
+    pub fn take_engine(&mut self) -> Box<dyn Engine> {
+        self.cancel_thinking();
+        self.engine
+            .replace(Box::new(AlphaBetaEngine::default()))
+            .expect("back once we've stopped thinking")
+    }
+
+    /// Whose turn it is.
+    pub fn to_move(&self) -> Side {
+        self.turns.to_move()
+    }
+
+    /// Hand the turn to `side`.
+    ///
+    /// Going back in history doesn't change whose turn it is, so a frontend
+    /// replaying from an earlier turn may need this.
+    pub fn set_to_move(&mut self, side: Side) {
+        self.turns.set_to_move(side);
+    }
+
+    /// The game so far.
+    pub fn turns(&self) -> &Turns {
+        &self.turns
+    }
 }
 
 /// Angle between from and to, given a pivot.
@@ -120,15 +334,82 @@ fn calc_angle_offset(pivot: (f32, f32), from: (f32, f32), to: (f32, f32)) -> f32
     to_angle - from_angle
 }
 
+/// What an event did, if anything worth telling a frontend about.
+#[derive(Debug, Clone, PartialEq)]
 pub enum ThingHappened {
     FirstTurn,
     PrevTurn,
     NextTurn,
     LastTurn,
-    /// We rotated the piece at usize to r
-    Rotate(PieceId, f32),
-    /// We moved the piece at usize to x, y
-    Move(PieceId, f32, f32),
+    /// A piece rotated, finishing a turn.
+    Rotate {
+        piece: PieceId,
+        /// The angle it rotated from.
+        from: f32,
+        to: f32,
+        /// The turn it made, as counted by [`Turns::curr_turn`].
+        turn: usize,
+        /// Whose turn it is now.
+        to_move: Side,
+    },
+    /// A piece traveled, finishing a turn.
+    Move {
+        piece: PieceId,
+        from: (f32, f32),
+        to: (f32, f32),
+        /// Every piece taken off the board, and what it was.
+        captured: Vec<(PieceId, PieceKind)>,
+        /// What the piece became, if it promoted.
+        promoted: Option<PieceKind>,
+        /// The turn it made, as counted by [`Turns::curr_turn`].
+        turn: usize,
+        /// Whose turn it is now.
+        to_move: Side,
+    },
+    /// An engine traveled a piece and rotated it, finishing a turn. See
+    /// [`RotchessEmulator::start_thinking`].
+    EngineMove {
+        piece: PieceId,
+        from: (f32, f32),
+        to: (f32, f32),
+        /// The angles it rotated from and to.
+        rotation: (f32, f32),
+        /// Every piece taken off the board, and what it was.
+        captured: Vec<(PieceId, PieceKind)>,
+        /// What the piece became, if it promoted.
+        promoted: Option<PieceKind>,
+        /// The turn it made, as counted by [`Turns::curr_turn`].
+        turn: usize,
+        /// Whose turn it is now.
+        to_move: Side,
+    },
+    /// A turn was queued to play once it's ours. See [`RotchessEmulator::play_premove`].
+    PremoveQueued(Premove),
+    /// A queued turn was played, finishing it.
+    PremovePlayed {
+        premove: Premove,
+        /// Every piece taken off the board, and what it was.
+        captured: Vec<(PieceId, PieceKind)>,
+        /// What the piece became, if it promoted.
+        promoted: Option<PieceKind>,
+        /// The turn it made, as counted by [`Turns::curr_turn`].
+        turn: usize,
+        /// Whose turn it is now.
+        to_move: Side,
+    },
+    /// A queued turn was thrown away, since it's no longer legal.
+    PremoveDiscarded {
+        premove: Premove,
+        reason: PremoveError,
+    },
+    /// An annotation was drawn, recolored or rubbed out.
+    Annotated {
+        annotation: Annotation,
+        /// The turn it's on, as counted by [`Turns::curr_turn`].
+        turn: usize,
+        /// Whether it's there now.
+        shown: bool,
+    },
 }
 
 /// Helpful functions for the update portion of a game loop implementing rotchess.
@@ -170,9 +451,164 @@ impl RotchessEmulator {
         }
     }
 
-    pub fn make_best_move(&mut self) {
-        self.turns.make_best_move();
+    /// Every piece on the board, and what it is.
+    fn on_board(&self) -> Vec<(PieceId, PieceKind)> {
+        let pieces = self.turns.working_board_ref();
+        pieces
+            .ids()
+            .filter_map(|id| Some((id, pieces.get(id)?.kind())))
+            .collect()
+    }
+
+    /// The pieces of `on_board` that have since been taken off it.
+    fn captured_since(&self, on_board: Vec<(PieceId, PieceKind)>) -> Vec<(PieceId, PieceKind)> {
+        let pieces = self.turns.working_board_ref();
+        on_board
+            .into_iter()
+            .filter(|&(id, _)| pieces.get(id).is_none())
+            .collect()
+    }
+
+    /// What piece `id` became, if it's no longer a `kind`.
+    fn promoted(&self, id: PieceId, kind: PieceKind) -> Option<PieceKind> {
+        let piece = self.turns.working_board_ref().get(id).expect("exists");
+        Some(piece.kind()).filter(|&k| k != kind)
+    }
+
+    /// Travel the selected piece from `from` to `to`, finishing the turn.
+    fn travel_selected(&mut self, from: (f32, f32), to: (f32, f32)) -> ThingHappened {
+        let piece_id = self // the idx of the piece that moves
+            .selected_piece
+            .expect("Invariant of selected_travelpoint.issome");
+        let on_board = self.on_board();
+        let pieces = self.turns.working_board_mut();
+        let piece = pieces.get(piece_id).expect("exists");
+        let (side, kind) = (piece.side(), piece.kind());
+        pieces.travel(piece_id, to.0, to.1);
+        let captured = self.captured_since(on_board);
+        let promoted = self.promoted(piece_id, kind);
+
+        self.update_travelpoints_unchecked();
         self.selected_piece = None;
+        self.selected_travelpoint = None;
+        self.turns.save_turn();
+        self.turns.set_to_move(side.toggled());
+        ThingHappened::Move {
+            piece: piece_id,
+            from,
+            to,
+            captured,
+            promoted,
+            turn: self.turns.curr_turn(),
+            to_move: self.turns.to_move(),
+        }
+    }
+
+    /// Rotate the selected piece to the angle it's been turned to on the working
+    /// board, finishing the turn. If we're [premoving](premove), it's turned back and
+    /// the rotation queued instead.
+    fn rotate_selected(&mut self) -> ThingHappened {
+        let piece_id = self.selected_piece.expect("a piece is selected");
+        // the turn we're on still has the piece as it was.
+        let from = self.turns.history()[self.turns.curr_turn()]
+            .get(piece_id)
+            .expect("exists")
+            .angle();
+        if self.premoving() {
+            // leave the piece as it was, and rotate it later.
+            let piece = self
+                .turns
+                .working_board_mut()
+                .get_mut(piece_id)
+                .expect("exists");
+            let to = piece.angle();
+            piece.set_angle(from);
+            self.update_travelpoints_unchecked();
+            return self.premove_selected(None, Some(to));
+        }
+        self.turns.save_turn();
+
+        let selected_piece = self
+            .turns
+            .working_board_ref()
+            .get(piece_id)
+            .expect("exists");
+        let side = selected_piece.side();
+        let to = selected_piece.angle();
+
+        self.turns.set_to_move(side);
+        self.turns.set_to_move(side.toggled());
+        ThingHappened::Rotate {
+            piece: piece_id,
+            from,
+            to,
+            turn: self.turns.curr_turn(),
+            to_move: self.turns.to_move(),
+        }
+    }
+
+    /// Let our [engine](Self::set_engine) make a move for the side to move.
+    ///
+    /// Returns the move played, if the engine found one.
+    pub fn make_best_move(&mut self) -> Option<Move> {
+        self.make_best_move_with(&SearchLimits::default())
+    }
+
+    /// Like [`Self::make_best_move`], but the search is bounded by `limits`.
+    ///
+    /// Any [thinking](Self::start_thinking) is cancelled first.
+    pub fn make_best_move_with(&mut self, limits: &SearchLimits) -> Option<Move> {
+        self.cancel_thinking();
+        self.unrotate();
+        let engine = self
+            .engine
+            .as_mut()
+            .expect("back once we've stopped thinking");
+        let move_ = engine.choose_move(&mut self.turns, limits);
+        if let Some(move_) = &move_ {
+            self.turns.play(move_);
+        }
+        self.selected_piece = None;
+        move_
+    }
+
+    /// Like [`Self::make_best_move_with`], but with someone else's engine.
+    ///
+    /// Useful when each side is played by a different engine.
+    pub fn make_engine_move(
+        &mut self,
+        engine: &mut dyn Engine,
+        limits: &SearchLimits,
+    ) -> Option<Move> {
+        self.unrotate();
+        let move_ = engine.choose_move(&mut self.turns, limits);
+        if let Some(move_) = &move_ {
+            self.turns.play(move_);
+        }
+        self.selected_piece = None;
+        move_
+    }
+
+    /// Replace the whole game, say with one loaded or sent from elsewhere.
+    pub fn set_turns(&mut self, turns: Turns) {
+        self.rotating_from = None;
+        self.selected_piece = None;
+        self.selected_travelpoint = None;
+        self.selected_piece_being_dragged = None;
+        self.turns = turns;
+    }
+
+    /// Play a turn written in [notation](rotchess_core::notation), like
+    /// `4.5,6.5>4.5,4.5` or `4.5,6.5:0.785`.
+    ///
+    /// Unlike the unchecked events, the actions must be legal for the side to move.
+    /// Nothing happens if any of them isn't.
+    pub fn play_actions(&mut self, actions: &[Action]) -> Result<(), NotationError> {
+        self.unrotate();
+        self.selected_piece = None;
+        self.selected_travelpoint = None;
+        self.selected_piece_being_dragged = None;
+        notation::play_actions(&mut self.turns, actions)
     }
 
     /// Handle an event.
@@ -184,6 +620,59 @@ impl RotchessEmulator {
     /// 1. piece selection
     /// 1. moves
     pub fn handle_event(&mut self, e: Event) -> Option<ThingHappened> {
+        let e = self.view.to_board_event(e);
+        let happened = self.handle(e);
+        if let Some(recorder) = &mut self.recorder {
+            recorder.push(e, happened.clone());
+        }
+        happened
+    }
+
+    fn handle(&mut self, e: Event) -> Option<ThingHappened> {
+        // even spectators get to point at things.
+        if let Event::MouseMotion { x, y }
+        | Event::ButtonDown { x, y, .. }
+        | Event::ButtonUp { x, y, .. } = e
+        {
+            self.mouse = Some((x, y));
+        }
+        let looking = matches!(
+            e,
+            Event::FirstTurn
+                | Event::PrevTurn
+                | Event::NextTurn
+                | Event::LastTurn
+                | Event::Modifiers(_)
+                // they're handled as the events they stand in for.
+                | Event::TouchStart { .. }
+                | Event::TouchMove { .. }
+                | Event::TouchEnd { .. }
+                | Event::ButtonDown {
+                    button: MouseButton::RIGHT,
+                    ..
+                }
+                | Event::ButtonUp {
+                    button: MouseButton::RIGHT,
+                    ..
+                }
+        );
+        if self.control == Control::Spectator && !looking {
+            return None;
+        }
+        let keeps_rotation = matches!(
+            e,
+            Event::RotateBy(_)
+                | Event::RotateTo(_)
+                | Event::Confirm
+                | Event::MouseMotion { .. }
+                | Event::Modifiers(_)
+                | Event::TouchStart { .. }
+                | Event::TouchMove { .. }
+                | Event::TouchEnd { .. }
+        );
+        if !keeps_rotation {
+            self.unrotate();
+        }
         match e {
             Event::MouseMotion { x, y } => {
                 // println!("dragged: {} {}", x, y);
@@ -198,7 +687,7 @@ impl RotchessEmulator {
                     // some godforsaken reason I made the 0 angle up.
                     let mouse_angle = -calc_angle_offset(
                         piece_center,
-                        (piece_center.0, piece_center.1 - 10.), // and also, up is the negative y axis because macroquad.
+                        (piece_center.0, piece_center.1 - 10.), // and also, up is the negative y axis, in rotchess units whatever the view.
                         (x, y),
                     );
                     piece.set_angle(mouse_angle + angle_offset);
@@ -220,38 +709,50 @@ impl RotchessEmulator {
                 y,
                 button: MouseButton::RIGHT,
             } => {
-                debug_assert!(
-                    self.selected_travelpoint.is_none(),
-                    "Should not be possible to buttondown without having the
-                    travel point be deselected already."
-                );
-
-                let idx_of_piece_at_xy = self.turns.working_board_ref().get_id(x, y);
-
-                // handle piece selection
-                match (idx_of_piece_at_xy, self.selected_piece) {
-                    (Some(new_i), Some(curr_sel_i)) => {
-                        // we clicked on a piece, and a piece is already selected.
-                        if new_i == curr_sel_i {
-                            // we clicked on the already-selected piece, deselect it.
-                            self.selected_piece = None;
-                        } else {
-                            // we clicked on a different piece, select that instead.
-                            self.selected_piece = Some(new_i);
-                            self.update_travelpoints_unchecked();
-                        }
-                        return None;
-                    }
-                    (Some(new_i), None) => {
-                        // we clicked on a piece, and None pieces were selected.
-                        self.selected_piece = Some(new_i);
-                        self.update_travelpoints_unchecked();
-                        return None;
-                    }
-                    (None, _) => {
-                        self.selected_piece = None;
-                    }
+                self.annotating_from = Some(self.mark_point((x, y)));
+                None
+            }
+            Event::ButtonUp {
+                x,
+                y,
+                button: MouseButton::RIGHT,
+            } => {
+                let from = self.annotating_from.take()?;
+                Some(self.annotate(from, self.mark_point((x, y))))
+            }
+            Event::Modifiers(modifiers) => {
+                self.modifiers = modifiers;
+                None
+            }
+            Event::SelectNextPiece | Event::SelectPrevPiece => {
+                self.cycle_piece(e == Event::SelectNextPiece);
+                None
+            }
+            Event::NextTravelPoint | Event::PrevTravelPoint => {
+                self.cycle_travelpoint(e == Event::NextTravelPoint);
+                None
+            }
+            Event::RotateBy(delta) => {
+                self.rotate_selected_to(|angle| angle + delta);
+                None
+            }
+            Event::RotateTo(angle) => {
+                self.rotate_selected_to(|_| angle);
+                None
+            }
+            Event::Confirm => self.confirm(),
+            Event::TouchStart { .. } | Event::TouchMove { .. } | Event::TouchEnd { .. } => {
+                self.touch(e)
+            }
+            Event::Cancel => {
+                if let Some(center) = self.selected_piece_being_dragged.take() {
+                    let id = self.selected_piece.expect("dragged pieces are selected");
+                    let piece = self.turns.working_board_mut().get_mut(id).expect("exists");
+                    piece.set_center(center);
                 }
+                self.selected_piece = None;
+                self.selected_travelpoint = None;
+                self.focused_travelpoint = None;
                 None
             }
             Event::ButtonDown {
@@ -273,7 +774,9 @@ impl RotchessEmulator {
                 // if we click a travelpoint, store in emulator data that we've sel'd a tvp
                 // with such an angle offset from our mousepos to the tvp center
                 let pieces = &mut self.turns.working_board_ref();
-                if let Some(sel_idx) = self.selected_piece {
+                if let Some(sel_idx) = self.selected_piece
+                    && (self.may_move(sel_idx) || self.may_premove(sel_idx))
+                {
                     for (tvp_idx, tp) in self.travelpoints_buffer.iter().enumerate() {
                         if Piece::collidepoint_generic(x, y, tp.x, tp.y) {
                             self.selected_travelpoint = Some((
@@ -302,6 +805,10 @@ impl RotchessEmulator {
                 if let Some(idx) = idx_of_piece_at_xy {
                     self.selected_piece = Some(idx);
                     self.update_travelpoints_unchecked();
+                    if !self.may_move(idx) && !self.may_premove(idx) {
+                        // look, but don't touch.
+                        return None;
+                    }
                     self.selected_piece_being_dragged = Some(
                         self.turns
                             .working_board_ref()
@@ -327,29 +834,24 @@ impl RotchessEmulator {
                 // if we're dragging and we just released, remember to mark no longer selected_piece_being_dragged
                 if let Some(orig_center) = self.selected_piece_being_dragged {
                     // if we can find a travelable tvp under the mouse that we just released, travel. otherwise, snap back to orig_center.
+                    // premoves may go anywhere the piece could travel, since they're checked later.
+                    let premoving = self.premoving();
                     if let Some(tvp_idx) = self.travelpoints_buffer.iter().position(|tvp| {
-                        tvp.travelable && Piece::collidepoint_generic(tvp.x, tvp.y, x, y)
+                        (tvp.travelable || premoving)
+                            && Piece::collidepoint_generic(tvp.x, tvp.y, x, y)
                     }) {
                         let tvp = &self.travelpoints_buffer[tvp_idx];
-                        let (tvp_x, tvp_y) = (tvp.x, tvp.y);
-
-                        let pieces = &mut self.turns.working_board_mut();
-                        let piece_id = self // the idx of the piece that moves
-                            .selected_piece
-                            .expect("Invariant of selected_travelpoint.issome");
-                        let piece_color_that_just_moved =
-                            pieces.get(piece_id).expect("exists").side();
-                        pieces.travel(piece_id, tvp_x, tvp_y);
-                        pieces.get(piece_id).expect("exists");
-                        self.selected_piece = Some(piece_id);
-                        self.update_travelpoints_unchecked();
-                        self.selected_piece = None;
-                        self.selected_travelpoint = None;
-                        self.turns.save_turn();
-                        self.turns
-                            .set_to_move(piece_color_that_just_moved.toggled());
+                        let to = (tvp.x, tvp.y);
                         self.selected_piece_being_dragged = None;
-                        return Some(ThingHappened::Move(piece_id, tvp_x, tvp_y));
+                        if premoving {
+                            self.turns
+                                .working_board_mut()
+                                .get_mut(self.selected_piece.expect("invt of selpiecebeingdrag"))
+                                .expect("exists from selpiece")
+                                .set_center(orig_center);
+                            return Some(self.premove_selected(Some((orig_center, to)), None));
+                        }
+                        return Some(self.travel_selected(orig_center, to));
                     } else {
                         self.turns
                             .working_board_mut()
@@ -367,45 +869,28 @@ impl RotchessEmulator {
                     let (tp_x, tp_y) = (tp.x, tp.y);
                     debug_assert!(Piece::collidepoint_generic(x, y, tp_x, tp_y));
 
-                    if tp.travelable {
+                    if tp.travelable || self.premoving() {
                         // if it is indeed travelable, travel.
-                        let pieces = &mut self.turns.working_board_mut();
-                        let piece_id = self // the idx of the piece that moves
-                            .selected_piece
-                            .expect("Invariant of selected_travelpoint.issome");
-                        let piece_color_that_just_moved =
-                            pieces.get(piece_id).expect("exists").side();
-                        pieces.travel(piece_id, tp_x, tp_y);
-                        self.selected_piece = Some(piece_id);
-                        self.update_travelpoints_unchecked();
-                        self.selected_piece = None;
-                        self.selected_travelpoint = None;
-                        self.turns.save_turn();
-                        self.turns
-                            .set_to_move(piece_color_that_just_moved.toggled());
-                        return Some(ThingHappened::Move(piece_id, tp_x, tp_y));
+                        let from = self
+                            .turns
+                            .working_board_ref()
+                            .get(
+                                self.selected_piece
+                                    .expect("Invariant of selected_travelpoint.issome"),
+                            )
+                            .expect("exists")
+                            .center();
+                        if self.premoving() {
+                            return Some(self.premove_selected(Some((from, (tp_x, tp_y))), None));
+                        }
+                        return Some(self.travel_selected(from, (tp_x, tp_y)));
                     }
                     self.selected_travelpoint = None;
                 }
 
                 if let Some((_, _, true)) = self.selected_travelpoint {
                     self.selected_travelpoint = None;
-                    self.turns.save_turn();
-
-                    let piece_id = self
-                        .selected_piece
-                        .expect("Invariant of sel travelpt.is_some");
-                    let selected_piece = self
-                        .turns
-                        .working_board_ref()
-                        .get(piece_id)
-                        .expect("exists");
-                    let side = selected_piece.side();
-                    let angle = selected_piece.angle();
-
-                    self.turns.set_to_move(side);
-                    self.turns.set_to_move(side.toggled());
-                    return Some(ThingHappened::Rotate(piece_id, angle));
+                    return Some(self.rotate_selected());
                 }
 
                 None
@@ -486,7 +971,6 @@ impl RotchessEmulator {
                 self.turns.set_to_move(side.toggled());
                 None
             }
-            _ => None,
         }
     }
 }
@@ -511,4 +995,30 @@ impl RotchessEmulator {
             )
         })
     }
+
+    /// The piece under the mouse, if any.
+    pub fn hovered_piece(&self) -> Option<&Piece> {
+        let (x, y) = self.mouse?;
+        let board = self.turns.working_board_ref();
+        board.get(board.get_id(x, y)?)
+    }
+
+    /// The selected piece's travel point under the mouse, if any.
+    ///
+    /// Where travel points overlap, a travelable one is picked, like when dropping
+    /// a dragged piece.
+    pub fn hovered_travelpoint(&self) -> Option<&TravelPoint> {
+        let (x, y) = self.mouse?;
+        let (_, travelpoints) = self.selected()?;
+        let mut under_mouse = travelpoints
+            .iter()
+            .filter(|tp| Piece::collidepoint_generic(x, y, tp.x, tp.y));
+        let first = under_mouse.next()?;
+        Some(
+            std::iter::once(first)
+                .chain(under_mouse)
+                .find(|tp| tp.travelable)
+                .unwrap_or(first),
+        )
+    }
 }
@@ -0,0 +1,182 @@
+//! Turns queued up while waiting on the opponent.
+//!
+//! With [`Control::Side`], travelling or rotating one of our pieces while it's the
+//! other side's turn doesn't play anything. It queues a [`Premove`] instead, and the
+//! piece snaps back. Once the opponent's move is in, [`RotchessEmulator::play_premove`]
+//! plays the first premove, or throws it away if it's no longer legal.
+//!
+//! Premoves are made against the board as it is, not as the queue will leave it.
+
+use std::fmt;
+
+use rotchess_core::piece::{Piece, PieceId};
+
+use crate::{Control, RotchessEmulator, ThingHappened};
+
+/// A turn queued to play once it's ours.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Premove {
+    pub piece: PieceId,
+    /// Where it travels from and to, if it travels.
+    pub travel: Option<((f32, f32), (f32, f32))>,
+    /// The angle it rotates to, after any travel, if it rotates.
+    pub rotation: Option<f32>,
+}
+
+/// Why a premove was thrown away.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PremoveError {
+    /// The piece was captured.
+    Captured,
+    /// The travel isn't legal anymore, by [`Pieces::travelable`](rotchess_core::piece::Pieces::travelable).
+    NotTravelable,
+    /// The game's over.
+    GameOver,
+}
+
+impl fmt::Display for PremoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PremoveError::Captured => write!(f, "the piece was captured"),
+            PremoveError::NotTravelable => write!(f, "the piece can't travel there anymore"),
+            PremoveError::GameOver => write!(f, "the game's over"),
+        }
+    }
+}
+
+impl std::error::Error for PremoveError {}
+
+/// Premoves.
+impl RotchessEmulator {
+    /// Queue a turn to play once it's ours.
+    ///
+    /// It's checked when it's played, not now.
+    pub fn queue_premove(&mut self, premove: Premove) {
+        self.premoves.push_back(premove);
+    }
+
+    /// Every queued premove, the next to play first.
+    pub fn premoves(&self) -> impl Iterator<Item = &Premove> {
+        self.premoves.iter()
+    }
+
+    /// Throw away every queued premove.
+    pub fn clear_premoves(&mut self) {
+        self.premoves.clear();
+    }
+
+    /// Whether events queue premoves rather than play, since we're waiting on the
+    /// opponent.
+    pub(crate) fn premoving(&self) -> bool {
+        match self.control {
+            Control::Side(side) => {
+                self.turns.to_move() != side
+                    && self.turns.curr_turn() + 1 == self.turns.turn_count()
+                    && self.turns.winner().is_none()
+            }
+            Control::Anyone | Control::Spectator => false,
+        }
+    }
+
+    /// Whether mouse events may premove piece `id` right now.
+    pub(crate) fn may_premove(&self, id: PieceId) -> bool {
+        let Control::Side(side) = self.control else {
+            return false;
+        };
+        self.premoving()
+            && self
+                .turns
+                .working_board_ref()
+                .get(id)
+                .is_some_and(|piece| piece.side() == side)
+    }
+
+    /// Queue a premove of the selected piece, deselecting it.
+    pub(crate) fn premove_selected(
+        &mut self,
+        travel: Option<((f32, f32), (f32, f32))>,
+        rotation: Option<f32>,
+    ) -> ThingHappened {
+        let premove = Premove {
+            piece: self.selected_piece.expect("a piece is selected"),
+            travel,
+            rotation,
+        };
+        self.queue_premove(premove.clone());
+        self.selected_piece = None;
+        self.selected_travelpoint = None;
+        self.selected_piece_being_dragged = None;
+        ThingHappened::PremoveQueued(premove)
+    }
+
+    /// Play the first queued premove, if it's our turn, or throw it away if it's no
+    /// longer legal. Call it once the opponent's move is in.
+    ///
+    /// Nothing happens while looking back through history, or holding or turning a piece. Once
+    /// the game's over, every premove is thrown away, one call at a time.
+    pub fn play_premove(&mut self) -> Option<ThingHappened> {
+        let Control::Side(side) = self.control else {
+            return None;
+        };
+        let latest = self.turns.curr_turn() + 1 == self.turns.turn_count();
+        let holding = self.selected_piece_being_dragged.is_some()
+            || self.selected_travelpoint.is_some()
+            || self.rotating_from.is_some();
+        if self.turns.to_move() != side || !latest || holding {
+            return None;
+        }
+        let premove = self.premoves.pop_front()?;
+        let discard = |reason| {
+            Some(ThingHappened::PremoveDiscarded {
+                premove: premove.clone(),
+                reason,
+            })
+        };
+
+        if self.turns.winner().is_some() {
+            return discard(PremoveError::GameOver);
+        }
+        let Some(piece) = self.turns.working_board_mut().get_mut(premove.piece) else {
+            return discard(PremoveError::Captured);
+        };
+        let kind = piece.kind();
+        let mut dest = None;
+        if let Some((_, to)) = premove.travel {
+            piece.init_auxiliary_data();
+            let board = self.turns.working_board_ref();
+            let piece = board.get(premove.piece).expect("we just found it");
+            let Some(travel) = piece
+                .travel_points_unchecked()
+                .filter(|&(_, x, y)| Piece::collidepoint_generic(to.0, to.1, x, y))
+                .find_map(|(tvk, x, y)| board.travelable(piece, x, y, tvk))
+            else {
+                return discard(PremoveError::NotTravelable);
+            };
+            dest = Some(travel.dest());
+        }
+
+        let on_board = self.on_board();
+        self.selected_piece = None;
+        let pieces = self.turns.working_board_mut();
+        if let Some((x, y)) = dest {
+            pieces.travel(premove.piece, x, y);
+        }
+        if let Some(angle) = premove.rotation {
+            pieces
+                .get_mut(premove.piece)
+                .expect("own pieces can't capture themselves")
+                .set_angle(angle);
+        }
+        let captured = self.captured_since(on_board);
+        let promoted = self.promoted(premove.piece, kind);
+        self.turns.save_turn();
+        self.turns.set_to_move(side.toggled());
+        Some(ThingHappened::PremovePlayed {
+            premove,
+            captured,
+            promoted,
+            turn: self.turns.curr_turn(),
+            to_move: self.turns.to_move(),
+        })
+    }
+}
@@ -0,0 +1,770 @@
+//! Recording the events an emulator is handed, so that glitches can be replayed.
+//!
+//! Start a recording with [`RotchessEmulator::start_recording`] and every event
+//! handled from then on is logged with when it came and what it made happen. The
+//! [`EventLog`] can be written out, attached to a bug report, read back in and
+//! [replayed](EventLog::replay) on a fresh emulator.
+//!
+//! ```text
+//! control anyone
+//! turn w WR@0.5,7.5:0 ...
+//! looking 0
+//! 0 down 3.5,6.5 left
+//! 0.25 motion 3.5,5.5
+//! 0.5 up 3.5,4.5 left => move 7 3.5,6.5>3.5,4.5 - - 1 b
+//! end b WR@0.5,7.5:0 ...
+//! ```
+//!
+//! The game is written as every saved turn's position, one `turn` per line, each
+//! followed by its [annotations](crate::annotating) on a `marks` line if it has any,
+//! then the turn being looked at. Each event is the seconds since the recording started, the
+//! event, and what happened, if anything. A move's captures are written like `12P,13N`,
+//! or `-` for none, followed by what it promoted to, again or `-`. A premove is written
+//! as its piece, travel and angle, like `7 3.5,6.5>3.5,4.5 -`. Coordinates are written like
+//! [notation](rotchess_core::notation), so they read back exactly.
+//!
+//! Events are logged as the emulator saw them, in rotchess units after the
+//! [view](crate::view), so replays don't depend on where the board was on screen.
+//!
+//! Only events are recorded. A game changed some other way while recording, say by
+//! [`RotchessEmulator::play_actions`] or an engine, won't replay.
+
+use std::{fmt, str::FromStr};
+
+use rotchess_core::{
+    annotation::{self, Annotation},
+    notation::{self, NotationError},
+    piece::{PieceId, PieceKind, Pieces, Side},
+    search::Clock,
+    turn::Turns,
+};
+
+use crate::{
+    Control, Event, Modifiers, MouseButton, RotchessEmulator, ThingHappened,
+    premove::{Premove, PremoveError},
+};
+
+/// One event, as it was handled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedEvent {
+    /// Seconds since the recording started.
+    pub at: f64,
+    pub event: Event,
+    pub happened: Option<ThingHappened>,
+}
+
+/// A game, and the events handed to an emulator playing it.
+#[derive(Clone)]
+pub struct EventLog {
+    control: Control,
+    /// Every saved turn, starting with the starting position.
+    history: Vec<Pieces>,
+    /// What was drawn on each saved turn.
+    annotations: Vec<Vec<Annotation>>,
+    /// The turn being looked at.
+    looking: usize,
+    to_move: Side,
+    events: Vec<RecordedEvent>,
+    /// Where the game ended up, once the recording has stopped.
+    end: Option<(Pieces, Side)>,
+}
+
+/// Why a replay didn't go like the recording.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+    /// Event `index` made something else happen. Both are written like the log,
+    /// or `nothing`.
+    Diverged {
+        index: usize,
+        recorded: String,
+        replayed: String,
+    },
+    /// Every event did the same, but the game ended up somewhere else. Both are
+    /// positions in notation.
+    EndedElsewhere { recorded: String, replayed: String },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Diverged {
+                index,
+                recorded,
+                replayed,
+            } => write!(
+                f,
+                "event {index} should've made {recorded} happen, but made {replayed} happen"
+            ),
+            ReplayError::EndedElsewhere { recorded, replayed } => {
+                write!(
+                    f,
+                    "the game should've ended at {recorded}, but ended at {replayed}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// A recording in progress.
+pub(crate) struct Recorder {
+    log: EventLog,
+    clock: Clock,
+    started: f64,
+}
+
+impl Recorder {
+    pub(crate) fn push(&mut self, event: Event, happened: Option<ThingHappened>) {
+        self.log.events.push(RecordedEvent {
+            at: (self.clock)() - self.started,
+            event,
+            happened,
+        });
+    }
+}
+
+/// Recording and replaying.
+impl RotchessEmulator {
+    /// Start logging every event handled, timed by `clock`.
+    ///
+    /// Any recording already going is thrown away.
+    pub fn start_recording(&mut self, clock: Clock) {
+        let turns = self.turns();
+        self.recorder = Some(Recorder {
+            log: EventLog {
+                control: self.control(),
+                history: turns.history().to_vec(),
+                annotations: (0..turns.turn_count())
+                    .map(|turn| turns.annotations_at(turn).to_vec())
+                    .collect(),
+                looking: turns.curr_turn(),
+                to_move: turns.to_move(),
+                events: vec![],
+                end: None,
+            },
+            clock,
+            started: clock(),
+        });
+    }
+
+    /// The recording so far, if we're recording.
+    pub fn recording(&self) -> Option<&EventLog> {
+        self.recorder.as_ref().map(|recorder| &recorder.log)
+    }
+
+    /// Stop recording, noting where the game ended up.
+    pub fn stop_recording(&mut self) -> Option<EventLog> {
+        let mut log = self.recorder.take()?.log;
+        log.end = Some((self.turns().working_board_ref().clone(), self.to_move()));
+        Some(log)
+    }
+}
+
+impl EventLog {
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    /// A fresh emulator, at the game the recording started from.
+    pub fn emulator(&self) -> RotchessEmulator {
+        let mut turns = Turns::with(self.history[0].clone());
+        for board in &self.history[1..] {
+            turns.working_board_mut().clone_from(board);
+            turns.save_turn();
+        }
+        for (turn, annotations) in self.annotations.iter().enumerate() {
+            turns.set_annotations(turn, annotations.clone());
+        }
+        turns.first();
+        for _ in 0..self.looking {
+            _ = turns.next();
+        }
+        turns.set_to_move(self.to_move);
+
+        let mut emulator = RotchessEmulator::with(self.history[0].clone());
+        emulator.set_turns(turns);
+        emulator.set_control(self.control);
+        emulator
+    }
+
+    /// Hand every event to a [fresh emulator](Self::emulator), checking each makes
+    /// the same thing happen and that the game ends up in the same place.
+    ///
+    /// Returns the emulator, for a closer look.
+    pub fn replay(&self) -> Result<RotchessEmulator, ReplayError> {
+        let mut emulator = self.emulator();
+        for (index, recorded) in self.events.iter().enumerate() {
+            let replayed = emulator.handle_event(recorded.event);
+            if replayed != recorded.happened {
+                let write = |happened: &Option<ThingHappened>| match happened {
+                    Some(happened) => happened.to_string(),
+                    None => String::from("nothing"),
+                };
+                return Err(ReplayError::Diverged {
+                    index,
+                    recorded: write(&recorded.happened),
+                    replayed: write(&replayed),
+                });
+            }
+        }
+        if let Some((board, to_move)) = &self.end {
+            let recorded = notation::write_position(board, *to_move);
+            let replayed =
+                notation::write_position(emulator.turns().working_board_ref(), emulator.to_move());
+            if recorded != replayed {
+                return Err(ReplayError::EndedElsewhere { recorded, replayed });
+            }
+        }
+        Ok(emulator)
+    }
+}
+
+fn malformed(text: &str) -> NotationError {
+    NotationError::Malformed(text.to_string())
+}
+
+fn write_point((x, y): (f32, f32)) -> String {
+    format!("{x},{y}")
+}
+
+fn parse_number<T: FromStr>(text: Option<&str>) -> Result<T, NotationError> {
+    let text = text.unwrap_or_default();
+    text.parse().map_err(|_| malformed(text))
+}
+
+/// Sides to move are lowercase, like in positions.
+fn write_side(side: Side) -> &'static str {
+    match side {
+        Side::White => "w",
+        Side::Black => "b",
+    }
+}
+
+fn parse_side(text: Option<&str>) -> Result<Side, NotationError> {
+    match text {
+        Some("w") => Ok(Side::White),
+        Some("b") => Ok(Side::Black),
+        text => Err(malformed(text.unwrap_or_default())),
+    }
+}
+
+fn parse_kind(text: Option<&str>) -> Result<PieceKind, NotationError> {
+    let text = text.unwrap_or_default();
+    let mut chars = text.chars();
+    match (
+        chars.next().and_then(notation::kind_from_letter),
+        chars.next(),
+    ) {
+        (Some(kind), None) => Ok(kind),
+        _ => Err(malformed(text)),
+    }
+}
+
+fn parse_point(text: Option<&str>) -> Result<(f32, f32), NotationError> {
+    notation::parse_point(text.unwrap_or_default())
+}
+
+/// Where a travel went from and to.
+type Travel = ((f32, f32), (f32, f32));
+
+/// What a travel captured, then what it promoted to.
+type Captures = (Vec<(PieceId, PieceKind)>, Option<PieceKind>);
+
+/// A travel, like `3.5,6.5>3.5,4.5`.
+fn parse_travel(text: Option<&str>) -> Result<Travel, NotationError> {
+    let text = text.unwrap_or_default();
+    let (from, to) = text.split_once('>').ok_or_else(|| malformed(text))?;
+    Ok((notation::parse_point(from)?, notation::parse_point(to)?))
+}
+
+/// What a travel captured, then what it promoted to. See the [module docs](self).
+fn write_captures(captured: &[(PieceId, PieceKind)], promoted: Option<PieceKind>) -> String {
+    let captured = match captured.is_empty() {
+        true => String::from("-"),
+        false => captured
+            .iter()
+            .map(|&(id, kind)| format!("{id}{}", notation::kind_letter(kind)))
+            .collect::<Vec<_>>()
+            .join(","),
+    };
+    format!("{captured} {}", promoted.map_or('-', notation::kind_letter))
+}
+
+/// A premove, like `7 3.5,6.5>3.5,4.5 -`.
+fn write_premove(premove: &Premove) -> String {
+    let travel = premove.travel.map_or(String::from("-"), |(from, to)| {
+        format!("{}>{}", write_point(from), write_point(to))
+    });
+    let rotation = premove
+        .rotation
+        .map_or(String::from("-"), |angle| angle.to_string());
+    format!("{} {travel} {rotation}", premove.piece)
+}
+
+fn parse_premove<'a>(words: &mut impl Iterator<Item = &'a str>) -> Result<Premove, NotationError> {
+    let piece = parse_number(words.next())?;
+    let travel = match words.next() {
+        Some("-") => None,
+        travel => Some(parse_travel(travel)?),
+    };
+    let rotation = match words.next() {
+        Some("-") => None,
+        angle => Some(parse_number(angle)?),
+    };
+    Ok(Premove {
+        piece,
+        travel,
+        rotation,
+    })
+}
+
+fn write_premove_error(reason: PremoveError) -> &'static str {
+    match reason {
+        PremoveError::Captured => "captured",
+        PremoveError::NotTravelable => "untravelable",
+        PremoveError::GameOver => "game-over",
+    }
+}
+
+fn parse_premove_error(text: Option<&str>) -> Result<PremoveError, NotationError> {
+    match text {
+        Some("captured") => Ok(PremoveError::Captured),
+        Some("untravelable") => Ok(PremoveError::NotTravelable),
+        Some("game-over") => Ok(PremoveError::GameOver),
+        text => Err(malformed(text.unwrap_or_default())),
+    }
+}
+
+fn parse_captures<'a>(
+    words: &mut impl Iterator<Item = &'a str>,
+) -> Result<Captures, NotationError> {
+    let captured = match words.next() {
+        Some("-") => vec![],
+        Some(captured) => captured
+            .split(',')
+            .map(|capture| {
+                let (id, kind) = capture.split_at(capture.len().saturating_sub(1));
+                Ok((parse_number(Some(id))?, parse_kind(Some(kind))?))
+            })
+            .collect::<Result<_, NotationError>>()?,
+        None => return Err(malformed("")),
+    };
+    let promoted = match words.next() {
+        Some("-") => None,
+        kind => Some(parse_kind(kind)?),
+    };
+    Ok((captured, promoted))
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let button = |button: &MouseButton| match button {
+            MouseButton::LEFT => "left",
+            MouseButton::RIGHT => "right",
+        };
+        match self {
+            Event::ButtonDown { x, y, button: b } => {
+                write!(f, "down {} {}", write_point((*x, *y)), button(b))
+            }
+            Event::ButtonUp { x, y, button: b } => {
+                write!(f, "up {} {}", write_point((*x, *y)), button(b))
+            }
+            Event::MouseMotion { x, y } => write!(f, "motion {}", write_point((*x, *y))),
+            Event::FirstTurn => write!(f, "first"),
+            Event::PrevTurn => write!(f, "prev"),
+            Event::NextTurn => write!(f, "next"),
+            Event::LastTurn => write!(f, "last"),
+            Event::RotateUnchecked(id, r) => write!(f, "rotate-unchecked {id} {r}"),
+            Event::MoveUnchecked(id, x, y) => {
+                write!(f, "move-unchecked {id} {}", write_point((*x, *y)))
+            }
+            Event::Modifiers(modifiers) => {
+                let held: Vec<_> = [
+                    (modifiers.shift, "shift"),
+                    (modifiers.ctrl, "ctrl"),
+                    (modifiers.alt, "alt"),
+                ]
+                .into_iter()
+                .filter_map(|(held, name)| held.then_some(name))
+                .collect();
+                match held.is_empty() {
+                    true => write!(f, "modifiers none"),
+                    false => write!(f, "modifiers {}", held.join("+")),
+                }
+            }
+            Event::SelectNextPiece => write!(f, "select-next"),
+            Event::SelectPrevPiece => write!(f, "select-prev"),
+            Event::NextTravelPoint => write!(f, "point-next"),
+            Event::PrevTravelPoint => write!(f, "point-prev"),
+            Event::RotateBy(r) => write!(f, "rotate-by {r}"),
+            Event::RotateTo(r) => write!(f, "rotate-to {r}"),
+            Event::Confirm => write!(f, "confirm"),
+            Event::Cancel => write!(f, "cancel"),
+            Event::TouchStart { id, x, y } => {
+                write!(f, "touch-start {id} {}", write_point((*x, *y)))
+            }
+            Event::TouchMove { id, x, y } => {
+                write!(f, "touch-move {id} {}", write_point((*x, *y)))
+            }
+            Event::TouchEnd { id, x, y } => write!(f, "touch-end {id} {}", write_point((*x, *y))),
+        }
+    }
+}
+
+impl FromStr for Event {
+    type Err = NotationError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut words = text.split_whitespace();
+        let button = |words: &mut std::str::SplitWhitespace| match words.next() {
+            Some("left") => Ok(MouseButton::LEFT),
+            Some("right") => Ok(MouseButton::RIGHT),
+            _ => Err(malformed(text)),
+        };
+        let event = match words.next() {
+            Some("down") => {
+                let (x, y) = parse_point(words.next())?;
+                Event::ButtonDown {
+                    x,
+                    y,
+                    button: button(&mut words)?,
+                }
+            }
+            Some("up") => {
+                let (x, y) = parse_point(words.next())?;
+                Event::ButtonUp {
+                    x,
+                    y,
+                    button: button(&mut words)?,
+                }
+            }
+            Some("motion") => {
+                let (x, y) = parse_point(words.next())?;
+                Event::MouseMotion { x, y }
+            }
+            Some("first") => Event::FirstTurn,
+            Some("prev") => Event::PrevTurn,
+            Some("next") => Event::NextTurn,
+            Some("last") => Event::LastTurn,
+            Some("rotate-unchecked") => {
+                Event::RotateUnchecked(parse_number(words.next())?, parse_number(words.next())?)
+            }
+            Some("move-unchecked") => {
+                let id = parse_number(words.next())?;
+                let (x, y) = parse_point(words.next())?;
+                Event::MoveUnchecked(id, x, y)
+            }
+            Some("modifiers") => {
+                let mut modifiers = Modifiers::default();
+                match words.next() {
+                    Some("none") => {}
+                    Some(held) => {
+                        for name in held.split('+') {
+                            match name {
+                                "shift" => modifiers.shift = true,
+                                "ctrl" => modifiers.ctrl = true,
+                                "alt" => modifiers.alt = true,
+                                _ => return Err(malformed(text)),
+                            }
+                        }
+                    }
+                    None => return Err(malformed(text)),
+                }
+                Event::Modifiers(modifiers)
+            }
+            Some("select-next") => Event::SelectNextPiece,
+            Some("select-prev") => Event::SelectPrevPiece,
+            Some("point-next") => Event::NextTravelPoint,
+            Some("point-prev") => Event::PrevTravelPoint,
+            Some("rotate-by") => Event::RotateBy(parse_number(words.next())?),
+            Some("rotate-to") => Event::RotateTo(parse_number(words.next())?),
+            Some("confirm") => Event::Confirm,
+            Some("cancel") => Event::Cancel,
+            Some(touch @ ("touch-start" | "touch-move" | "touch-end")) => {
+                let id = parse_number(words.next())?;
+                let (x, y) = parse_point(words.next())?;
+                match touch {
+                    "touch-start" => Event::TouchStart { id, x, y },
+                    "touch-move" => Event::TouchMove { id, x, y },
+                    _ => Event::TouchEnd { id, x, y },
+                }
+            }
+            _ => return Err(malformed(text)),
+        };
+        match words.next() {
+            Some(_) => Err(malformed(text)),
+            None => Ok(event),
+        }
+    }
+}
+
+impl fmt::Display for ThingHappened {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThingHappened::FirstTurn => write!(f, "first"),
+            ThingHappened::PrevTurn => write!(f, "prev"),
+            ThingHappened::NextTurn => write!(f, "next"),
+            ThingHappened::LastTurn => write!(f, "last"),
+            ThingHappened::Rotate {
+                piece,
+                from,
+                to,
+                turn,
+                to_move,
+            } => write!(
+                f,
+                "rotate {piece} {from} {to} {turn} {}",
+                write_side(*to_move)
+            ),
+            ThingHappened::Move {
+                piece,
+                from,
+                to,
+                captured,
+                promoted,
+                turn,
+                to_move,
+            } => write!(
+                f,
+                "move {piece} {}>{} {} {turn} {}",
+                write_point(*from),
+                write_point(*to),
+                write_captures(captured, *promoted),
+                write_side(*to_move)
+            ),
+            ThingHappened::EngineMove {
+                piece,
+                from,
+                to,
+                rotation,
+                captured,
+                promoted,
+                turn,
+                to_move,
+            } => write!(
+                f,
+                "engine {piece} {}>{} {}:{} {} {turn} {}",
+                write_point(*from),
+                write_point(*to),
+                rotation.0,
+                rotation.1,
+                write_captures(captured, *promoted),
+                write_side(*to_move)
+            ),
+            ThingHappened::PremoveQueued(premove) => {
+                write!(f, "premove {}", write_premove(premove))
+            }
+            ThingHappened::PremovePlayed {
+                premove,
+                captured,
+                promoted,
+                turn,
+                to_move,
+            } => write!(
+                f,
+                "premoved {} {} {turn} {}",
+                write_premove(premove),
+                write_captures(captured, *promoted),
+                write_side(*to_move)
+            ),
+            ThingHappened::PremoveDiscarded { premove, reason } => write!(
+                f,
+                "discard {} {}",
+                write_premove(premove),
+                write_premove_error(*reason)
+            ),
+            ThingHappened::Annotated {
+                annotation,
+                turn,
+                shown,
+            } => {
+                let shown = if *shown { "shown" } else { "gone" };
+                write!(f, "annotate {turn} {annotation} {shown}")
+            }
+        }
+    }
+}
+
+impl FromStr for ThingHappened {
+    type Err = NotationError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut words = text.split_whitespace();
+        let happened = match words.next() {
+            Some("first") => ThingHappened::FirstTurn,
+            Some("prev") => ThingHappened::PrevTurn,
+            Some("next") => ThingHappened::NextTurn,
+            Some("last") => ThingHappened::LastTurn,
+            Some("rotate") => ThingHappened::Rotate {
+                piece: parse_number(words.next())?,
+                from: parse_number(words.next())?,
+                to: parse_number(words.next())?,
+                turn: parse_number(words.next())?,
+                to_move: parse_side(words.next())?,
+            },
+            Some("move") => {
+                let piece = parse_number(words.next())?;
+                let (from, to) = parse_travel(words.next())?;
+                let (captured, promoted) = parse_captures(&mut words)?;
+                ThingHappened::Move {
+                    piece,
+                    from,
+                    to,
+                    captured,
+                    promoted,
+                    turn: parse_number(words.next())?,
+                    to_move: parse_side(words.next())?,
+                }
+            }
+            Some("engine") => {
+                let piece = parse_number(words.next())?;
+                let (from, to) = parse_travel(words.next())?;
+                let (rotation_from, rotation_to) = words
+                    .next()
+                    .and_then(|rotation| rotation.split_once(':'))
+                    .ok_or_else(|| malformed(text))?;
+                let rotation = (
+                    parse_number(Some(rotation_from))?,
+                    parse_number(Some(rotation_to))?,
+                );
+                let (captured, promoted) = parse_captures(&mut words)?;
+                ThingHappened::EngineMove {
+                    piece,
+                    from,
+                    to,
+                    rotation,
+                    captured,
+                    promoted,
+                    turn: parse_number(words.next())?,
+                    to_move: parse_side(words.next())?,
+                }
+            }
+            Some("premove") => ThingHappened::PremoveQueued(parse_premove(&mut words)?),
+            Some("premoved") => {
+                let premove = parse_premove(&mut words)?;
+                let (captured, promoted) = parse_captures(&mut words)?;
+                ThingHappened::PremovePlayed {
+                    premove,
+                    captured,
+                    promoted,
+                    turn: parse_number(words.next())?,
+                    to_move: parse_side(words.next())?,
+                }
+            }
+            Some("discard") => ThingHappened::PremoveDiscarded {
+                premove: parse_premove(&mut words)?,
+                reason: parse_premove_error(words.next())?,
+            },
+            Some("annotate") => ThingHappened::Annotated {
+                turn: parse_number(words.next())?,
+                annotation: parse_number(words.next())?,
+                shown: match words.next() {
+                    Some("shown") => true,
+                    Some("gone") => false,
+                    _ => return Err(malformed(text)),
+                },
+            },
+            _ => return Err(malformed(text)),
+        };
+        match words.next() {
+            Some(_) => Err(malformed(text)),
+            None => Ok(happened),
+        }
+    }
+}
+
+impl fmt::Display for EventLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let control = match self.control {
+            Control::Anyone => "anyone",
+            Control::Side(Side::White) => "w",
+            Control::Side(Side::Black) => "b",
+            Control::Spectator => "spectator",
+        };
+        writeln!(f, "control {control}")?;
+        let last = self.history.len() - 1;
+        for (turn, board) in self.history.iter().enumerate() {
+            // sides took turns, as far as anyone can tell.
+            let side = if (last - turn).is_multiple_of(2) {
+                self.to_move
+            } else {
+                self.to_move.toggled()
+            };
+            writeln!(f, "turn {}", notation::write_position(board, side))?;
+            if !self.annotations[turn].is_empty() {
+                let annotations = annotation::write_annotations(&self.annotations[turn]);
+                writeln!(f, "marks {annotations}")?;
+            }
+        }
+        writeln!(f, "looking {}", self.looking)?;
+        for recorded in &self.events {
+            write!(f, "{} {}", recorded.at, recorded.event)?;
+            if let Some(happened) = &recorded.happened {
+                write!(f, " => {happened}")?;
+            }
+            writeln!(f)?;
+        }
+        if let Some((board, to_move)) = &self.end {
+            writeln!(f, "end {}", notation::write_position(board, *to_move))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for EventLog {
+    type Err = NotationError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut ans = EventLog {
+            control: Control::Anyone,
+            history: vec![],
+            annotations: vec![],
+            looking: 0,
+            to_move: Side::White,
+            events: vec![],
+            end: None,
+        };
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (word, rest) = line.split_once(' ').ok_or_else(|| malformed(line))?;
+            match word {
+                "control" => {
+                    ans.control = match rest {
+                        "anyone" => Control::Anyone,
+                        "w" => Control::Side(Side::White),
+                        "b" => Control::Side(Side::Black),
+                        "spectator" => Control::Spectator,
+                        _ => return Err(malformed(line)),
+                    }
+                }
+                "turn" => {
+                    let (board, to_move) = notation::parse_position(rest)?;
+                    ans.history.push(board);
+                    ans.annotations.push(vec![]);
+                    ans.to_move = to_move;
+                }
+                "marks" => {
+                    *ans.annotations.last_mut().ok_or_else(|| malformed(line))? =
+                        annotation::parse_annotations(rest)?;
+                }
+                "looking" => ans.looking = parse_number(Some(rest))?,
+                "end" => ans.end = Some(notation::parse_position(rest)?),
+                at => {
+                    let (event, happened) = match rest.split_once(" => ") {
+                        Some((event, happened)) => (event, Some(happened.parse()?)),
+                        None => (rest, None),
+                    };
+                    ans.events.push(RecordedEvent {
+                        at: parse_number(Some(at))?,
+                        event: event.parse()?,
+                        happened,
+                    });
+                }
+            }
+        }
+        if ans.looking >= ans.history.len() {
+            return Err(malformed(&format!("looking {}", ans.looking)));
+        }
+        Ok(ans)
+    }
+}
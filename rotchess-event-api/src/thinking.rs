@@ -0,0 +1,378 @@
+//! Engines that think while the frame loop carries on.
+//!
+//! [`RotchessEmulator::start_thinking`] starts our engine on the side to move, and
+//! [`RotchessEmulator::poll_thinking`], called once a frame or so, plays its move once
+//! it has one. Natively, the engine thinks on a thread of its own. There are no
+//! threads on wasm, so there each poll searches a few thousand nodes instead, and the
+//! frame loop gets a look in between.
+
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use rotchess_core::engine::AlphaBetaEngine;
+use rotchess_core::{
+    engine::Engine,
+    search::{SearchLimits, SearchResult},
+    turn::Move,
+};
+#[cfg(any(target_arch = "wasm32", test))]
+use rotchess_core::{search::MAX_DEPTH, turn::Turns};
+
+use crate::{RotchessEmulator, ThingHappened};
+
+/// A search going on in the background.
+pub(crate) struct Thinking {
+    stop: Arc<AtomicBool>,
+    /// The latest finished iteration, for [`RotchessEmulator::thinking_progress`].
+    progress: Arc<Mutex<Option<SearchResult>>>,
+    /// The position hash and turn count we started thinking at. A move found for
+    /// anything else is thrown away.
+    game: (u64, usize),
+    /// The engine and its move, once it's done.
+    #[cfg(not(target_arch = "wasm32"))]
+    done: Receiver<(Box<dyn Engine>, Option<Move>)>,
+    #[cfg(target_arch = "wasm32")]
+    slices: Slices,
+}
+
+impl Drop for Thinking {
+    fn drop(&mut self) {
+        // nobody wants the move anymore.
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// How many nodes each poll may search, on wasm. A few milliseconds' worth.
+#[cfg(any(target_arch = "wasm32", test))]
+const SLICE_NODES: u64 = 4096;
+
+/// A search done a slice at a time.
+///
+/// A search can't be paused, so each slice searches again from the top, to the depth
+/// we're on, until it runs out of nodes. What earlier slices found is kept in the
+/// engine's transposition table, so little of it is searched twice, and once a
+/// slice finishes the depth we go a ply deeper.
+#[cfg(any(target_arch = "wasm32", test))]
+struct Slices {
+    turns: Turns,
+    limits: SearchLimits,
+    /// The depth to search next.
+    depth: usize,
+    /// When we started, by `limits.clock`.
+    started: f64,
+    /// Nodes searched so far, over every slice.
+    nodes: u64,
+    /// The move found by the deepest search finished so far.
+    best: Option<Move>,
+}
+
+#[cfg(any(target_arch = "wasm32", test))]
+impl Slices {
+    fn with(turns: Turns, limits: SearchLimits) -> Self {
+        Self {
+            turns,
+            started: (limits.clock)(),
+            limits,
+            depth: 1,
+            nodes: 0,
+            best: None,
+        }
+    }
+
+    /// Search one slice. Returns the move once we're done, `None` if not yet.
+    fn step(&mut self, engine: &mut dyn Engine) -> Option<Option<Move>> {
+        let reached = Arc::new(Mutex::new(None));
+        let reports = Arc::clone(&reached);
+        let on_iteration = self.limits.on_iteration.clone();
+        let mut limits = self
+            .limits
+            .clone()
+            .with_depth(self.depth)
+            .with_on_iteration(Arc::new(move |turns, result| {
+                *reports.lock().expect("never poisoned") = Some((result.depth, result.nodes));
+                if let Some(on_iteration) = &on_iteration {
+                    on_iteration(turns, result);
+                }
+            }));
+        // the first ply is quick, so it's searched whole. that's how we tell engines
+        // that don't search a ply at a time, which never report an iteration.
+        let budget = self.limits.nodes.map_or(SLICE_NODES, |nodes| {
+            nodes.saturating_sub(self.nodes).min(SLICE_NODES)
+        });
+        if self.depth > 1 {
+            limits = limits.with_nodes(budget);
+        }
+        if let Some(time) = self.limits.time {
+            limits = limits.with_time(time - ((self.limits.clock)() - self.started));
+        }
+
+        let move_ = engine.choose_move(&mut self.turns, &limits);
+        let finished = match *reached.lock().expect("never poisoned") {
+            Some((depth, nodes)) => {
+                self.nodes += nodes;
+                depth >= self.depth
+            }
+            None if self.depth == 1 => return Some(move_),
+            None => {
+                self.nodes += budget;
+                false
+            }
+        };
+        if finished || self.best.is_none() {
+            self.best = move_;
+        }
+
+        let out_of_time = self
+            .limits
+            .time
+            .is_some_and(|time| (self.limits.clock)() - self.started >= time);
+        let out_of_nodes = self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes);
+        let stopped = self
+            .limits
+            .stop
+            .as_ref()
+            .is_some_and(|stop| stop.load(Ordering::Relaxed));
+        let deepest = self.limits.depth.unwrap_or(MAX_DEPTH).min(MAX_DEPTH);
+        if self.best.is_none()
+            || out_of_time
+            || out_of_nodes
+            || stopped
+            || (finished && self.depth >= deepest)
+        {
+            return Some(self.best.take());
+        }
+        if finished {
+            self.depth += 1;
+        }
+        None
+    }
+}
+
+/// Thinking in the background.
+impl RotchessEmulator {
+    /// Start our [engine](Self::set_engine) thinking about a move for the side to
+    /// move, within `limits`. Any thinking already going on is cancelled.
+    ///
+    /// Nothing's played until [`Self::poll_thinking`] says so.
+    pub fn start_thinking(&mut self, limits: SearchLimits) {
+        self.cancel_thinking();
+        let stop = limits.stop.clone().unwrap_or_default();
+        let progress = Arc::new(Mutex::new(None));
+        let reported = Arc::clone(&progress);
+        let on_iteration = limits.on_iteration.clone();
+        let limits = limits
+            .with_stop(Arc::clone(&stop))
+            .with_on_iteration(Arc::new(move |turns, result| {
+                *reported.lock().expect("never poisoned") = Some(result.clone());
+                if let Some(on_iteration) = &on_iteration {
+                    on_iteration(turns, result);
+                }
+            }));
+        let game = self.game();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let done = {
+            let mut engine = self
+                .engine
+                .take()
+                .expect("back once we've stopped thinking");
+            let mut turns = self.turns.clone();
+            let (sender, done) = mpsc::channel();
+            thread::spawn(move || {
+                let move_ = engine.choose_move(&mut turns, &limits);
+                _ = sender.send((engine, move_));
+            });
+            done
+        };
+        #[cfg(target_arch = "wasm32")]
+        let slices = Slices::with(self.turns.clone(), limits);
+
+        self.thinking = Some(Thinking {
+            stop,
+            progress,
+            game,
+            #[cfg(not(target_arch = "wasm32"))]
+            done,
+            #[cfg(target_arch = "wasm32")]
+            slices,
+        });
+    }
+
+    /// Whether our engine is thinking.
+    pub fn is_thinking(&self) -> bool {
+        self.thinking.is_some()
+    }
+
+    /// The deepest search our engine has finished so far, if it's thinking and says.
+    ///
+    /// Engines that don't search, like the random one, never say.
+    pub fn thinking_progress(&self) -> Option<SearchResult> {
+        let thinking = self.thinking.as_ref()?;
+        thinking.progress.lock().expect("never poisoned").clone()
+    }
+
+    /// Stop thinking, throwing away whatever the engine had found.
+    pub fn cancel_thinking(&mut self) {
+        let Some(thinking) = self.thinking.take() else {
+            return;
+        };
+        thinking.stop.store(true, Ordering::Relaxed);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            // the search stops within a few nodes of being told to.
+            self.engine = Some(match thinking.done.recv() {
+                Ok((engine, _)) => engine,
+                // it panicked, taking the engine with it.
+                Err(_) => Box::new(AlphaBetaEngine::default()),
+            });
+        }
+    }
+
+    /// Play our engine's move, if it's done thinking.
+    ///
+    /// Returns what happened if a move was played. Once the engine's done, we stop
+    /// thinking whether or not there was a move, so check [`Self::is_thinking`] to tell
+    /// waiting from having nothing to play. If the game changed while the engine
+    /// thought, its move is thrown away.
+    pub fn poll_thinking(&mut self) -> Option<ThingHappened> {
+        let thinking = self.thinking.as_mut()?;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let move_ = {
+            let (engine, move_) = match thinking.done.try_recv() {
+                Ok(done) => done,
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    // it panicked, taking the engine with it.
+                    (
+                        Box::new(AlphaBetaEngine::default()) as Box<dyn Engine>,
+                        None,
+                    )
+                }
+            };
+            self.engine = Some(engine);
+            move_
+        };
+        #[cfg(target_arch = "wasm32")]
+        let move_ = {
+            let engine = self
+                .engine
+                .as_deref_mut()
+                .expect("engines stay put on wasm");
+            thinking.slices.step(engine)?
+        };
+
+        let thinking = self.thinking.take().expect("we were thinking");
+        self.unrotate();
+        if thinking.game != self.game() {
+            return None;
+        }
+        Some(self.play_engine_move(&move_?))
+    }
+
+    /// Which game we're playing, near enough to notice it changing.
+    fn game(&self) -> (u64, usize) {
+        (self.turns.position_hash(), self.turns.turn_count())
+    }
+
+    /// Play an engine's move, finishing the turn.
+    fn play_engine_move(&mut self, move_: &Move) -> ThingHappened {
+        let piece = move_.travel.piece();
+        let kind = self
+            .turns
+            .working_board_ref()
+            .get(piece)
+            .expect("engines move pieces that exist")
+            .kind();
+        let on_board = self.on_board();
+        self.turns.play(move_);
+        self.selected_piece = None;
+        self.selected_travelpoint = None;
+        self.selected_piece_being_dragged = None;
+        ThingHappened::EngineMove {
+            piece,
+            from: move_.travel.src(),
+            to: move_.travel.dest(),
+            rotation: (move_.rotate.src, move_.rotate.dest),
+            captured: self.captured_since(on_board),
+            promoted: self.promoted(piece, kind),
+            turn: self.turns.curr_turn(),
+            to_move: self.turns.to_move(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rotchess_core::{
+        engine::{AlphaBetaEngine, Engine, GreedyEngine},
+        notation,
+        piece::Side,
+        search::SearchLimits,
+        turn::Turns,
+    };
+
+    use super::Slices;
+
+    /// Rooks, a knight and kings: a few thousand nodes a ply.
+    fn endgame() -> Turns {
+        let (pieces, _) = notation::parse_position(
+            "w WR@0.5,7.5:0 WK@4.5,7.5:0 WN@6.5,7.5:0 BR@0.5,0.5:0 BK@4.5,0.5:0",
+        )
+        .unwrap();
+        Turns::with(pieces)
+    }
+
+    #[test]
+    fn slices_go_a_ply_deeper_once_one_is_finished() {
+        let turns = endgame();
+        let mut slices = Slices::with(turns.clone(), SearchLimits::infinite().with_depth(4));
+        let mut engine = AlphaBetaEngine::default();
+        let mut polls = 0;
+        let move_ = loop {
+            polls += 1;
+            if let Some(move_) = slices.step(&mut engine) {
+                break move_.expect("there are moves");
+            }
+        };
+        assert_eq!(slices.depth, 4);
+        // the deeper plies take more than one slice each.
+        assert!(polls > 4, "{polls} polls");
+        let board = slices.turns.working_board_ref();
+        assert_eq!(board.get(move_.travel.piece()).unwrap().side(), Side::White);
+    }
+
+    #[test]
+    fn slices_stop_at_the_node_limit() {
+        let turns = endgame();
+        let mut slices = Slices::with(turns, SearchLimits::infinite().with_nodes(50_000));
+        let mut engine = AlphaBetaEngine::default();
+        let move_ = loop {
+            if let Some(move_) = slices.step(&mut engine) {
+                break move_;
+            }
+        };
+        assert!(move_.is_some());
+        assert!(
+            slices.nodes >= 50_000 && slices.nodes < 60_000,
+            "{}",
+            slices.nodes
+        );
+    }
+
+    #[test]
+    fn engines_that_dont_search_answer_at_once() {
+        let turns = endgame();
+        let mut slices = Slices::with(turns, SearchLimits::infinite());
+        let engine: &mut dyn Engine = &mut GreedyEngine;
+        assert!(slices.step(engine).is_some_and(|move_| move_.is_some()));
+    }
+}
@@ -0,0 +1,159 @@
+//! Playing with fingers.
+//!
+//! One finger does what the left mouse button does: it selects, drags pieces and
+//! drags travel points around. Putting a second finger down lets go of whatever the
+//! first was holding and twists the selected piece instead, turning it as the line
+//! between the two fingers turns. Lifting either finger plays the rotation. Any other
+//! fingers are ignored, as are the ones left down once a gesture's over.
+//!
+//! A piece turned by dragging a travel point goes on turning with the twist, and is
+//! played from where it was before either.
+
+use crate::{Event, MouseButton, RotchessEmulator, ThingHappened};
+
+/// The fingers on the board, and what they're doing.
+#[derive(Debug, Default)]
+pub(crate) struct Touches {
+    /// Every finger down, by id, and where it is.
+    down: Vec<(u64, (f32, f32))>,
+    gesture: Gesture,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+enum Gesture {
+    /// No gesture, though fingers may still be down from the last one.
+    #[default]
+    Idle,
+    /// One finger, standing in for the left mouse button.
+    Pressing(u64),
+    /// Two fingers turning the selected piece. `from` is the angle of the line between
+    /// them when they went down, and `angle` the piece's angle then.
+    Twisting {
+        fingers: (u64, u64),
+        from: f32,
+        angle: f32,
+    },
+}
+
+impl Touches {
+    fn at(&self, id: u64) -> Option<(f32, f32)> {
+        self.down
+            .iter()
+            .find_map(|&(finger, at)| (finger == id).then_some(at))
+    }
+
+    /// The angle of the line from one finger to the other, counterclockwise as seen
+    /// with y pointing down.
+    fn angle_between(&self, (a, b): (u64, u64)) -> Option<f32> {
+        let ((ax, ay), (bx, by)) = (self.at(a)?, self.at(b)?);
+        Some((ay - by).atan2(bx - ax))
+    }
+}
+
+/// Touching.
+impl RotchessEmulator {
+    /// Handle [`Event::TouchStart`], [`Event::TouchMove`] or [`Event::TouchEnd`].
+    pub(crate) fn touch(&mut self, e: Event) -> Option<ThingHappened> {
+        match e {
+            Event::TouchStart { id, x, y } => {
+                if self.touches.at(id).is_some() {
+                    return None;
+                }
+                self.touches.down.push((id, (x, y)));
+                match (self.touches.gesture, self.touches.down.len()) {
+                    (Gesture::Idle, 1) => {
+                        self.touches.gesture = Gesture::Pressing(id);
+                        self.handle(Event::ButtonDown {
+                            x,
+                            y,
+                            button: MouseButton::LEFT,
+                        })
+                    }
+                    (Gesture::Pressing(first), 2) => {
+                        self.let_go();
+                        let fingers = (first, id);
+                        let angle = self
+                            .selected_piece
+                            .and_then(|id| self.turns.working_board_ref().get(id))
+                            .map_or(0., |piece| piece.angle());
+                        self.touches.gesture = Gesture::Twisting {
+                            fingers,
+                            from: self.touches.angle_between(fingers).expect("both down"),
+                            angle,
+                        };
+                        None
+                    }
+                    _ => None,
+                }
+            }
+            Event::TouchMove { id, x, y } => {
+                let finger = self
+                    .touches
+                    .down
+                    .iter_mut()
+                    .find(|(finger, _)| *finger == id)?;
+                finger.1 = (x, y);
+                match self.touches.gesture {
+                    Gesture::Pressing(first) if first == id => {
+                        self.handle(Event::MouseMotion { x, y })
+                    }
+                    Gesture::Twisting {
+                        fingers,
+                        from,
+                        angle,
+                    } if fingers.0 == id || fingers.1 == id => {
+                        let now = self.touches.angle_between(fingers).expect("both down");
+                        self.rotate_selected_to(|_| angle + now - from);
+                        None
+                    }
+                    _ => None,
+                }
+            }
+            Event::TouchEnd { id, x, y } => {
+                let at = self
+                    .touches
+                    .down
+                    .iter()
+                    .position(|&(finger, _)| finger == id)?;
+                self.touches.down.remove(at);
+                match self.touches.gesture {
+                    Gesture::Pressing(first) if first == id => {
+                        self.touches.gesture = Gesture::Idle;
+                        self.handle(Event::ButtonUp {
+                            x,
+                            y,
+                            button: MouseButton::LEFT,
+                        })
+                    }
+                    Gesture::Twisting { fingers, .. } if fingers.0 == id || fingers.1 == id => {
+                        self.touches.gesture = Gesture::Idle;
+                        // a twist that didn't turn anything plays nothing.
+                        self.rotating_from?;
+                        self.confirm()
+                    }
+                    _ => None,
+                }
+            }
+            _ => unreachable!("only touch events are handed here"),
+        }
+    }
+
+    /// Let go of whatever the first finger held, without playing it.
+    fn let_go(&mut self) {
+        let Some(id) = self.selected_piece else {
+            return;
+        };
+        if let Some(center) = self.selected_piece_being_dragged.take() {
+            let piece = self.turns.working_board_mut().get_mut(id).expect("exists");
+            piece.set_center(center);
+        }
+        if let Some((_, _, true)) = self.selected_travelpoint.take() {
+            // the twist takes over the turn.
+            let angle = self.turns.history()[self.turns.curr_turn()]
+                .get(id)
+                .expect("exists")
+                .angle();
+            self.rotating_from.get_or_insert(angle);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Where the board is on screen.
+//!
+//! Frontends hand the emulator events in screen coordinates, and the emulator's
+//! [`View`] turns them into rotchess units before any hit-testing or rotation
+//! dragging happens. Everything the emulator hands back, like pieces and travel
+//! points, is in rotchess units; [`View::to_screen`] puts it on screen.
+
+use std::f32::consts::PI;
+
+use crate::Event;
+
+/// The middle of the board, which views turn about.
+const BOARD_CENTER: (f32, f32) = (4., 4.);
+
+/// How the board is shown.
+///
+/// The board is turned about its center, then scaled and moved so that its top left
+/// corner, before turning, is at the offset. Screen y points down, like rotchess units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    flipped: bool,
+    rotation: f32,
+    scale: f32,
+    offset: (f32, f32),
+}
+
+impl Default for View {
+    /// One screen unit per rotchess unit, white at the bottom.
+    fn default() -> Self {
+        Self {
+            flipped: false,
+            rotation: 0.,
+            scale: 1.,
+            offset: (0., 0.),
+        }
+    }
+}
+
+impl View {
+    /// Show black's side at the bottom, turning the board half way round.
+    pub fn with_flipped(mut self, flipped: bool) -> Self {
+        self.flipped = flipped;
+        self
+    }
+
+    /// Turn the board by `rotation` radians, counterclockwise like piece angles, on
+    /// top of any flip.
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Screen units per rotchess unit.
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Where on screen the board's top left corner is, before turning.
+    pub fn with_offset(mut self, offset: (f32, f32)) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn flipped(&self) -> bool {
+        self.flipped
+    }
+
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn offset(&self) -> (f32, f32) {
+        self.offset
+    }
+
+    /// How far the board is turned, flip included.
+    fn turn(&self) -> f32 {
+        match self.flipped {
+            true => self.rotation + PI,
+            false => self.rotation,
+        }
+    }
+
+    /// Where a point on the board is on screen.
+    pub fn to_screen(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (dx, dy) = turned((x - BOARD_CENTER.0, y - BOARD_CENTER.1), self.turn());
+        (
+            self.offset.0 + self.scale * (BOARD_CENTER.0 + dx),
+            self.offset.1 + self.scale * (BOARD_CENTER.1 + dy),
+        )
+    }
+
+    /// Where a point on screen is on the board.
+    pub fn to_board(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (dx, dy) = turned(
+            (
+                (x - self.offset.0) / self.scale - BOARD_CENTER.0,
+                (y - self.offset.1) / self.scale - BOARD_CENTER.1,
+            ),
+            -self.turn(),
+        );
+        (BOARD_CENTER.0 + dx, BOARD_CENTER.1 + dy)
+    }
+
+    /// The angle a piece at `angle` is drawn at.
+    pub fn angle_to_screen(&self, angle: f32) -> f32 {
+        angle + self.turn()
+    }
+
+    /// `event`, with any screen coordinates turned into rotchess units.
+    pub fn to_board_event(&self, event: Event) -> Event {
+        match event {
+            Event::ButtonDown { x, y, button } => {
+                let (x, y) = self.to_board((x, y));
+                Event::ButtonDown { x, y, button }
+            }
+            Event::ButtonUp { x, y, button } => {
+                let (x, y) = self.to_board((x, y));
+                Event::ButtonUp { x, y, button }
+            }
+            Event::MouseMotion { x, y } => {
+                let (x, y) = self.to_board((x, y));
+                Event::MouseMotion { x, y }
+            }
+            Event::TouchStart { id, x, y } => {
+                let (x, y) = self.to_board((x, y));
+                Event::TouchStart { id, x, y }
+            }
+            Event::TouchMove { id, x, y } => {
+                let (x, y) = self.to_board((x, y));
+                Event::TouchMove { id, x, y }
+            }
+            Event::TouchEnd { id, x, y } => {
+                let (x, y) = self.to_board((x, y));
+                Event::TouchEnd { id, x, y }
+            }
+            event => event,
+        }
+    }
+}
+
+/// `(x, y)` turned counterclockwise by `angle`, as seen with y pointing down.
+fn turned((x, y): (f32, f32), angle: f32) -> (f32, f32) {
+    let (sin, cos) = angle.sin_cos();
+    (x * cos + y * sin, y * cos - x * sin)
+}
@@ -0,0 +1,106 @@
+//! Drawing on the board with the right mouse button.
+
+mod common;
+
+use rotchess_core::annotation::{Annotation, MarkColor};
+use rotchess_event_api::{
+    Event, Modifiers, MouseButton, RotchessEmulator, ThingHappened,
+    piece::{Pieces, Side},
+};
+
+use common::{right_click, right_drag};
+
+/// Whether a mark was drawn (or rubbed out, if not `shown`).
+fn annotated(happened: Option<ThingHappened>) -> (Annotation, bool) {
+    match happened {
+        Some(ThingHappened::Annotated {
+            annotation, shown, ..
+        }) => (annotation, shown),
+        happened => panic!("nothing was drawn: {happened:?}"),
+    }
+}
+
+#[test]
+fn right_dragging_draws_an_arrow() {
+    let mut game = RotchessEmulator::with(Pieces::standard_board());
+    game.handle_event(Event::ButtonDown {
+        x: 4.6,
+        y: 6.4,
+        button: MouseButton::RIGHT,
+    });
+    game.handle_event(Event::MouseMotion { x: 4.2, y: 4.8 });
+    // from the middle of the pawn to the middle of the square.
+    let arrow = Annotation::Arrow {
+        from: (4.5, 6.5),
+        to: (4.5, 4.5),
+        color: MarkColor::Green,
+    };
+    assert_eq!(game.drawing_arrow(), Some(arrow));
+
+    let happened = game.handle_event(Event::ButtonUp {
+        x: 4.2,
+        y: 4.8,
+        button: MouseButton::RIGHT,
+    });
+    assert_eq!(annotated(happened), (arrow, true));
+    assert_eq!(game.annotations(), [arrow]);
+    assert_eq!(game.drawing_arrow(), None);
+
+    // the pawn didn't go anywhere.
+    assert!(game.selected().is_none());
+    assert_eq!(game.to_move(), Side::White);
+}
+
+#[test]
+fn right_clicking_toggles_a_circle() {
+    let mut game = RotchessEmulator::with(Pieces::standard_board());
+    let circle = Annotation::Circle {
+        at: (2.5, 3.5),
+        color: MarkColor::Green,
+    };
+    assert_eq!(
+        annotated(right_click(&mut game, (2.3, 3.9))),
+        (circle, true)
+    );
+    assert_eq!(game.annotations(), [circle]);
+    assert_eq!(
+        annotated(right_click(&mut game, (2.7, 3.1))),
+        (circle, false)
+    );
+    assert!(game.annotations().is_empty());
+}
+
+#[test]
+fn modifiers_pick_the_color() {
+    let mut game = RotchessEmulator::with(Pieces::standard_board());
+    for (modifiers, color) in [
+        (
+            Modifiers {
+                shift: true,
+                ..Modifiers::default()
+            },
+            MarkColor::Red,
+        ),
+        (
+            Modifiers {
+                ctrl: true,
+                ..Modifiers::default()
+            },
+            MarkColor::Blue,
+        ),
+        (
+            Modifiers {
+                alt: true,
+                ..Modifiers::default()
+            },
+            MarkColor::Yellow,
+        ),
+        (Modifiers::default(), MarkColor::Green),
+    ] {
+        game.handle_event(Event::Modifiers(modifiers));
+        let (arrow, shown) = annotated(right_drag(&mut game, (1.5, 6.5), (1.5, 4.5)));
+        assert_eq!((arrow.color(), shown), (color, true));
+        // another color takes the arrow's place, rather than drawing another.
+        assert_eq!(game.annotations(), [arrow]);
+    }
+}
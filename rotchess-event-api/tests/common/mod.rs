@@ -0,0 +1,63 @@
+//! Helpers shared by the tests.
+
+// each test uses only some of them.
+#![allow(dead_code)]
+
+use rotchess_core::notation;
+use rotchess_event_api::{Event, MouseButton, RotchessEmulator, ThingHappened};
+
+/// A game at `position`, as written by [`notation::write_position`].
+pub fn game_at(position: &str) -> RotchessEmulator {
+    let (pieces, to_move) = notation::parse_position(position).unwrap();
+    let mut game = RotchessEmulator::with(pieces);
+    game.set_to_move(to_move);
+    game
+}
+
+pub fn down(game: &mut RotchessEmulator, (x, y): (f32, f32)) -> Option<ThingHappened> {
+    game.handle_event(Event::ButtonDown {
+        x,
+        y,
+        button: MouseButton::LEFT,
+    })
+}
+
+pub fn motion(game: &mut RotchessEmulator, (x, y): (f32, f32)) -> Option<ThingHappened> {
+    game.handle_event(Event::MouseMotion { x, y })
+}
+
+pub fn up(game: &mut RotchessEmulator, (x, y): (f32, f32)) -> Option<ThingHappened> {
+    game.handle_event(Event::ButtonUp {
+        x,
+        y,
+        button: MouseButton::LEFT,
+    })
+}
+
+pub fn click(game: &mut RotchessEmulator, at: (f32, f32)) -> Option<ThingHappened> {
+    down(game, at);
+    up(game, at)
+}
+
+/// Press the right button at `from`, and let go of it at `to`.
+pub fn right_drag(
+    game: &mut RotchessEmulator,
+    (x, y): (f32, f32),
+    to: (f32, f32),
+) -> Option<ThingHappened> {
+    game.handle_event(Event::ButtonDown {
+        x,
+        y,
+        button: MouseButton::RIGHT,
+    });
+    motion(game, to);
+    game.handle_event(Event::ButtonUp {
+        x: to.0,
+        y: to.1,
+        button: MouseButton::RIGHT,
+    })
+}
+
+pub fn right_click(game: &mut RotchessEmulator, at: (f32, f32)) -> Option<ThingHappened> {
+    right_drag(game, at, at)
+}
@@ -0,0 +1,59 @@
+//! What's under the mouse.
+
+mod common;
+
+use rotchess_core::piece::TravelKind;
+use rotchess_event_api::{RotchessEmulator, piece::PieceKind};
+
+use common::{click, game_at, motion as hover};
+
+fn game() -> RotchessEmulator {
+    game_at("w WR@0.5,7.5:0 WK@4.5,7.5:0 BR@0.5,0.5:0 BK@4.5,0.5:0")
+}
+
+#[test]
+fn hovering_follows_the_mouse() {
+    let mut game = game();
+    assert!(game.hovered_piece().is_none());
+
+    hover(&mut game, (0.5, 0.5));
+    let piece = game.hovered_piece().unwrap();
+    assert_eq!(piece.kind(), PieceKind::Rook);
+    assert_eq!(piece.center(), (0.5, 0.5));
+
+    hover(&mut game, (2.5, 3.5));
+    assert!(game.hovered_piece().is_none());
+    // nothing's selected, so there are no travel points to be over.
+    assert!(game.hovered_travelpoint().is_none());
+
+    click(&mut game, (0.5, 7.5));
+    hover(&mut game, (0.5, 4.6));
+    let tp = game.hovered_travelpoint().unwrap();
+    assert_eq!((tp.x, tp.y, tp.kind), (0.5, 4.5, TravelKind::Move));
+    assert!(tp.travelable);
+}
+
+#[test]
+fn travelable_points_win_where_they_overlap() {
+    let mut game = game();
+    click(&mut game, (0.5, 7.5));
+    hover(&mut game, (0.5, 0.5));
+
+    // the rook could move to the black rook, or capture it, and only capturing is
+    // travelable. moves come first.
+    let (_, travelpoints) = game.selected().unwrap();
+    let under: Vec<_> = travelpoints
+        .iter()
+        .filter(|tp| (tp.x, tp.y) == (0.5, 0.5))
+        .map(|tp| (tp.kind, tp.travelable))
+        .collect();
+    assert_eq!(
+        under,
+        [(TravelKind::Move, false), (TravelKind::Capture, true)]
+    );
+
+    let tp = game.hovered_travelpoint().unwrap();
+    assert_eq!((tp.kind, tp.travelable), (TravelKind::Capture, true));
+    // the piece under the travel point is still hovered.
+    assert_eq!(game.hovered_piece().unwrap().kind(), PieceKind::Rook);
+}
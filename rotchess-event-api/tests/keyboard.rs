@@ -0,0 +1,143 @@
+//! Playing with keys, and a scroll wheel.
+
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+mod common;
+
+use rotchess_event_api::{
+    Event, RotchessEmulator, ThingHappened,
+    piece::{PieceKind, Side},
+};
+
+use common::game_at;
+
+/// White's rook, king and knight along the bottom, and black's king.
+fn game() -> RotchessEmulator {
+    game_at("w WR@0.5,7.5:0 WK@4.5,7.5:0 WN@6.5,7.5:0 BK@4.5,0.5:0")
+}
+
+fn selected_kind(game: &RotchessEmulator) -> Option<PieceKind> {
+    game.selected().map(|(piece, _)| piece.kind())
+}
+
+fn focused(game: &RotchessEmulator) -> Option<(f32, f32)> {
+    game.focused_travelpoint().map(|tp| (tp.x, tp.y))
+}
+
+fn angle(game: &RotchessEmulator) -> f32 {
+    game.selected().unwrap().0.angle()
+}
+
+#[test]
+fn pieces_are_picked_across_the_board_and_wrap_around() {
+    let mut game = game();
+    let mut picked = vec![];
+    for _ in 0..4 {
+        game.handle_event(Event::SelectNextPiece);
+        picked.push(selected_kind(&game).unwrap());
+    }
+    assert_eq!(
+        picked,
+        [
+            PieceKind::Rook,
+            PieceKind::King,
+            PieceKind::Knight,
+            PieceKind::Rook
+        ]
+    );
+
+    // back past the start to the end.
+    game.handle_event(Event::SelectPrevPiece);
+    assert_eq!(selected_kind(&game), Some(PieceKind::Knight));
+
+    // nothing's picked first going backwards, so it's the last.
+    game.handle_event(Event::Cancel);
+    game.handle_event(Event::SelectPrevPiece);
+    assert_eq!(selected_kind(&game), Some(PieceKind::Knight));
+}
+
+#[test]
+fn travel_points_wrap_around_and_confirm_travels() {
+    let mut game = game();
+    game.handle_event(Event::SelectNextPiece);
+    let travelable = game
+        .selected()
+        .unwrap()
+        .1
+        .iter()
+        .filter(|tp| tp.travelable)
+        .count();
+    assert!(travelable > 1);
+
+    game.handle_event(Event::NextTravelPoint);
+    let first = focused(&game).unwrap();
+    for _ in 0..travelable {
+        game.handle_event(Event::NextTravelPoint);
+        assert!(game.focused_travelpoint().unwrap().travelable);
+    }
+    assert_eq!(focused(&game), Some(first));
+    game.handle_event(Event::PrevTravelPoint);
+    let last = focused(&game).unwrap();
+    assert_ne!(last, first);
+
+    let Some(ThingHappened::Move { from, to, .. }) = game.handle_event(Event::Confirm) else {
+        panic!("confirming travels to the focused point");
+    };
+    assert_eq!((from, to), ((0.5, 7.5), last));
+    assert_eq!(game.to_move(), Side::Black);
+}
+
+#[test]
+fn rotations_are_played_on_confirm() {
+    let mut game = game();
+    game.handle_event(Event::SelectNextPiece);
+    let id = game.selected().unwrap().0.id();
+
+    game.handle_event(Event::RotateBy(FRAC_PI_4));
+    game.handle_event(Event::RotateBy(FRAC_PI_4));
+    assert!((angle(&game) - FRAC_PI_2).abs() < 1e-5);
+    // nothing's played yet.
+    assert_eq!(game.to_move(), Side::White);
+
+    game.handle_event(Event::RotateTo(1.));
+    let Some(ThingHappened::Rotate {
+        piece,
+        from,
+        to,
+        to_move,
+        ..
+    }) = game.handle_event(Event::Confirm)
+    else {
+        panic!("confirming plays the rotation");
+    };
+    assert_eq!((piece, from, to, to_move), (id, 0., 1., Side::Black));
+}
+
+#[test]
+fn other_events_turn_the_piece_back() {
+    let mut game = game();
+    game.handle_event(Event::SelectNextPiece);
+    game.handle_event(Event::RotateTo(1.));
+
+    // moving the mouse keeps the rotation.
+    game.handle_event(Event::MouseMotion { x: 3.5, y: 3.5 });
+    assert_eq!(angle(&game), 1.);
+
+    // picking a travel point doesn't.
+    game.handle_event(Event::NextTravelPoint);
+    assert_eq!(angle(&game), 0.);
+
+    // nor does letting go of the piece.
+    game.handle_event(Event::RotateBy(0.5));
+    game.handle_event(Event::Cancel);
+    assert!(game.selected().is_none());
+    game.handle_event(Event::SelectNextPiece);
+    assert_eq!(angle(&game), 0.);
+
+    // with nothing turned, confirming travels rather than rotates.
+    game.handle_event(Event::NextTravelPoint);
+    assert!(matches!(
+        game.handle_event(Event::Confirm),
+        Some(ThingHappened::Move { .. })
+    ));
+}
@@ -0,0 +1,109 @@
+//! Queueing turns while the other side thinks, and playing or dropping them.
+
+mod common;
+
+use rotchess_core::notation;
+use rotchess_event_api::{
+    Control, RotchessEmulator, ThingHappened,
+    piece::{PieceId, Pieces, Side},
+    premove::{Premove, PremoveError},
+};
+
+use common::{down, game_at, motion, up};
+
+/// The standard board with `side` ours, after `turns`.
+fn game(side: Side, turns: &[&str]) -> RotchessEmulator {
+    let mut game = RotchessEmulator::with(Pieces::standard_board());
+    game.set_control(Control::Side(side));
+    for turn in turns {
+        game.play_actions(&notation::parse_actions(turn).unwrap())
+            .unwrap();
+    }
+    game
+}
+
+fn id_at(game: &RotchessEmulator, (x, y): (f32, f32)) -> PieceId {
+    game.turns().working_board_ref().get_id(x, y).unwrap()
+}
+
+/// Queue a premove of the piece at `from` travelling to `to`.
+fn premove(game: &mut RotchessEmulator, from: (f32, f32), to: (f32, f32)) {
+    game.queue_premove(Premove {
+        piece: id_at(game, from),
+        travel: Some((from, to)),
+        rotation: None,
+    });
+}
+
+fn discarded(game: &mut RotchessEmulator) -> Option<PremoveError> {
+    match game.play_premove() {
+        Some(ThingHappened::PremoveDiscarded { reason, .. }) => Some(reason),
+        _ => None,
+    }
+}
+
+#[test]
+fn premoves_wait_for_the_opponent() {
+    let mut game = game(Side::White, &["4.5,6.5>4.5,4.5"]);
+    let id = id_at(&game, (3.5, 6.5));
+
+    // dragging the d pawn while black thinks only queues it.
+    down(&mut game, (3.5, 6.5));
+    motion(&mut game, (3.5, 5.5));
+    let happened = up(&mut game, (3.5, 4.5));
+    let Some(ThingHappened::PremoveQueued(queued)) = happened else {
+        panic!("waiting on black, so it's a premove: {happened:?}");
+    };
+    assert_eq!(queued.piece, id);
+    assert_eq!(game.premoves().count(), 1);
+    assert_eq!(id_at(&game, (3.5, 6.5)), id);
+    assert!(game.play_premove().is_none());
+
+    game.play_actions(&notation::parse_actions("0.5,1.5>0.5,2.5").unwrap())
+        .unwrap();
+    let Some(ThingHappened::PremovePlayed {
+        premove, to_move, ..
+    }) = game.play_premove()
+    else {
+        panic!("it's our turn, and the d pawn can still go");
+    };
+    assert_eq!(premove, queued);
+    assert_eq!(to_move, Side::Black);
+    assert_eq!(id_at(&game, (3.5, 4.5)), id);
+    assert_eq!(game.premoves().count(), 0);
+}
+
+#[test]
+fn captured_pieces_drop_their_premoves() {
+    let mut game = game(Side::Black, &["4.5,6.5>4.5,4.5", "3.5,1.5>3.5,3.5"]);
+    premove(&mut game, (3.5, 3.5), (3.5, 4.5));
+    game.play_actions(&notation::parse_actions("4.5,4.5>3.5,3.5").unwrap())
+        .unwrap();
+    assert_eq!(discarded(&mut game), Some(PremoveError::Captured));
+}
+
+#[test]
+fn blocked_travels_drop_their_premoves() {
+    let mut game = game(Side::Black, &["4.5,6.5>4.5,4.5", "3.5,1.5>3.5,3.5"]);
+    premove(&mut game, (4.5, 1.5), (4.5, 3.5));
+    game.play_actions(&notation::parse_actions("4.5,4.5>4.5,3.5").unwrap())
+        .unwrap();
+    assert_eq!(discarded(&mut game), Some(PremoveError::NotTravelable));
+    // the board's as white left it.
+    assert_eq!(game.to_move(), Side::Black);
+}
+
+#[test]
+fn premoves_are_dropped_once_the_game_is_over() {
+    let mut game = game_at("w WK@4.5,7.5:0 WR@0.5,0.5:0 BK@4.5,0.5:0 BP@7.5,1.5:0");
+    game.set_control(Control::Side(Side::Black));
+    premove(&mut game, (7.5, 1.5), (7.5, 2.5));
+    premove(&mut game, (7.5, 1.5), (7.5, 3.5));
+
+    // the rook takes the king.
+    game.play_actions(&notation::parse_actions("0.5,0.5>4.5,0.5").unwrap())
+        .unwrap();
+    assert_eq!(discarded(&mut game), Some(PremoveError::GameOver));
+    assert_eq!(discarded(&mut game), Some(PremoveError::GameOver));
+    assert!(game.play_premove().is_none());
+}
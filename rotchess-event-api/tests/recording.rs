@@ -0,0 +1,74 @@
+//! Recording games played with the mouse, and replaying them.
+
+mod common;
+
+use rotchess_core::notation;
+use rotchess_event_api::{
+    RotchessEmulator, ThingHappened,
+    piece::Pieces,
+    recording::{EventLog, ReplayError},
+};
+
+use common::{click, down, motion, up};
+
+/// White drags the e pawn two forward, then black turns theirs by dragging one of
+/// its travel points. Returns the log as written.
+fn record() -> (RotchessEmulator, String) {
+    let mut game = RotchessEmulator::with(Pieces::standard_board());
+    game.start_recording(|| 0.);
+
+    down(&mut game, (4.5, 6.5));
+    motion(&mut game, (4.5, 5.5));
+    assert!(matches!(
+        up(&mut game, (4.5, 4.5)),
+        Some(ThingHappened::Move { .. })
+    ));
+
+    click(&mut game, (4.5, 1.5));
+    down(&mut game, (4.5, 2.5));
+    motion(&mut game, (5.5, 2.));
+    assert!(matches!(
+        up(&mut game, (5.5, 1.5)),
+        Some(ThingHappened::Rotate { .. })
+    ));
+
+    let text = game.stop_recording().unwrap().to_string();
+    (game, text)
+}
+
+fn position(game: &RotchessEmulator) -> String {
+    notation::write_position(game.turns().working_board_ref(), game.to_move())
+}
+
+#[test]
+fn drags_and_rotations_round_trip() {
+    let (game, text) = record();
+    assert!(text.contains("up 4.5,4.5 left => move "), "{text}");
+    assert!(text.contains("up 5.5,1.5 left => rotate "), "{text}");
+
+    let log: EventLog = text.parse().unwrap();
+    assert_eq!(log.to_string(), text);
+    let replayed = log.replay().unwrap();
+    assert_eq!(position(&replayed), position(&game));
+}
+
+#[test]
+fn tampered_logs_diverge() {
+    let (_, text) = record();
+    // the pawn's let go of a square short, but the log still says it went two.
+    let tampered = text.replace("up 4.5,4.5 left", "up 4.5,5.5 left");
+    assert_ne!(tampered, text);
+
+    let log: EventLog = tampered.parse().unwrap();
+    let Err(ReplayError::Diverged {
+        index,
+        recorded,
+        replayed,
+    }) = log.replay()
+    else {
+        panic!("a tampered log can't replay");
+    };
+    assert_eq!(index, 2);
+    assert!(recorded.contains("4.5,6.5>4.5,4.5"), "{recorded}");
+    assert!(replayed.contains("4.5,6.5>4.5,5.5"), "{replayed}");
+}
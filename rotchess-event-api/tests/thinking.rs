@@ -0,0 +1,78 @@
+//! Engines thinking while the frame loop carries on.
+
+mod common;
+
+use std::{thread, time::Duration};
+
+use rotchess_core::{notation, search::SearchLimits};
+use rotchess_event_api::{RotchessEmulator, ThingHappened, piece::Side};
+
+use common::game_at;
+
+/// White's rook can take black's.
+const ROOKS: &str = "w WR@0.5,7.5:0 WK@4.5,7.5:0 BR@0.5,0.5:0 BK@7.5,0.5:0";
+
+/// Poll like a frame loop would, until the engine's done.
+fn wait(game: &mut RotchessEmulator) -> Option<ThingHappened> {
+    while game.is_thinking() {
+        if let Some(happened) = game.poll_thinking() {
+            return Some(happened);
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    None
+}
+
+#[test]
+fn polling_plays_the_engines_move() {
+    let mut game = game_at(ROOKS);
+    game.start_thinking(SearchLimits::default());
+    assert!(game.is_thinking());
+    let Some(ThingHappened::EngineMove {
+        to,
+        captured,
+        to_move,
+        ..
+    }) = wait(&mut game)
+    else {
+        panic!("the engine had a move to play");
+    };
+    assert_eq!((to, captured.len(), to_move), ((0.5, 0.5), 1, Side::Black));
+    assert!(!game.is_thinking());
+    assert!(game.engine().is_some());
+}
+
+#[test]
+fn cancelling_gives_the_engine_back() {
+    let mut game = game_at(ROOKS);
+    game.start_thinking(SearchLimits::infinite());
+    // natively, it's off on a thread of its own.
+    assert!(game.engine().is_none());
+
+    game.cancel_thinking();
+    assert!(!game.is_thinking());
+    assert_eq!(
+        game.engine().map(|engine| engine.name()),
+        Some("alpha-beta")
+    );
+    assert!(game.poll_thinking().is_none());
+    assert_eq!(game.to_move(), Side::White);
+}
+
+#[test]
+fn moves_for_changed_positions_are_thrown_away() {
+    let mut game = game_at(ROOKS);
+    game.start_thinking(SearchLimits::default());
+    // the rook goes somewhere else while the engine thinks.
+    game.play_actions(&notation::parse_actions("0.5,7.5>0.5,4.5").unwrap())
+        .unwrap();
+    let position = notation::write_position(game.turns().working_board_ref(), game.to_move());
+
+    assert!(wait(&mut game).is_none());
+    assert!(!game.is_thinking());
+    assert!(game.engine().is_some());
+    assert_eq!(
+        notation::write_position(game.turns().working_board_ref(), game.to_move()),
+        position
+    );
+}
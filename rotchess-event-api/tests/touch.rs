@@ -0,0 +1,154 @@
+//! Fingers on a screen, as synthetic touch events.
+
+use std::f32::consts::FRAC_PI_2;
+
+use rotchess_event_api::{
+    Event, RotchessEmulator, ThingHappened,
+    piece::{Pieces, Side},
+    recording::EventLog,
+    view::View,
+};
+
+/// Pixels to a square, so that touches go through the view like they would on screen.
+const SCALE: f32 = 100.;
+
+fn game() -> RotchessEmulator {
+    let mut game = RotchessEmulator::with(Pieces::standard_board());
+    game.set_view(View::default().with_scale(SCALE));
+    game
+}
+
+fn start(game: &mut RotchessEmulator, id: u64, (x, y): (f32, f32)) -> Option<ThingHappened> {
+    let (x, y) = (x * SCALE, y * SCALE);
+    game.handle_event(Event::TouchStart { id, x, y })
+}
+
+fn drag(game: &mut RotchessEmulator, id: u64, (x, y): (f32, f32)) -> Option<ThingHappened> {
+    let (x, y) = (x * SCALE, y * SCALE);
+    game.handle_event(Event::TouchMove { id, x, y })
+}
+
+fn end(game: &mut RotchessEmulator, id: u64, (x, y): (f32, f32)) -> Option<ThingHappened> {
+    let (x, y) = (x * SCALE, y * SCALE);
+    game.handle_event(Event::TouchEnd { id, x, y })
+}
+
+fn tap(game: &mut RotchessEmulator, id: u64, at: (f32, f32)) -> Option<ThingHappened> {
+    start(game, id, at);
+    end(game, id, at)
+}
+
+#[test]
+fn one_finger_taps_and_drags_like_the_mouse() {
+    let mut game = game();
+
+    // tap the e pawn, then where it goes.
+    assert!(tap(&mut game, 0, (4.5, 6.5)).is_none());
+    assert!(game.selected().is_some());
+    assert!(matches!(
+        tap(&mut game, 1, (4.5, 4.5)),
+        Some(ThingHappened::Move {
+            from: (4.5, 6.5),
+            to: (4.5, 4.5),
+            ..
+        })
+    ));
+
+    // drag the e pawn instead.
+    start(&mut game, 7, (4.5, 1.5));
+    drag(&mut game, 7, (4.5, 2.5));
+    assert_eq!(
+        game.selected().map(|(piece, _)| piece.center()),
+        Some((4.5, 2.5))
+    );
+    assert!(matches!(
+        end(&mut game, 7, (4.5, 3.5)),
+        Some(ThingHappened::Move {
+            from: (4.5, 1.5),
+            to: (4.5, 3.5),
+            to_move: Side::White,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn two_fingers_twist_the_selected_piece() {
+    let mut game = game();
+    tap(&mut game, 0, (4.5, 6.5));
+    let id = game.selected().unwrap().0.id();
+
+    // the first finger grabs the pawn, but the second lets go of it.
+    start(&mut game, 1, (4.5, 6.5));
+    assert!(drag(&mut game, 1, (4.6, 6.5)).is_none());
+    start(&mut game, 2, (6.6, 6.5));
+    assert_eq!(
+        game.selected().map(|(piece, _)| piece.center()),
+        Some((4.5, 6.5))
+    );
+
+    // a quarter turn counterclockwise, moving the second finger over the first.
+    drag(&mut game, 2, (4.6, 4.5));
+    let angle = game.selected().unwrap().0.angle();
+    assert!((angle - FRAC_PI_2).abs() < 1e-5, "{angle}");
+
+    // a third finger does nothing.
+    assert!(tap(&mut game, 3, (0.5, 0.5)).is_none());
+
+    let Some(ThingHappened::Rotate {
+        piece,
+        from,
+        to,
+        to_move,
+        ..
+    }) = end(&mut game, 2, (4.6, 4.5))
+    else {
+        panic!("lifting a finger plays the twist");
+    };
+    assert_eq!((piece, from, to_move), (id, 0., Side::Black));
+    assert!((to - FRAC_PI_2).abs() < 1e-5, "{to}");
+
+    // the finger left down is done with, even over a black piece.
+    assert!(drag(&mut game, 1, (4.5, 1.5)).is_none());
+    assert!(end(&mut game, 1, (4.5, 1.5)).is_none());
+    assert_eq!(game.to_move(), Side::Black);
+}
+
+#[test]
+fn a_twist_takes_over_a_dragged_travel_point() {
+    let mut game = game();
+    tap(&mut game, 0, (4.5, 6.5));
+
+    // drag a travel point around a little, turning the pawn.
+    start(&mut game, 1, (4.5, 5.5));
+    drag(&mut game, 1, (5.5, 6.5));
+    let dragged = game.selected().unwrap().0.angle();
+    assert!(dragged != 0.);
+
+    // then twist it back a quarter turn clockwise.
+    start(&mut game, 2, (6.5, 6.5));
+    drag(&mut game, 2, (5.5, 7.5));
+    let Some(ThingHappened::Rotate { from, to, .. }) = end(&mut game, 1, (5.5, 6.5)) else {
+        panic!("lifting a finger plays the twist");
+    };
+    assert_eq!(from, 0.);
+    assert!((to - (dragged - FRAC_PI_2)).abs() < 1e-5, "{to}");
+}
+
+#[test]
+fn touches_are_recorded_and_replay() {
+    let mut game = game();
+    game.start_recording(|| 0.);
+    tap(&mut game, 0, (4.5, 6.5));
+    start(&mut game, 1, (4.5, 6.5));
+    start(&mut game, 2, (6.5, 6.5));
+    drag(&mut game, 2, (4.5, 4.5));
+    assert!(end(&mut game, 2, (4.5, 4.5)).is_some());
+    end(&mut game, 1, (4.5, 6.5));
+
+    let log = game.stop_recording().unwrap();
+    let text = log.to_string();
+    assert!(text.contains("touch-start 2 6.5,6.5"), "{text}");
+    let log: EventLog = text.parse().unwrap();
+    log.replay().unwrap();
+}
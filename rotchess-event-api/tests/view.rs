@@ -0,0 +1,67 @@
+//! Playing on a board that isn't where the emulator thinks it is.
+
+use std::f32::consts::FRAC_PI_3;
+
+mod common;
+
+use rotchess_event_api::{
+    RotchessEmulator, ThingHappened,
+    piece::{Pieces, Side},
+    view::View,
+};
+
+use common::{down, motion, up};
+
+fn close((ax, ay): (f32, f32), (bx, by): (f32, f32)) -> bool {
+    (ax - bx).abs() < 1e-4 && (ay - by).abs() < 1e-4
+}
+
+#[test]
+fn screen_and_board_round_trip() {
+    for view in [
+        View::default(),
+        View::default().with_scale(80.).with_offset((12., -7.)),
+        View::default().with_flipped(true).with_scale(50.),
+        View::default()
+            .with_rotation(FRAC_PI_3)
+            .with_flipped(true)
+            .with_scale(33.)
+            .with_offset((100., 40.)),
+    ] {
+        for p in [(0., 0.), (4., 4.), (0.5, 7.5), (7.25, 1.75), (-1., 9.)] {
+            let back = view.to_board(view.to_screen(p));
+            assert!(close(back, p), "{view:?}: {p:?} came back as {back:?}");
+        }
+    }
+}
+
+#[test]
+fn flipped_views_drag_the_piece_under_the_mouse() {
+    let view = View::default()
+        .with_flipped(true)
+        .with_scale(100.)
+        .with_offset((10., 20.));
+    let mut game = RotchessEmulator::with(Pieces::standard_board());
+    game.set_view(view);
+
+    // the e pawn is near the top left, with black at the bottom.
+    let from = view.to_screen((4.5, 6.5));
+    let to = view.to_screen((4.5, 4.5));
+    assert!(close(from, (360., 170.)), "{from:?}");
+
+    down(&mut game, from);
+    motion(&mut game, (from.0, from.1 + 100.));
+    assert_eq!(
+        game.selected().map(|(piece, _)| piece.center()),
+        Some((4.5, 5.5))
+    );
+    assert!(matches!(
+        up(&mut game, to),
+        Some(ThingHappened::Move {
+            from: (4.5, 6.5),
+            to: (4.5, 4.5),
+            to_move: Side::Black,
+            ..
+        })
+    ));
+}
@@ -0,0 +1,168 @@
+//! Messages over a TCP stream, or in tests, a lossy channel.
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{Shutdown, TcpStream},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+use crate::{NetError, message::Message};
+
+/// Read one line, without its newline. Running out of stream is [`NetError::Closed`].
+pub(crate) fn read_line(reader: &mut impl BufRead) -> Result<String, NetError> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(NetError::Closed);
+    }
+    Ok(line.trim_end().to_string())
+}
+
+/// Where a connection's messages go, already written as lines.
+trait Outgoing: Send {
+    fn send_line(&mut self, line: &str) -> io::Result<()>;
+
+    /// Stop, so the other end notices we've gone.
+    fn close(&mut self);
+}
+
+impl Outgoing for TcpStream {
+    fn send_line(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self, "{line}")?;
+        self.flush()
+    }
+
+    fn close(&mut self) {
+        // wakes the reading thread, so it can end.
+        _ = self.shutdown(Shutdown::Both);
+    }
+}
+
+/// A stream of [`Message`]s both ways.
+///
+/// Incoming messages are read by a thread of their own, so they can be waited for or
+/// polled. Once the connection's gone, that's [`NetError::Dropped`].
+pub(crate) struct Connection {
+    outgoing: Box<dyn Outgoing>,
+    incoming: Receiver<Result<Message, NetError>>,
+}
+
+impl Connection {
+    /// Start reading messages off `reader`, which may already have buffered some.
+    pub(crate) fn tcp(mut reader: BufReader<TcpStream>) -> io::Result<Self> {
+        let stream = reader.get_ref().try_clone()?;
+        let (sender, incoming) = mpsc::channel();
+        thread::spawn(move || {
+            loop {
+                let message = read_line(&mut reader).and_then(|line| {
+                    Message::parse(&line)
+                        .map_err(|e| NetError::Protocol(format!("can't read {line:?}: {e}")))
+                });
+                let done = matches!(message, Err(NetError::Closed | NetError::Io(_)));
+                // the receiving end says dropped once we're gone.
+                if done || sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            outgoing: Box::new(stream),
+            incoming,
+        })
+    }
+
+    pub(crate) fn send(&mut self, message: &Message) -> Result<(), NetError> {
+        self.outgoing
+            .send_line(&message.to_string())
+            .map_err(|_| NetError::Dropped)
+    }
+
+    /// Wait up to `timeout` for the next message. `None` if nothing came.
+    pub(crate) fn recv_timeout(&self, timeout: Duration) -> Result<Option<Message>, NetError> {
+        match self.incoming.recv_timeout(timeout) {
+            Ok(message) => message.map(Some),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(NetError::Dropped),
+        }
+    }
+
+    /// The next message, if one has come in.
+    pub(crate) fn try_recv(&self) -> Result<Option<Message>, NetError> {
+        match self.incoming.try_recv() {
+            Ok(message) => message.map(Some),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(NetError::Dropped),
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.outgoing.close();
+    }
+}
+
+#[cfg(test)]
+mod lossy {
+    use std::{io, sync::mpsc::Sender};
+
+    use rotchess_core::rng::Rng;
+
+    use super::{Connection, Outgoing};
+    use crate::{NetError, message::Message};
+
+    /// One end of a channel that loses some lines, and sends some twice.
+    struct Lossy {
+        sender: Option<Sender<Result<Message, NetError>>>,
+        rng: Rng,
+        /// Out of 100, how many lines are lost, and separately, how many repeated.
+        loss: usize,
+    }
+
+    impl Outgoing for Lossy {
+        fn send_line(&mut self, line: &str) -> io::Result<()> {
+            let Some(sender) = &self.sender else {
+                return Err(io::ErrorKind::NotConnected.into());
+            };
+            let times = match self.rng.below(100) {
+                roll if roll < self.loss => 0,
+                roll if roll < 2 * self.loss => 2,
+                _ => 1,
+            };
+            for _ in 0..times {
+                // through text, like the real thing.
+                let message = Message::parse(line).map_err(|e| NetError::Protocol(e.to_string()));
+                sender
+                    .send(message)
+                    .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+            }
+            Ok(())
+        }
+
+        fn close(&mut self) {
+            self.sender = None;
+        }
+    }
+
+    impl Connection {
+        /// Two ends of a connection that loses `loss` in 100 messages, and repeats as
+        /// many, as picked by `seed`.
+        pub(crate) fn lossy_pair(loss: usize, seed: u64) -> (Self, Self) {
+            let (a_sender, a_incoming) = std::sync::mpsc::channel();
+            let (b_sender, b_incoming) = std::sync::mpsc::channel();
+            let end = |sender, incoming, seed| Self {
+                outgoing: Box::new(Lossy {
+                    sender: Some(sender),
+                    rng: Rng::new(seed),
+                    loss,
+                }),
+                incoming,
+            };
+            (
+                end(b_sender, a_incoming, seed),
+                end(a_sender, b_incoming, seed + 1),
+            )
+        }
+    }
+}
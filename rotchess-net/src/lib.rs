@@ -0,0 +1,196 @@
+//! Online play over TCP.
+//!
+//! One player [hosts](Host::listen) and gets a [`LobbyKey`] to give the other, who
+//! [joins](Link::join) with it. The host picks the sides and the [`Rules`]; the
+//! client may ask for a side, and is turned away if it's taken. After that, each
+//! player has a [`Link`] that sends their turns and checks the other's with the same
+//! rules a local game uses, so neither can sneak in a turn the other wouldn't allow.
+//!
+//! Instead of one player hosting, both may join a game on a [`server`], which checks
+//! every turn itself and lets others watch.
+//!
+//! Turns are numbered and hashed, so a lost or repeated turn, or a game that's
+//! drifted, is noticed and put right from the host's or server's game. A client
+//! whose connection drops may [reconnect](Link::reconnect) and carry on.
+//!
+//! What goes over the wire is described in [`message`]. On the web, hosting and
+//! joining fail with an unsupported error.
+
+use std::{
+    collections::hash_map::RandomState,
+    error::Error,
+    fmt,
+    hash::{BuildHasher, Hasher},
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    str::FromStr,
+};
+
+use rotchess_core::{
+    notation::{self, NotationError},
+    piece::{Pieces, Side},
+};
+
+mod connection;
+mod link;
+pub mod message;
+pub mod server;
+
+pub use link::{Host, Link};
+
+/// What a client needs to join a hosted game: where the host is, and a secret so
+/// that strangers who find the port can't.
+///
+/// Written `address/secret`, like `192.168.1.20:41234/9f86d081884c7d65`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LobbyKey {
+    pub addr: SocketAddr,
+    pub secret: u64,
+}
+
+impl fmt::Display for LobbyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{:016x}", self.addr, self.secret)
+    }
+}
+
+impl FromStr for LobbyKey {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bad = || {
+            format!("{text:?} isn't a lobby key, which looks like 10.0.0.2:4000/9f86d081884c7d65")
+        };
+        let (addr, secret) = text.trim().rsplit_once('/').ok_or_else(bad)?;
+        Ok(Self {
+            addr: addr.parse().map_err(|_| bad())?,
+            secret: u64::from_str_radix(secret, 16).map_err(|_| bad())?,
+        })
+    }
+}
+
+/// An address others on the network can probably reach us at, for lobby keys.
+///
+/// No packets are sent: connecting a UDP socket only picks the interface a packet
+/// would leave by. Falls back to localhost.
+fn local_ip() -> IpAddr {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9))?;
+            socket.local_addr()
+        })
+        .map(|addr| addr.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+}
+
+/// A number nobody else could guess, for secrets and tokens.
+fn random() -> u64 {
+    // randomly seeded by the os.
+    RandomState::new().build_hasher().finish()
+}
+
+/// How a game is played, decided by the host.
+#[derive(Clone)]
+pub struct Rules {
+    /// The starting position.
+    pub start: Pieces,
+    /// Who moves first.
+    pub to_move: Side,
+}
+
+impl Default for Rules {
+    /// The standard board, white first.
+    fn default() -> Self {
+        Self {
+            start: Pieces::standard_board(),
+            to_move: Side::White,
+        }
+    }
+}
+
+impl fmt::Display for Rules {
+    /// The starting position in [notation](rotchess_core::notation).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", notation::write_position(&self.start, self.to_move))
+    }
+}
+
+impl FromStr for Rules {
+    type Err = NotationError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (start, to_move) = notation::parse_position(text)?;
+        Ok(Self { start, to_move })
+    }
+}
+
+/// Why an online game couldn't go on.
+#[derive(Debug)]
+pub enum NetError {
+    Io(io::Error),
+    /// The other side left.
+    Closed,
+    /// The connection was lost without a goodbye. A joiner may
+    /// [reconnect](Link::reconnect).
+    Dropped,
+    /// The other side sent something we didn't expect.
+    Protocol(String),
+    /// The host turned us away, or the other side refused our last turn.
+    Refused(String),
+    /// A turn broke the rules. If the other side sent it, they've been told.
+    Illegal(NotationError),
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetError::Io(e) => write!(f, "{e}"),
+            NetError::Closed => write!(f, "the other side left"),
+            NetError::Dropped => write!(f, "the connection dropped"),
+            NetError::Protocol(why) => write!(f, "protocol error: {why}"),
+            NetError::Refused(why) => write!(f, "refused: {why}"),
+            NetError::Illegal(e) => write!(f, "illegal turn: {e}"),
+        }
+    }
+}
+
+impl Error for NetError {}
+
+impl From<io::Error> for NetError {
+    fn from(e: io::Error) -> Self {
+        NetError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LobbyKey, Rules};
+    use rotchess_core::piece::{Pieces, Side};
+
+    #[test]
+    fn lobby_keys_round_trip() {
+        let key = LobbyKey {
+            addr: "10.0.0.2:4000".parse().unwrap(),
+            secret: 0x9f86d081884c7d65,
+        };
+        assert_eq!(key.to_string(), "10.0.0.2:4000/9f86d081884c7d65");
+        assert_eq!(key.to_string().parse(), Ok(key));
+        assert_eq!(" [::1]:80/1 ".parse::<LobbyKey>().unwrap().secret, 1);
+        assert!("10.0.0.2:4000".parse::<LobbyKey>().is_err());
+        assert!("10.0.0.2/1".parse::<LobbyKey>().is_err());
+    }
+
+    #[test]
+    fn rules_round_trip() {
+        let rules = Rules {
+            start: Pieces::chess960_board(7),
+            to_move: Side::Black,
+        };
+        let parsed: Rules = rules.to_string().parse().unwrap();
+        assert_eq!(parsed.to_move, Side::Black);
+        assert_eq!(
+            parsed.start.position_hash(),
+            Pieces::chess960_board(7).position_hash()
+        );
+    }
+}
@@ -0,0 +1,791 @@
+//! Hosting, joining, and playing.
+
+use std::{
+    cmp::Ordering,
+    io::{self, BufReader, ErrorKind, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    thread,
+    time::{Duration, Instant},
+};
+
+use rotchess_core::{
+    notation::{self, Action, NotationError},
+    piece::Side,
+    search::SearchLimits,
+    turn::{Move, Turns},
+};
+use rotchess_event_api::{Control, Event, RotchessEmulator, ThingHappened};
+
+use crate::{
+    LobbyKey, NetError, Rules,
+    connection::{Connection, read_line},
+    local_ip,
+    message::{Message, VERSION},
+    random,
+};
+
+/// How long connecting and shaking hands may take.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often a side waiting for the other says where it's at.
+const HEARTBEAT: Duration = Duration::from_secs(1);
+
+/// Play `turns` from the start `rules` give.
+fn replay(rules: &Rules, turns: &[Vec<Action>]) -> Result<Turns, NotationError> {
+    let mut game = Turns::with(rules.start.clone());
+    game.set_to_move(rules.to_move);
+    for actions in turns {
+        notation::play_actions(&mut game, actions)?;
+    }
+    Ok(game)
+}
+
+/// Let a client in as `side`, telling it the game so far. `None` if it's left.
+fn welcome(
+    mut reader: BufReader<TcpStream>,
+    side: Side,
+    token: u64,
+    rules: &Rules,
+    turns: &[Vec<Action>],
+) -> Option<Connection> {
+    let welcome = Message::Welcome {
+        side,
+        token,
+        rules: rules.clone(),
+    };
+    let sync = Message::Sync {
+        rules: rules.clone(),
+        turns: turns.to_vec(),
+    };
+    writeln!(reader.get_mut(), "{welcome}\n{sync}").ok()?;
+    reader.get_ref().set_read_timeout(None).ok()?;
+    Connection::tcp(reader).ok()
+}
+
+/// A hosted game, waiting for someone to join.
+pub struct Host {
+    listener: TcpListener,
+    key: LobbyKey,
+    /// The host's side.
+    side: Side,
+    rules: Rules,
+}
+
+impl Host {
+    /// Listen on `addr` for someone to play the side opposite `side`.
+    ///
+    /// Listening on an unspecified address like `0.0.0.0:0` puts this machine's
+    /// address on the network in the [key](Self::key).
+    pub fn listen(addr: impl ToSocketAddrs, side: Side, rules: Rules) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let mut addr = listener.local_addr()?;
+        if addr.ip().is_unspecified() {
+            addr.set_ip(local_ip());
+        }
+        Ok(Self {
+            listener,
+            key: LobbyKey {
+                addr,
+                secret: random(),
+            },
+            side,
+            rules,
+        })
+    }
+
+    /// What the other player needs to join.
+    pub fn key(&self) -> LobbyKey {
+        self.key
+    }
+
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    /// Wait for someone to join. Clients with the wrong key or version, or who want
+    /// our side, are turned away while we keep waiting.
+    pub fn accept(&self) -> Result<Link, NetError> {
+        self.listener.set_nonblocking(false)?;
+        loop {
+            let (stream, _) = self.listener.accept()?;
+            if let Some(link) = self.greet(stream) {
+                return Ok(link);
+            }
+        }
+    }
+
+    /// Like [`Self::accept`], but doesn't wait. `None` if nobody's joined yet.
+    pub fn poll(&self) -> Result<Option<Link>, NetError> {
+        self.listener.set_nonblocking(true)?;
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    if let Some(link) = self.greet(stream) {
+                        return Ok(Some(link));
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Shake hands with a client. `None` if it was turned away, or left.
+    fn greet(&self, stream: TcpStream) -> Option<Link> {
+        stream.set_nonblocking(false).ok()?;
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).ok()?;
+        let mut reader = BufReader::new(stream);
+        let line = read_line(&mut reader).ok()?;
+
+        let refusal = match Message::parse(&line) {
+            Ok(Message::Hello { version, .. }) if version != VERSION => {
+                Some(format!("the host speaks version {VERSION}, not {version}"))
+            }
+            Ok(Message::Hello { secret, .. }) if secret != self.key.secret => {
+                Some(String::from("wrong lobby key"))
+            }
+            Ok(Message::Hello {
+                side: Some(side), ..
+            }) if side == self.side => Some(format!("the host plays {side:?}")),
+            Ok(Message::Hello { .. }) => None,
+            _ => Some(format!("expected hello, not {line:?}")),
+        };
+        if let Some(why) = refusal {
+            _ = writeln!(reader.get_mut(), "{}", Message::Reject(why));
+            return None;
+        }
+
+        let token = random();
+        let connection = welcome(reader, self.side.toggled(), token, &self.rules, &[])?;
+        // kept for the client to resume on, if it drops.
+        let listener = self.listener.try_clone().ok()?;
+        listener.set_nonblocking(true).ok()?;
+        let mut link = Link::with(connection, Some(self.side), self.rules.clone(), true);
+        link.key = Some(self.key);
+        link.token = token;
+        link.listener = Some(listener);
+        Some(link)
+    }
+}
+
+/// What joining got us.
+struct Welcomed {
+    connection: Connection,
+    /// `None` if we're watching.
+    side: Option<Side>,
+    token: u64,
+    rules: Rules,
+    turns: Vec<Vec<Action>>,
+}
+
+/// Connect to `key`, say `greeting`, and be welcomed, or let watch, and synced.
+fn dial(key: &LobbyKey, greeting: &Message) -> Result<Welcomed, NetError> {
+    let stream = TcpStream::connect_timeout(&key.addr, HANDSHAKE_TIMEOUT)?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    writeln!(reader.get_mut(), "{greeting}")?;
+
+    let line = read_line(&mut reader)?;
+    let (side, token, rules) = match Message::parse(&line) {
+        Ok(Message::Welcome { side, token, rules }) => (Some(side), token, rules),
+        Ok(Message::Watching(rules)) => (None, 0, rules),
+        Ok(Message::Reject(why)) => return Err(NetError::Refused(why)),
+        _ => {
+            return Err(NetError::Protocol(format!(
+                "expected welcome, not {line:?}"
+            )));
+        }
+    };
+    let line = read_line(&mut reader)?;
+    let Ok(Message::Sync { turns, .. }) = Message::parse(&line) else {
+        return Err(NetError::Protocol(format!("expected sync, not {line:?}")));
+    };
+    replay(&rules, &turns).map_err(NetError::Illegal)?;
+    reader.get_ref().set_read_timeout(None)?;
+    Ok(Welcomed {
+        connection: Connection::tcp(reader)?,
+        side,
+        token,
+        rules,
+        turns,
+    })
+}
+
+/// One player's end of an online game, or a spectator's.
+///
+/// The game itself is a [`RotchessEmulator`] kept by the caller, made with
+/// [`Self::new_emulator`]. Events for it go through [`Self::handle_event`], which
+/// sends our turns, and [`Self::poll`] plays the other side's turns on it.
+///
+/// Either end may be the authority, whose game wins when the two disagree: see
+/// [`message`](crate::message) for how they agree again. That's the host, or for
+/// games on a [server](crate::server), the server. The host takes back a client
+/// that drops and resumes while polling or waiting.
+///
+/// Says bye when dropped.
+pub struct Link {
+    connection: Connection,
+    /// Our side, or `None` if we're watching.
+    side: Option<Side>,
+    rules: Rules,
+    /// Every turn played so far, by either side.
+    played: Vec<Vec<Action>>,
+    authority: bool,
+    /// The key we were joined with, or are hosting with.
+    key: Option<LobbyKey>,
+    /// What the client resumes with.
+    token: u64,
+    /// Where a host listens for the client to resume.
+    listener: Option<TcpListener>,
+    heartbeat: Duration,
+    last_heartbeat: Instant,
+}
+
+impl Link {
+    fn with(connection: Connection, side: Option<Side>, rules: Rules, authority: bool) -> Self {
+        Self {
+            connection,
+            side,
+            rules,
+            played: vec![],
+            authority,
+            key: None,
+            token: 0,
+            listener: None,
+            heartbeat: HEARTBEAT,
+            last_heartbeat: Instant::now(),
+        }
+    }
+
+    /// Join the game hosted with `key`, optionally asking to play `side`.
+    pub fn join(key: &LobbyKey, side: Option<Side>) -> Result<Self, NetError> {
+        let hello = Message::Hello {
+            version: VERSION,
+            secret: key.secret,
+            side,
+        };
+        Self::dial(key, &hello)
+    }
+
+    /// Watch a game on the [server](crate::server) with `key`.
+    ///
+    /// Our emulator only goes through history, and [`Self::wait`] waits until
+    /// the game's over. [`Self::played`] makes a move list.
+    pub fn watch(key: &LobbyKey) -> Result<Self, NetError> {
+        let watch = Message::Watch {
+            version: VERSION,
+            secret: key.secret,
+        };
+        Self::dial(key, &watch)
+    }
+
+    fn dial(key: &LobbyKey, greeting: &Message) -> Result<Self, NetError> {
+        let welcomed = dial(key, greeting)?;
+        let mut link = Self::with(welcomed.connection, welcomed.side, welcomed.rules, false);
+        link.played = welcomed.turns;
+        link.key = Some(*key);
+        link.token = welcomed.token;
+        Ok(link)
+    }
+
+    /// After the connection has [dropped](NetError::Dropped), join again as the
+    /// same side, and catch up on any turns missed. Returns them.
+    ///
+    /// Only a joiner can, and only if the host or server still has our seat. A
+    /// spectator just watches again.
+    pub fn reconnect(
+        &mut self,
+        emulator: &mut RotchessEmulator,
+    ) -> Result<Vec<Vec<Action>>, NetError> {
+        let Some(key) = self.key.filter(|_| !self.authority) else {
+            return Err(NetError::Refused(String::from(
+                "only the joining side can reconnect",
+            )));
+        };
+        let greeting = match self.side {
+            Some(_) => Message::Resume {
+                version: VERSION,
+                secret: key.secret,
+                token: self.token,
+            },
+            None => Message::Watch {
+                version: VERSION,
+                secret: key.secret,
+            },
+        };
+        let welcomed = dial(&key, &greeting)?;
+        if welcomed.side != self.side {
+            return Err(NetError::Protocol(format!(
+                "we were {:?}, but came back as {:?}",
+                self.side, welcomed.side
+            )));
+        }
+        self.connection = welcomed.connection;
+        let sync = Message::Sync {
+            rules: welcomed.rules,
+            turns: welcomed.turns,
+        };
+        self.receive(emulator, sync)
+    }
+
+    /// Our side, or `None` if we're watching.
+    pub fn side(&self) -> Option<Side> {
+        self.side
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Every turn played so far, by either side.
+    pub fn played(&self) -> &[Vec<Action>] {
+        &self.played
+    }
+
+    /// The game so far, where events may only move our side, or if we're watching,
+    /// only go through history.
+    pub fn new_emulator(&self) -> RotchessEmulator {
+        let mut emulator = RotchessEmulator::with(self.rules.start.clone());
+        emulator.set_turns(replay(&self.rules, &self.played).expect("checked when joining"));
+        emulator.set_control(match self.side {
+            Some(side) => Control::Side(side),
+            None => Control::Spectator,
+        });
+        emulator
+    }
+
+    /// Handle an event like [`RotchessEmulator::handle_event`], sending the turn it
+    /// made, if any.
+    pub fn handle_event(
+        &mut self,
+        emulator: &mut RotchessEmulator,
+        event: Event,
+    ) -> Result<Option<ThingHappened>, NetError> {
+        let happened = emulator.handle_event(event);
+        let action = match happened {
+            Some(ThingHappened::Move { from, to, .. }) => Action::Travel { from, to },
+            Some(ThingHappened::Rotate { piece, to, .. }) => {
+                let at = emulator
+                    .turns()
+                    .working_board_ref()
+                    .get(piece)
+                    .expect("the piece just rotated")
+                    .center();
+                Action::Rotate { at, angle: to }
+            }
+            _ => return Ok(happened),
+        };
+        self.sent(emulator, vec![action])?;
+        Ok(happened)
+    }
+
+    /// Play a turn for our side and send it. Nothing is sent if it's illegal.
+    pub fn play(
+        &mut self,
+        emulator: &mut RotchessEmulator,
+        actions: &[Action],
+    ) -> Result<(), NetError> {
+        self.our_turn(emulator)?;
+        emulator.play_actions(actions).map_err(NetError::Illegal)?;
+        self.sent(emulator, actions.to_vec())
+    }
+
+    /// Let `emulator`'s engine play our turn, and send it. `None` if we have no moves.
+    pub fn play_engine_move(
+        &mut self,
+        emulator: &mut RotchessEmulator,
+        limits: &SearchLimits,
+    ) -> Result<Option<Move>, NetError> {
+        self.our_turn(emulator)?;
+        let before = emulator.turns().working_board_ref().clone();
+        let Some(move_) = emulator.make_best_move_with(limits) else {
+            return Ok(None);
+        };
+        self.sent(emulator, notation::move_actions(&move_, &before).to_vec())?;
+        Ok(Some(move_))
+    }
+
+    /// Play our first premove like [`RotchessEmulator::play_premove`], sending it if
+    /// it was played.
+    pub fn play_premove(
+        &mut self,
+        emulator: &mut RotchessEmulator,
+    ) -> Result<Option<ThingHappened>, NetError> {
+        let before = emulator.turns().working_board_ref().clone();
+        let happened = emulator.play_premove();
+        if let Some(ThingHappened::PremovePlayed { premove, .. }) = &happened {
+            // actions refer to the board as it was before the turn.
+            let at = before.get(premove.piece).expect("it just moved").center();
+            let travel = premove
+                .travel
+                .map(|(_, to)| Action::Travel { from: at, to });
+            let rotate = premove.rotation.map(|angle| Action::Rotate { at, angle });
+            self.sent(emulator, travel.into_iter().chain(rotate).collect())?;
+        }
+        Ok(happened)
+    }
+
+    /// Go to the latest turn, checking it's ours to play.
+    fn our_turn(&self, emulator: &mut RotchessEmulator) -> Result<(), NetError> {
+        emulator.handle_event(Event::LastTurn);
+        if Some(emulator.to_move()) != self.side {
+            return Err(NetError::Illegal(NotationError::BadTurn(String::from(
+                "it's not our turn",
+            ))));
+        }
+        Ok(())
+    }
+
+    /// Remember and send a turn we've just played on `emulator`.
+    fn sent(&mut self, emulator: &RotchessEmulator, actions: Vec<Action>) -> Result<(), NetError> {
+        let number = self.played.len();
+        self.played.push(actions.clone());
+        let turn = Message::Turn {
+            number,
+            hash: emulator.turns().turn_hash(number + 1),
+            actions,
+        };
+        self.send(&turn)
+    }
+
+    /// Send a message. A host carries on without a client that's dropped, since
+    /// it may resume.
+    fn send(&mut self, message: &Message) -> Result<(), NetError> {
+        match self.connection.send(message) {
+            Err(NetError::Dropped) if self.listener.is_some() => Ok(()),
+            sent => sent,
+        }
+    }
+
+    /// Whether we're waiting for the other side to play.
+    fn waiting(&self, emulator: &RotchessEmulator) -> bool {
+        Some(emulator.to_move()) != self.side && emulator.turns().winner().is_none()
+    }
+
+    /// Play every turn the other side has sent since we last looked, without waiting.
+    ///
+    /// Returns the turns played. If our game was replaced by the authority's, these
+    /// are the turns from where they differ.
+    pub fn poll(&mut self, emulator: &mut RotchessEmulator) -> Result<Vec<Vec<Action>>, NetError> {
+        self.take_back();
+        let mut ans = vec![];
+        loop {
+            match self.connection.try_recv() {
+                Ok(Some(message)) => ans.extend(self.receive(emulator, message)?),
+                Ok(None) => break,
+                Err(NetError::Dropped) if self.listener.is_some() => break,
+                Err(e) => return Err(e),
+            }
+        }
+        self.beat(emulator);
+        Ok(ans)
+    }
+
+    /// Wait for the other side's next turn and play it, like [`Self::poll`].
+    ///
+    /// Returns early, maybe with nothing, if the turn becomes ours some other way.
+    pub fn wait(&mut self, emulator: &mut RotchessEmulator) -> Result<Vec<Vec<Action>>, NetError> {
+        loop {
+            self.take_back();
+            let message = match self.connection.recv_timeout(self.heartbeat) {
+                Err(NetError::Dropped) if self.listener.is_some() => {
+                    thread::sleep(self.heartbeat);
+                    None
+                }
+                received => received?,
+            };
+            let ans = match message {
+                Some(message) => self.receive(emulator, message)?,
+                None => vec![],
+            };
+            self.beat(emulator);
+            if !ans.is_empty() || !self.waiting(emulator) {
+                return Ok(ans);
+            }
+        }
+    }
+
+    /// Say where we're at, if we're waiting and haven't lately.
+    fn beat(&mut self, emulator: &RotchessEmulator) {
+        if self.waiting(emulator) && self.last_heartbeat.elapsed() >= self.heartbeat {
+            self.last_heartbeat = Instant::now();
+            _ = self.send(&self.at(emulator));
+        }
+    }
+
+    fn at(&self, emulator: &RotchessEmulator) -> Message {
+        Message::At {
+            count: self.played.len(),
+            hash: emulator.turns().turn_hash(self.played.len()),
+        }
+    }
+
+    fn sync(&self) -> Message {
+        Message::Sync {
+            rules: self.rules.clone(),
+            turns: self.played.clone(),
+        }
+    }
+
+    /// Send our turns from `number` on again.
+    fn resend(&mut self, emulator: &RotchessEmulator, number: usize) {
+        for number in number..self.played.len() {
+            let turn = Message::Turn {
+                number,
+                hash: emulator.turns().turn_hash(number + 1),
+                actions: self.played[number].clone(),
+            };
+            _ = self.send(&turn);
+        }
+    }
+
+    /// Answer the other side being at `count` turns when we aren't, or at a position
+    /// with another hash.
+    fn disagree(&mut self, emulator: &RotchessEmulator, count: usize) {
+        let ours = self.played.len();
+        let answer = match (self.authority, count.cmp(&ours)) {
+            (true, Ordering::Greater) => self.at(emulator),
+            (true, _) => self.sync(),
+            (false, Ordering::Less) if self.side.is_some() => {
+                return self.resend(emulator, count);
+            }
+            (false, _) => Message::Resync,
+        };
+        _ = self.send(&answer);
+    }
+
+    /// Take the other side back, if it's dropped and resumed.
+    fn take_back(&mut self) {
+        let (Some(listener), Some(key)) = (&self.listener, self.key) else {
+            return;
+        };
+        let Ok((stream, _)) = listener.accept() else {
+            return;
+        };
+        if stream.set_nonblocking(false).is_err()
+            || stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).is_err()
+        {
+            return;
+        }
+        let mut reader = BufReader::new(stream);
+        let Ok(line) = read_line(&mut reader) else {
+            return;
+        };
+        match Message::parse(&line) {
+            Ok(Message::Resume {
+                version,
+                secret,
+                token,
+            }) if version == VERSION && secret == key.secret && token == self.token => {}
+            _ => {
+                let why = String::from("the game has started, and only its players may resume");
+                _ = writeln!(reader.get_mut(), "{}", Message::Reject(why));
+                return;
+            }
+        }
+        let side = self.side.expect("hosts play").toggled();
+        if let Some(connection) = welcome(reader, side, self.token, &self.rules, &self.played) {
+            self.connection = connection;
+        }
+    }
+
+    /// Handle a message from the other side. Returns the turns it played.
+    fn receive(
+        &mut self,
+        emulator: &mut RotchessEmulator,
+        message: Message,
+    ) -> Result<Vec<Vec<Action>>, NetError> {
+        let count = self.played.len();
+        match message {
+            Message::Turn {
+                number,
+                hash,
+                actions,
+            } if number == count => {
+                // new turns are played at the end, wherever we were looking.
+                emulator.handle_event(Event::LastTurn);
+                let refusal = if Some(emulator.to_move()) == self.side {
+                    Some(NotationError::BadTurn(String::from("it's not your turn")))
+                } else {
+                    emulator.play_actions(&actions).err()
+                };
+                if let Some(e) = refusal {
+                    _ = self.send(&Message::Illegal(e.to_string()));
+                    return Err(NetError::Illegal(e));
+                }
+                self.played.push(actions.clone());
+                if emulator.turns().turn_hash(count + 1) != hash {
+                    self.disagree(emulator, count + 1);
+                }
+                Ok(vec![actions])
+            }
+            // one we've had, or one after one we've missed.
+            Message::Turn { number, .. } => {
+                if self.authority {
+                    _ = self.send(&self.at(emulator));
+                } else if number > count {
+                    _ = self.send(&Message::Resync);
+                }
+                Ok(vec![])
+            }
+            Message::At {
+                count: theirs,
+                hash,
+            } => {
+                let ours = emulator.turns().turn_hash(count);
+                if (theirs, hash) != (count, ours) {
+                    self.disagree(emulator, theirs);
+                }
+                Ok(vec![])
+            }
+            Message::Resync => {
+                if self.authority {
+                    _ = self.send(&self.sync());
+                }
+                Ok(vec![])
+            }
+            Message::Sync { turns, .. } => {
+                // a sync older than what we have would lose turns on their way.
+                if self.authority || turns.len() < count {
+                    return Ok(vec![]);
+                }
+                let mut game = replay(&self.rules, &turns).map_err(|e| {
+                    NetError::Protocol(format!("the game sent doesn't play out: {e}"))
+                })?;
+                game.set_eval_weights(emulator.turns().eval_weights().clone());
+                emulator.set_turns(game);
+                let same = self
+                    .played
+                    .iter()
+                    .zip(&turns)
+                    .take_while(|(ours, theirs)| ours == theirs)
+                    .count();
+                self.played = turns;
+                Ok(self.played[same..].to_vec())
+            }
+            Message::Illegal(why) => Err(NetError::Refused(why)),
+            Message::Bye => Err(NetError::Closed),
+            message => Err(NetError::Protocol(format!("didn't expect {message}"))),
+        }
+    }
+}
+
+impl Drop for Link {
+    fn drop(&mut self) {
+        _ = self.connection.send(&Message::Bye);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use rotchess_core::{notation, piece::Side};
+    use rotchess_event_api::RotchessEmulator;
+
+    use super::{Host, Link, replay};
+    use crate::{NetError, Rules, connection::Connection};
+
+    /// Poll both ends until they've played `count` turns and agree, or give up.
+    fn settle(ends: &mut [(Link, RotchessEmulator); 2], count: usize) {
+        for _ in 0..5000 {
+            for (link, game) in ends.iter_mut() {
+                link.poll(game).unwrap();
+            }
+            let [(white, white_game), (black, black_game)] = &*ends;
+            if white.played.len() == count
+                && black.played.len() == count
+                && white_game.turns().turn_hash(count) == black_game.turns().turn_hash(count)
+            {
+                return;
+            }
+            thread::sleep(Duration::from_micros(200));
+        }
+        panic!("never agreed on {count} turns");
+    }
+
+    #[test]
+    fn games_survive_a_lossy_connection() {
+        let (white, black) = Connection::lossy_pair(20, 7);
+        let ends = [
+            Link::with(white, Some(Side::White), Rules::default(), true),
+            Link::with(black, Some(Side::Black), Rules::default(), false),
+        ];
+        let mut ends = ends.map(|mut link| {
+            link.heartbeat = Duration::from_millis(1);
+            let game = link.new_emulator();
+            (link, game)
+        });
+
+        let script = [
+            "4.5,6.5>4.5,4.5",
+            "3.5,1.5>3.5,3.5",
+            "1.5,7.5:0.5",
+            "6.5,0.5:0.5",
+            "3.5,6.5>3.5,5.5",
+            "4.5,1.5>4.5,2.5",
+            "4.5,4.5:0.3",
+            "3.5,3.5:0.3",
+        ];
+        for (number, turn) in script.iter().enumerate() {
+            let (link, game) = &mut ends[number % 2];
+            link.play(game, &notation::parse_actions(turn).unwrap())
+                .unwrap();
+            settle(&mut ends, number + 1);
+        }
+
+        // black's game drifts, say from a bug, and is put right from white's.
+        let [(white, white_game), (black, black_game)] = &mut ends;
+        black.played[7] = notation::parse_actions("3.5,3.5:0.4").unwrap();
+        black_game.set_turns(replay(&black.rules, &black.played).unwrap());
+        assert_ne!(
+            white_game.turns().turn_hash(8),
+            black_game.turns().turn_hash(8)
+        );
+        assert_eq!(white.played.len(), black.played.len());
+        settle(&mut ends, 8);
+        assert_eq!(
+            ends[1].0.played[7],
+            notation::parse_actions(script[7]).unwrap()
+        );
+    }
+
+    #[test]
+    fn a_dropped_client_resumes_with_its_token() {
+        let host = Host::listen("127.0.0.1:0", Side::White, Rules::default()).unwrap();
+        let key = host.key();
+        let joining = thread::spawn(move || Link::join(&key, None).unwrap());
+        let mut white = host.accept().unwrap();
+        let mut black = joining.join().unwrap();
+        let (mut white_game, mut black_game) = (white.new_emulator(), black.new_emulator());
+
+        // black's connection goes before white's turn gets there.
+        black.connection = Connection::lossy_pair(0, 0).0;
+        let e4 = notation::parse_actions("4.5,6.5>4.5,4.5").unwrap();
+        white.play(&mut white_game, &e4).unwrap();
+        assert!(matches!(
+            black.poll(&mut black_game),
+            Err(NetError::Dropped)
+        ));
+
+        // the host takes black back while polling.
+        let resuming = thread::spawn(move || {
+            let missed = black.reconnect(&mut black_game).unwrap();
+            (black, black_game, missed)
+        });
+        while !resuming.is_finished() {
+            white.poll(&mut white_game).unwrap();
+            thread::sleep(Duration::from_millis(1));
+        }
+        let (mut black, mut black_game, missed) = resuming.join().unwrap();
+        assert_eq!(missed, vec![e4]);
+
+        let d5 = notation::parse_actions("3.5,1.5>3.5,3.5").unwrap();
+        black.play(&mut black_game, &d5).unwrap();
+        assert_eq!(white.wait(&mut white_game).unwrap(), vec![d5]);
+        assert!(matches!(
+            white.reconnect(&mut white_game),
+            Err(NetError::Refused(_))
+        ));
+    }
+}
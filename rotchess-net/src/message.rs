@@ -0,0 +1,388 @@
+//! The messages players, hosts and servers send each other, one per line.
+//!
+//! ```text
+//! client> hello rotchess 2 9f86d081884c7d65 b
+//! host>   welcome b 51c3a00e6f1d2b47 w WR@0.5,7.5:0 WN@1.5,7.5:0 ...
+//! host>   sync w WR@0.5,7.5:0 WN@1.5,7.5:0 ...
+//! host>   turn 0 0e1c52f6d2e6b3a1 4.5,6.5>4.5,4.5
+//! client> turn 1 8d0f4b8f3e7a9c05 3.5,1.5:0.3926991
+//! host>   at 2 8d0f4b8f3e7a9c05
+//! client> bye
+//! ```
+//!
+//! | message                              | meaning                                     |
+//! |--------------------------------------|---------------------------------------------|
+//! | `hello rotchess <version> <secret> [w\|b]` | client asks to join, maybe as a side  |
+//! | `resume rotchess <version> <secret> <token>` | client that dropped asks for its side back |
+//! | `welcome <w\|b> <token> <position>`  | host lets the client in as that side, playing from a [position](rotchess_core::notation) |
+//! | `reject <reason>`                    | host turns the client away                  |
+//! | `turn <n> <hash> <actions>`          | the sender played [actions](notation::Action) as turn `n`, counting from 0, leaving a position with `hash` |
+//! | `at <n> <hash>`                      | the sender has `n` turns, and is at a position with `hash` |
+//! | `resync`                             | the sender lost track, and wants the whole game |
+//! | `sync <position> [\| <actions>]...`  | the whole game: where it started, and every turn since |
+//! | `illegal <reason>`                   | the last turn was refused, and the game is over |
+//! | `bye`                                | the sender is leaving                       |
+//! | `watch rotchess <version> <secret>`  | client asks a [server](crate::server) to watch a game |
+//! | `watching <position>`                | server lets the client watch                |
+//!
+//! Secrets, tokens and hashes are in hex. Hashes are [`Turns::turn_hash`]es.
+//! `welcome` and `watching` are always followed by a `sync`, for whoever came late.
+//!
+//! # Staying in sync
+//!
+//! Turn numbers and hashes let either side notice a turn it missed, a turn it got
+//! twice, or a game that's somehow gone somewhere else. One side is the authority:
+//! the host, or the server. Whoever's waiting for the other says `at` now and then.
+//!
+//! - The authority answers a game behind or beside its own with `sync`, and one
+//!   ahead with `at`, so the other side sends the turns it's missing.
+//! - Anyone else answers a game ahead or beside its own with `resync`, and one
+//!   behind by sending the turns again.
+//!
+//! Turns out of order are answered the same way. If a connection drops, the client
+//! may `resume` with the token it was welcomed with.
+//!
+//! [`Turns::turn_hash`]: rotchess_core::turn::Turns::turn_hash
+
+use std::fmt;
+
+use rotchess_core::{
+    notation::{self, Action, NotationError},
+    piece::Side,
+};
+
+use crate::Rules;
+
+/// The version of these messages. Hosts turn away clients with another.
+pub const VERSION: u32 = 2;
+
+pub enum Message {
+    Hello {
+        version: u32,
+        secret: u64,
+        /// The side the client wants, if it cares.
+        side: Option<Side>,
+    },
+    Resume {
+        version: u32,
+        secret: u64,
+        token: u64,
+    },
+    Welcome {
+        /// The client's side.
+        side: Side,
+        /// What the client resumes with.
+        token: u64,
+        rules: Rules,
+    },
+    Reject(String),
+    Turn {
+        /// How many turns were played before this one.
+        number: usize,
+        /// The hash after this turn.
+        hash: u64,
+        actions: Vec<Action>,
+    },
+    At {
+        /// How many turns have been played.
+        count: usize,
+        hash: u64,
+    },
+    Resync,
+    Sync {
+        rules: Rules,
+        turns: Vec<Vec<Action>>,
+    },
+    Illegal(String),
+    Bye,
+    Watch {
+        version: u32,
+        secret: u64,
+    },
+    /// The game being watched started from these rules.
+    Watching(Rules),
+}
+
+impl Message {
+    pub fn parse(line: &str) -> Result<Self, NotationError> {
+        let malformed = || NotationError::Malformed(line.to_string());
+        let line = line.trim();
+        let (word, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+        let hex = |text: &str| u64::from_str_radix(text, 16).map_err(|_| malformed());
+        match word {
+            "hello" => {
+                let mut words = rest.split_whitespace();
+                if words.next() != Some("rotchess") {
+                    return Err(malformed());
+                }
+                let version = words.next().and_then(|v| v.parse().ok());
+                let secret = words.next().and_then(|s| hex(s).ok());
+                let side = match words.next() {
+                    None => None,
+                    Some(side) => Some(parse_side(side).ok_or_else(malformed)?),
+                };
+                match (version, secret, words.next()) {
+                    (Some(version), Some(secret), None) => Ok(Message::Hello {
+                        version,
+                        secret,
+                        side,
+                    }),
+                    _ => Err(malformed()),
+                }
+            }
+            "resume" => match rest.split_whitespace().collect::<Vec<_>>()[..] {
+                ["rotchess", version, secret, token] => Ok(Message::Resume {
+                    version: version.parse().map_err(|_| malformed())?,
+                    secret: hex(secret)?,
+                    token: hex(token)?,
+                }),
+                _ => Err(malformed()),
+            },
+            "welcome" => {
+                let mut parts = rest.splitn(3, ' ');
+                let (Some(side), Some(token), Some(position)) =
+                    (parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(malformed());
+                };
+                Ok(Message::Welcome {
+                    side: parse_side(side).ok_or_else(malformed)?,
+                    token: hex(token)?,
+                    rules: position.parse()?,
+                })
+            }
+            "reject" => Ok(Message::Reject(rest.to_string())),
+            "turn" => {
+                let mut parts = rest.splitn(3, ' ');
+                let (Some(number), Some(hash), Some(actions)) =
+                    (parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(malformed());
+                };
+                Ok(Message::Turn {
+                    number: number.parse().map_err(|_| malformed())?,
+                    hash: hex(hash)?,
+                    actions: notation::parse_actions(actions)?,
+                })
+            }
+            "at" => match rest.split_whitespace().collect::<Vec<_>>()[..] {
+                [count, hash] => Ok(Message::At {
+                    count: count.parse().map_err(|_| malformed())?,
+                    hash: hex(hash)?,
+                }),
+                _ => Err(malformed()),
+            },
+            "resync" if rest.is_empty() => Ok(Message::Resync),
+            "sync" => {
+                let mut parts = rest.split('|');
+                let rules = parts.next().unwrap_or_default().trim().parse()?;
+                let turns = parts
+                    .map(notation::parse_actions)
+                    .collect::<Result<_, _>>()?;
+                Ok(Message::Sync { rules, turns })
+            }
+            "illegal" => Ok(Message::Illegal(rest.to_string())),
+            "watch" => {
+                let mut words = rest.split_whitespace();
+                if words.next() != Some("rotchess") {
+                    return Err(malformed());
+                }
+                let version = words.next().and_then(|v| v.parse().ok());
+                let secret = words.next().and_then(|s| hex(s).ok());
+                match (version, secret, words.next()) {
+                    (Some(version), Some(secret), None) => Ok(Message::Watch { version, secret }),
+                    _ => Err(malformed()),
+                }
+            }
+            "watching" => Ok(Message::Watching(rest.parse()?)),
+            "bye" if rest.is_empty() => Ok(Message::Bye),
+            _ => Err(malformed()),
+        }
+    }
+}
+
+fn parse_side(text: &str) -> Option<Side> {
+    match text {
+        "w" => Some(Side::White),
+        "b" => Some(Side::Black),
+        _ => None,
+    }
+}
+
+fn side_str(side: Side) -> &'static str {
+    match side {
+        Side::White => "w",
+        Side::Black => "b",
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Message::Hello {
+                version,
+                secret,
+                side,
+            } => {
+                write!(f, "hello rotchess {version} {secret:016x}")?;
+                match side {
+                    Some(side) => write!(f, " {}", side_str(*side)),
+                    None => Ok(()),
+                }
+            }
+            Message::Resume {
+                version,
+                secret,
+                token,
+            } => write!(f, "resume rotchess {version} {secret:016x} {token:016x}"),
+            Message::Welcome { side, token, rules } => {
+                write!(f, "welcome {} {token:016x} {rules}", side_str(*side))
+            }
+            Message::Reject(why) => write!(f, "reject {why}"),
+            Message::Turn {
+                number,
+                hash,
+                actions,
+            } => write!(
+                f,
+                "turn {number} {hash:016x} {}",
+                notation::write_actions(actions)
+            ),
+            Message::At { count, hash } => write!(f, "at {count} {hash:016x}"),
+            Message::Resync => write!(f, "resync"),
+            Message::Sync { rules, turns } => {
+                write!(f, "sync {rules}")?;
+                for turn in turns {
+                    write!(f, " | {}", notation::write_actions(turn))?;
+                }
+                Ok(())
+            }
+            Message::Illegal(why) => write!(f, "illegal {why}"),
+            Message::Bye => write!(f, "bye"),
+            Message::Watch { version, secret } => {
+                write!(f, "watch rotchess {version} {secret:016x}")
+            }
+            Message::Watching(rules) => write!(f, "watching {rules}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_8;
+
+    use super::{Message, VERSION};
+    use crate::Rules;
+    use rotchess_core::{notation::Action, piece::Side};
+
+    fn round_trip(message: Message) -> Message {
+        let line = message.to_string();
+        let parsed = Message::parse(&line).unwrap();
+        assert_eq!(parsed.to_string(), line);
+        parsed
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        let Message::Hello { side, secret, .. } = round_trip(Message::Hello {
+            version: VERSION,
+            secret: 0xabc,
+            side: Some(Side::Black),
+        }) else {
+            panic!("hello parses as hello");
+        };
+        assert_eq!((side, secret), (Some(Side::Black), 0xabc));
+
+        let Message::Welcome { side, token, rules } = round_trip(Message::Welcome {
+            side: Side::White,
+            token: 0xdef,
+            rules: Rules::default(),
+        }) else {
+            panic!("welcome parses as welcome");
+        };
+        assert_eq!((side, token), (Side::White, 0xdef));
+        assert_eq!(rules.to_move, Side::White);
+
+        let actions = vec![
+            Action::Travel {
+                from: (4.5, 6.5),
+                to: (4.5, 4.5),
+            },
+            Action::Rotate {
+                at: (4.5, 4.5),
+                angle: FRAC_PI_8,
+            },
+        ];
+        let Message::Turn {
+            number,
+            hash,
+            actions: parsed,
+        } = round_trip(Message::Turn {
+            number: 3,
+            hash: u64::MAX,
+            actions: actions.clone(),
+        })
+        else {
+            panic!("turn parses as turn");
+        };
+        assert_eq!((number, hash, parsed), (3, u64::MAX, actions.clone()));
+
+        let synced = vec![actions.clone(), actions[..1].to_vec()];
+        let Message::Sync { turns, .. } = round_trip(Message::Sync {
+            rules: Rules::default(),
+            turns: synced.clone(),
+        }) else {
+            panic!("sync parses as sync");
+        };
+        assert_eq!(turns, synced);
+        let Message::Sync { turns, .. } = round_trip(Message::Sync {
+            rules: Rules::default(),
+            turns: vec![],
+        }) else {
+            panic!("sync parses as sync");
+        };
+        assert!(turns.is_empty());
+
+        round_trip(Message::Resume {
+            version: VERSION,
+            secret: 0xabc,
+            token: 0xdef,
+        });
+        round_trip(Message::At { count: 7, hash: 8 });
+        round_trip(Message::Resync);
+
+        round_trip(Message::Reject(String::from("wrong key")));
+        round_trip(Message::Illegal(String::from("no piece at 1,1")));
+        round_trip(Message::Bye);
+        round_trip(Message::Watch {
+            version: VERSION,
+            secret: 0xabc,
+        });
+        round_trip(Message::Watching(Rules::default()));
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        for line in [
+            "",
+            "hello",
+            "hello chess 1 abc",
+            "hello rotchess 1 abc x",
+            "welcome",
+            "welcome g 1 w",
+            "welcome w w",
+            "resume rotchess 2 abc",
+            "turn x 1 1,1>2,2",
+            "turn 1 1,1>2,2",
+            "at 1",
+            "resync now",
+            "sync",
+            "bye now",
+            "watch rotchess 1",
+            "watching",
+        ] {
+            assert!(Message::parse(line).is_err(), "{line:?}");
+        }
+    }
+}
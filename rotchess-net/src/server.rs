@@ -0,0 +1,631 @@
+//! An authoritative server for many games at once.
+//!
+//! Players join a server game just like a [hosted](crate::Host) one, with a
+//! [`LobbyKey`](crate::LobbyKey) made of the server's address and any secret they
+//! agree on: the secret names the game, and the first to join creates it. Players
+//! may ask for a side like they would of a host, and get the free one otherwise.
+//! Anyone with the key may also [watch](Message::Watch) instead.
+//!
+//! The server keeps every game's [`Turns`] and plays each turn on them before
+//! anyone else sees it, so travels are checked with [`Pieces::travelable`] no
+//! matter what the client checked. A travel may be followed by a rotation of any of
+//! the mover's pieces, as in an engine [`Move`](rotchess_core::turn::Move). Refused
+//! turns are answered with `illegal`, and their sender is sent away. Accepted turns
+//! go to the other player and every spectator, who may be shown them only
+//! [after a delay](Server::with_delay).
+//! Whoever joins late is sent the game so far, and so is anyone whose
+//! game has [drifted](crate::message#staying-in-sync) from the server's.
+//!
+//! Each client is written to on a thread of its own, so one that stops reading only
+//! holds itself up, and is dropped once a write's waited too long. A player whose
+//! connection drops keeps their seat until the game's over, and may
+//! [resume](crate::Link::reconnect) in it. Saying bye gives it up.
+//!
+//! Finished games, where a king has been captured, are written to a record file if
+//! the server [has somewhere to put them](Server::with_records).
+//!
+//! [`Pieces::travelable`]: rotchess_core::piece::Pieces::travelable
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use rotchess_core::{
+    notation::{self, Action},
+    piece::Side,
+    record::{GameRecord, GameResult},
+    turn::Turns,
+};
+
+use crate::{
+    Rules,
+    message::{Message, VERSION},
+    random,
+};
+
+type ClientId = u64;
+
+/// What the connection threads tell the server.
+enum Incoming {
+    /// A client connected, and what it's sent goes down this.
+    Connected(ClientId, Sender<String>),
+    Message(ClientId, Result<Message, String>),
+    Gone(ClientId),
+}
+
+/// Where a client sits in a game.
+#[derive(Clone, Copy, PartialEq)]
+enum Seat {
+    Player(Side),
+    Spectator,
+}
+
+struct Client {
+    /// Lines for the client's writer, which hangs up once this is dropped.
+    writer: Sender<String>,
+    /// The game the client's in, by secret, and where it sits. `None` until it's
+    /// said hello.
+    seat: Option<(u64, Seat)>,
+}
+
+/// A seat at a game, taken by a player.
+struct Player {
+    /// `None` while the player's dropped.
+    client: Option<ClientId>,
+    /// What the player resumes with.
+    token: u64,
+}
+
+struct Game {
+    rules: Rules,
+    turns: Turns,
+    record: GameRecord,
+    /// The white and black players, respectively.
+    players: [Option<Player>; 2],
+    spectators: Vec<ClientId>,
+    /// When each turn was played.
+    played_at: Vec<Instant>,
+    /// How many turns spectators have been shown.
+    shown: usize,
+}
+
+impl Game {
+    fn with(secret: u64, rules: &Rules) -> Self {
+        let mut turns = Turns::with(rules.start.clone());
+        turns.set_to_move(rules.to_move);
+        let mut record = GameRecord::with(rules.start.clone(), rules.to_move);
+        record.set_tag("Game", &format!("{secret:016x}"));
+        Self {
+            rules: rules.clone(),
+            turns,
+            record,
+            players: [None, None],
+            spectators: vec![],
+            played_at: vec![],
+            shown: 0,
+        }
+    }
+
+    fn player(&self, side: Side) -> Option<&Player> {
+        match side {
+            Side::White => self.players[0].as_ref(),
+            Side::Black => self.players[1].as_ref(),
+        }
+    }
+
+    fn player_mut(&mut self, side: Side) -> &mut Option<Player> {
+        match side {
+            Side::White => &mut self.players[0],
+            Side::Black => &mut self.players[1],
+        }
+    }
+
+    /// Everyone connected to the game.
+    fn clients(&self) -> impl Iterator<Item = ClientId> + '_ {
+        self.players
+            .iter()
+            .flatten()
+            .filter_map(|player| player.client)
+            .chain(self.spectators.iter().copied())
+    }
+
+    fn finished(&self) -> bool {
+        self.turns.winner().is_some()
+    }
+
+    fn turn_count(&self) -> usize {
+        self.record.turns().len()
+    }
+
+    /// How many turns whoever sits in `seat` may see.
+    fn seen(&self, seat: Seat) -> usize {
+        match seat {
+            Seat::Player(_) => self.turn_count(),
+            Seat::Spectator => self.shown,
+        }
+    }
+
+    /// Where the game's at after `count` turns, as said by [`Message::At`].
+    fn at(&self, count: usize) -> Message {
+        Message::At {
+            count,
+            hash: self.turns.turn_hash(count),
+        }
+    }
+
+    /// The game's first `count` turns, for a late joiner to catch up on.
+    fn sync(&self, count: usize) -> Message {
+        Message::Sync {
+            rules: self.rules.clone(),
+            turns: self.record.turns()[..count].to_vec(),
+        }
+    }
+
+    fn turn(&self, number: usize) -> Message {
+        Message::Turn {
+            number,
+            hash: self.turns.turn_hash(number + 1),
+            actions: self.record.turns()[number].clone(),
+        }
+    }
+}
+
+/// Whether `actions` make one turn: a travel, a rotation, or a travel and then a
+/// rotation.
+fn is_one_turn(actions: &[Action]) -> bool {
+    matches!(
+        actions,
+        [Action::Travel { .. }]
+            | [Action::Rotate { .. }]
+            | [Action::Travel { .. }, Action::Rotate { .. }]
+    )
+}
+
+/// A server, bound but not yet serving.
+pub struct Server {
+    listener: TcpListener,
+    rules: Rules,
+    records: Option<PathBuf>,
+    delay: Duration,
+}
+
+impl Server {
+    /// Listen on `addr`. Every game starts with `rules`.
+    pub fn bind(addr: impl ToSocketAddrs, rules: Rules) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            rules,
+            records: None,
+            delay: Duration::ZERO,
+        })
+    }
+
+    /// Write finished games' records into `dir`, which is created if needed.
+    pub fn with_records(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.records = Some(dir.into());
+        self
+    }
+
+    /// Show spectators each turn only `delay` after it's played, so that they
+    /// can't help the players.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Serve forever, or until listening fails.
+    pub fn run(self) -> io::Result<()> {
+        if let Some(dir) = &self.records {
+            fs::create_dir_all(dir)?;
+        }
+        let (sender, incoming) = mpsc::channel();
+        let listener = self.listener.try_clone()?;
+        let accepting = thread::spawn(move || accept(listener, sender));
+
+        let mut state = State {
+            rules: self.rules,
+            records: self.records,
+            delay: self.delay,
+            clients: HashMap::new(),
+            games: HashMap::new(),
+        };
+        let mut next_shown: Option<Instant> = None;
+        loop {
+            let received = match next_shown {
+                Some(when) => incoming.recv_timeout(when.saturating_duration_since(Instant::now())),
+                None => incoming.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok(incoming) => state.handle(incoming),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            next_shown = state.show_spectators();
+        }
+        accepting.join().expect("accepting doesn't panic")
+    }
+}
+
+/// How long a write may wait on a client before it's dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Accept clients, giving each a thread that reads its messages and one that writes
+/// ours.
+fn accept(listener: TcpListener, sender: Sender<Incoming>) -> io::Result<()> {
+    for (id, stream) in (0..).zip(listener.incoming()) {
+        let stream = match stream {
+            Ok(stream) => stream,
+            // a client that gave up before we got to it.
+            Err(e) if e.kind() == io::ErrorKind::ConnectionAborted => continue,
+            Err(e) => return Err(e),
+        };
+        let reader = match stream
+            .set_write_timeout(Some(WRITE_TIMEOUT))
+            .and_then(|()| stream.try_clone())
+        {
+            Ok(reader) => BufReader::new(reader),
+            Err(e) => {
+                // this client's lost, but the next may be fine.
+                eprintln!("couldn't set up a client: {e}");
+                continue;
+            }
+        };
+        let (writer, lines) = mpsc::channel();
+        thread::spawn(move || write_lines(stream, lines));
+        // connected always comes before the client's first message.
+        _ = sender.send(Incoming::Connected(id, writer));
+        let sender = sender.clone();
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let Ok(line) = line else { break };
+                let message =
+                    Message::parse(&line).map_err(|e| format!("can't read {line:?}: {e}"));
+                if sender.send(Incoming::Message(id, message)).is_err() {
+                    return;
+                }
+            }
+            _ = sender.send(Incoming::Gone(id));
+        });
+    }
+    Ok(())
+}
+
+/// Write `lines` to a client until there are no more, or one isn't taken in time,
+/// then hang up.
+fn write_lines(mut stream: TcpStream, lines: Receiver<String>) {
+    for line in lines {
+        if writeln!(stream, "{line}").is_err() {
+            break;
+        }
+    }
+    _ = stream.shutdown(Shutdown::Both);
+}
+
+/// Everything the server knows, owned by the thread serving.
+struct State {
+    rules: Rules,
+    records: Option<PathBuf>,
+    delay: Duration,
+    clients: HashMap<ClientId, Client>,
+    /// Games by secret.
+    games: HashMap<u64, Game>,
+}
+
+impl State {
+    fn handle(&mut self, incoming: Incoming) {
+        match incoming {
+            Incoming::Connected(id, writer) => {
+                self.clients.insert(id, Client { writer, seat: None });
+            }
+            Incoming::Message(id, Ok(message)) => self.receive(id, message),
+            Incoming::Message(id, Err(why)) => self.send_away(id, Message::Reject(why)),
+            Incoming::Gone(id) => self.leave(id, true),
+        }
+    }
+
+    fn receive(&mut self, id: ClientId, message: Message) {
+        let Some(client) = self.clients.get(&id) else {
+            // sent away, but its thread hadn't noticed yet.
+            return;
+        };
+        match (message, client.seat) {
+            (
+                Message::Hello {
+                    version,
+                    secret,
+                    side,
+                },
+                None,
+            ) => self.seat(id, version, secret, Some(side)),
+            (
+                Message::Resume {
+                    version,
+                    secret,
+                    token,
+                },
+                None,
+            ) => self.resume(id, version, secret, token),
+            (Message::Watch { version, secret }, None) => self.seat(id, version, secret, None),
+            (
+                Message::Turn {
+                    number,
+                    hash,
+                    actions,
+                },
+                Some((secret, Seat::Player(side))),
+            ) => self.play(id, secret, side, number, hash, actions),
+            (Message::At { count, hash }, Some((secret, seat))) => {
+                let game = &self.games[&secret];
+                let seen = game.seen(seat);
+                if (count, hash) != (seen, game.turns.turn_hash(seen)) {
+                    self.disagree(id, secret, seat, count);
+                }
+            }
+            (Message::Resync, Some((secret, seat))) => {
+                let game = &self.games[&secret];
+                let sync = game.sync(game.seen(seat));
+                self.send(id, &sync);
+            }
+            (Message::Bye, _) => self.leave(id, false),
+            (message, _) => {
+                self.send_away(id, Message::Reject(format!("didn't expect {message}")));
+            }
+        }
+    }
+
+    /// Seat a client in the game named `secret`: as a player, asking for a side or
+    /// not, or as a spectator if `player` is `None`.
+    fn seat(&mut self, id: ClientId, version: u32, secret: u64, player: Option<Option<Side>>) {
+        if version != VERSION {
+            let why = format!("the server speaks version {VERSION}, not {version}");
+            return self.send_away(id, Message::Reject(why));
+        }
+        let game = self
+            .games
+            .entry(secret)
+            .or_insert_with(|| Game::with(secret, &self.rules));
+
+        let (seat, welcome) = match player {
+            Some(side) => {
+                let free = |side| game.player(side).is_none();
+                let side = match side {
+                    Some(side) if !free(side) => Err(format!("{side:?} is taken")),
+                    Some(side) => Ok(side),
+                    None if free(Side::White) => Ok(Side::White),
+                    None if free(Side::Black) => Ok(Side::Black),
+                    None => Err(String::from("the game is full, but you can watch")),
+                };
+                let side = match side {
+                    Ok(side) => side,
+                    Err(why) => return self.send_away(id, Message::Reject(why)),
+                };
+                let token = random();
+                *game.player_mut(side) = Some(Player {
+                    client: Some(id),
+                    token,
+                });
+                let welcome = Message::Welcome {
+                    side,
+                    token,
+                    rules: self.rules.clone(),
+                };
+                (Seat::Player(side), welcome)
+            }
+            None => {
+                game.spectators.push(id);
+                (Seat::Spectator, Message::Watching(self.rules.clone()))
+            }
+        };
+
+        let sync = game.sync(game.seen(seat));
+        let client = self.clients.get_mut(&id).expect("checked by receive");
+        client.seat = Some((secret, seat));
+        self.send(id, &welcome);
+        self.send(id, &sync);
+    }
+
+    /// Give a player who dropped their seat back.
+    fn resume(&mut self, id: ClientId, version: u32, secret: u64, token: u64) {
+        if version != VERSION {
+            let why = format!("the server speaks version {VERSION}, not {version}");
+            return self.send_away(id, Message::Reject(why));
+        }
+        let seat = self.games.get_mut(&secret).and_then(|game| {
+            let side = [Side::White, Side::Black].into_iter().find(|&side| {
+                game.player(side)
+                    .is_some_and(|player| player.token == token)
+            })?;
+            let player = game.player_mut(side).as_mut().expect("just found");
+            Some((
+                side,
+                player.client.replace(id),
+                game.rules.clone(),
+                game.sync(game.turn_count()),
+            ))
+        });
+        let Some((side, old, rules, sync)) = seat else {
+            let why = String::from("there's no seat to resume");
+            return self.send_away(id, Message::Reject(why));
+        };
+        // the old connection may not have noticed it's gone yet. its writer hangs up.
+        if let Some(old) = old {
+            self.clients.remove(&old);
+        }
+        let client = self.clients.get_mut(&id).expect("checked by receive");
+        client.seat = Some((secret, Seat::Player(side)));
+        self.send(id, &Message::Welcome { side, token, rules });
+        self.send(id, &sync);
+    }
+
+    /// Answer a client in `seat` that says it's at `count` turns when it shouldn't
+    /// be, or at a position with another hash.
+    fn disagree(&mut self, id: ClientId, secret: u64, seat: Seat, count: usize) {
+        let game = &self.games[&secret];
+        let seen = game.seen(seat);
+        let answer = if count > seen {
+            game.at(seen)
+        } else {
+            game.sync(seen)
+        };
+        self.send(id, &answer);
+    }
+
+    /// Play a turn sent by the player of `side` in the game named `secret`.
+    fn play(
+        &mut self,
+        id: ClientId,
+        secret: u64,
+        side: Side,
+        number: usize,
+        hash: u64,
+        actions: Vec<Action>,
+    ) {
+        let game = self
+            .games
+            .get_mut(&secret)
+            .expect("seated clients have games");
+        if number != game.turn_count() {
+            // one we've had, or one after one we've missed.
+            let at = game.at(game.turn_count());
+            return self.send(id, &at);
+        }
+        let finished = game.finished();
+        let turns = &mut game.turns;
+        let refusal = if finished {
+            Some(String::from("the game is over"))
+        } else if turns.to_move() != side {
+            Some(String::from("it's not your turn"))
+        } else if !is_one_turn(&actions) {
+            Some(String::from(
+                "a turn is a travel, a rotation, or a travel then a rotation",
+            ))
+        } else {
+            notation::play_actions(turns, &actions)
+                .err()
+                .map(|e| e.to_string())
+        };
+        if let Some(why) = refusal {
+            return self.send_away(id, Message::Illegal(why));
+        }
+
+        game.record.push_turn(actions.clone());
+        game.played_at.push(Instant::now());
+        let ours = game.turns.turn_hash(number + 1);
+        // legal, but not where the sender thinks it leads.
+        let drifted = (hash != ours).then(|| game.sync(game.turn_count()));
+        let opponent = game.player(side.toggled()).and_then(|player| player.client);
+        if let Some(winner) = game.turns.winner() {
+            game.record.set_result(GameResult::win_for(winner));
+            let record = game.record.to_string();
+            self.save(secret, &record);
+        }
+        if let Some(sync) = drifted {
+            self.send(id, &sync);
+        }
+        if let Some(opponent) = opponent {
+            let message = Message::Turn {
+                number,
+                hash: ours,
+                actions,
+            };
+            self.send(opponent, &message);
+        }
+    }
+
+    /// Show spectators every turn that's waited long enough. Returns when the next
+    /// will have, if any are waiting.
+    fn show_spectators(&mut self) -> Option<Instant> {
+        let now = Instant::now();
+        let mut next = None;
+        let mut shown = vec![];
+        for game in self.games.values_mut() {
+            while let Some(&played_at) = game.played_at.get(game.shown) {
+                let when = played_at + self.delay;
+                if when > now {
+                    next = Some(next.map_or(when, |next: Instant| next.min(when)));
+                    break;
+                }
+                shown.push((game.spectators.clone(), game.turn(game.shown)));
+                game.shown += 1;
+            }
+        }
+        for (spectators, turn) in shown {
+            for id in spectators {
+                self.send(id, &turn);
+            }
+        }
+        next
+    }
+
+    /// Write a finished game's record, if we keep them.
+    fn save(&self, secret: u64, record: &str) {
+        let Some(dir) = &self.records else {
+            return;
+        };
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let path = dir.join(format!("game-{secret:016x}-{time}.rec"));
+        if let Err(e) = fs::write(&path, record) {
+            eprintln!("couldn't write {}: {e}", path.display());
+        }
+    }
+
+    /// Send a message, ignoring failures: if the client's gone, its thread will say.
+    fn send(&mut self, id: ClientId, message: &Message) {
+        if let Some(client) = self.clients.get(&id) {
+            _ = client.writer.send(message.to_string());
+        }
+    }
+
+    /// Send one last message, then disconnect.
+    fn send_away(&mut self, id: ClientId, message: Message) {
+        self.send(id, &message);
+        self.leave(id, false);
+    }
+
+    /// Forget a client, and its game if nobody's left in it. A player who `dropped`
+    /// keeps their seat to resume in, unless the game's over.
+    fn leave(&mut self, id: ClientId, dropped: bool) {
+        // dropping the client's writer hangs up once what's been sent is written.
+        let Some(client) = self.clients.remove(&id) else {
+            return;
+        };
+        let Some((secret, seat)) = client.seat else {
+            return;
+        };
+        let game = self
+            .games
+            .get_mut(&secret)
+            .expect("seated clients have games");
+        let keep = dropped && !game.finished();
+        match seat {
+            Seat::Player(side) => {
+                let seat = game.player_mut(side);
+                // unless it's been resumed by someone else already.
+                if let Some(player) = seat.as_mut().filter(|player| player.client == Some(id)) {
+                    if keep {
+                        player.client = None;
+                    } else {
+                        *seat = None;
+                    }
+                }
+            }
+            Seat::Spectator => game.spectators.retain(|&other| other != id),
+        }
+        if game.clients().next().is_none() && game.players.iter().all(Option::is_none) {
+            self.games.remove(&secret);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Two players on one machine, talking over TCP.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+    thread,
+};
+
+use rotchess_core::{
+    notation::{self, Action},
+    piece::{PieceKind, Side},
+};
+use rotchess_event_api::{
+    Event, MouseButton, RotchessEmulator, ThingHappened, premove::PremoveError,
+};
+use rotchess_net::{Host, Link, NetError, Rules};
+
+fn host(side: Side) -> Host {
+    Host::listen("127.0.0.1:0", side, Rules::default()).unwrap()
+}
+
+/// Host as `side` and have someone join, asking for nothing.
+fn connect(side: Side) -> (Link, Link) {
+    let host = host(side);
+    let key = host.key();
+    let client = thread::spawn(move || Link::join(&key, None).unwrap());
+    let host_link = host.accept().unwrap();
+    (host_link, client.join().unwrap())
+}
+
+fn click(
+    link: &mut Link,
+    game: &mut RotchessEmulator,
+    (x, y): (f32, f32),
+) -> Option<ThingHappened> {
+    let button = MouseButton::LEFT;
+    link.handle_event(game, Event::ButtonDown { x, y, button })
+        .unwrap();
+    link.handle_event(game, Event::ButtonUp { x, y, button })
+        .unwrap()
+}
+
+fn actions(text: &str) -> Vec<Action> {
+    notation::parse_actions(text).unwrap()
+}
+
+#[test]
+fn a_scripted_game_stays_in_sync() {
+    let (mut white, mut black) = connect(Side::White);
+    assert_eq!(
+        (white.side(), black.side()),
+        (Some(Side::White), Some(Side::Black))
+    );
+    let mut white_game = white.new_emulator();
+    let mut black_game = black.new_emulator();
+
+    // black can't touch white's pieces, or move out of turn.
+    assert!(click(&mut black, &mut black_game, (4.5, 6.5)).is_none());
+    assert!(click(&mut black, &mut black_game, (4.5, 4.5)).is_none());
+    assert!(matches!(
+        black.play(&mut black_game, &actions("3.5,1.5>3.5,3.5")),
+        Err(NetError::Illegal(_))
+    ));
+
+    // e4 and exd5 by mouse, d5 by notation, then black turns a knight.
+    click(&mut white, &mut white_game, (4.5, 6.5));
+    let happened = click(&mut white, &mut white_game, (4.5, 4.5));
+    let Some(ThingHappened::Move {
+        from,
+        to,
+        captured,
+        turn,
+        to_move,
+        ..
+    }) = happened
+    else {
+        panic!("white traveled");
+    };
+    assert_eq!((from, to), ((4.5, 6.5), (4.5, 4.5)));
+    assert_eq!((captured, turn, to_move), (vec![], 1, Side::Black));
+    assert_eq!(
+        black.wait(&mut black_game).unwrap(),
+        vec![actions("4.5,6.5>4.5,4.5")]
+    );
+
+    black
+        .play(&mut black_game, &actions("3.5,1.5>3.5,3.5"))
+        .unwrap();
+    assert_eq!(
+        white.wait(&mut white_game).unwrap(),
+        vec![actions("3.5,1.5>3.5,3.5")]
+    );
+
+    click(&mut white, &mut white_game, (4.5, 4.5));
+    let happened = click(&mut white, &mut white_game, (3.5, 3.5));
+    let Some(ThingHappened::Move { captured, .. }) = happened else {
+        panic!("white took");
+    };
+    assert_eq!(captured, [(6, PieceKind::Pawn)]);
+    assert_eq!(
+        black.wait(&mut black_game).unwrap(),
+        vec![actions("4.5,4.5>3.5,3.5")]
+    );
+
+    black
+        .play(&mut black_game, &actions("1.5,0.5:0.5"))
+        .unwrap();
+    // nothing's there until it's there.
+    while white.poll(&mut white_game).unwrap().is_empty() {
+        thread::yield_now();
+    }
+
+    for game in [&white_game, &black_game] {
+        assert_eq!(game.turns().turn_count(), 5);
+        assert_eq!(game.to_move(), Side::White);
+    }
+    assert_eq!(
+        white_game.turns().working_board_ref().position_hash(),
+        black_game.turns().working_board_ref().position_hash()
+    );
+
+    drop(black);
+    assert!(matches!(white.wait(&mut white_game), Err(NetError::Closed)));
+}
+
+#[test]
+fn premoves_wait_for_the_other_side() {
+    let (mut white, mut black) = connect(Side::White);
+    let mut white_game = white.new_emulator();
+    let mut black_game = black.new_emulator();
+
+    // d5 while white thinks. nothing moves yet.
+    click(&mut black, &mut black_game, (3.5, 1.5));
+    let happened = click(&mut black, &mut black_game, (3.5, 3.5));
+    let Some(ThingHappened::PremoveQueued(premove)) = happened else {
+        panic!("black premoved");
+    };
+    assert_eq!(premove.travel, Some(((3.5, 1.5), (3.5, 3.5))));
+    assert_eq!(black_game.premoves().count(), 1);
+    assert!(black.play_premove(&mut black_game).unwrap().is_none());
+    assert_eq!(black_game.turns().turn_count(), 1);
+
+    click(&mut white, &mut white_game, (4.5, 6.5));
+    click(&mut white, &mut white_game, (4.5, 4.5));
+    black.wait(&mut black_game).unwrap();
+    let happened = black.play_premove(&mut black_game).unwrap();
+    assert!(matches!(
+        happened,
+        Some(ThingHappened::PremovePlayed { .. })
+    ));
+    assert_eq!(
+        white.wait(&mut white_game).unwrap(),
+        vec![actions("3.5,1.5>3.5,3.5")]
+    );
+
+    // exd6 needs something on d6, which exd5 doesn't put there.
+    click(&mut black, &mut black_game, (4.5, 1.5));
+    let happened = click(&mut black, &mut black_game, (3.5, 2.5));
+    assert!(matches!(happened, Some(ThingHappened::PremoveQueued(_))));
+    click(&mut white, &mut white_game, (4.5, 4.5));
+    click(&mut white, &mut white_game, (3.5, 3.5));
+    black.wait(&mut black_game).unwrap();
+    let happened = black.play_premove(&mut black_game).unwrap();
+    let Some(ThingHappened::PremoveDiscarded { reason, .. }) = happened else {
+        panic!("black's premove was dropped");
+    };
+    assert_eq!(reason, PremoveError::NotTravelable);
+    assert_eq!(black_game.premoves().count(), 0);
+    assert_eq!(black_game.to_move(), Side::Black);
+}
+
+#[test]
+fn strangers_and_side_thieves_are_turned_away() {
+    let host = host(Side::Black);
+    let key = host.key();
+    let clients = thread::spawn(move || {
+        let mut wrong = key;
+        wrong.secret ^= 1;
+        let wrong_key = Link::join(&wrong, None).err();
+        let wrong_side = Link::join(&key, Some(Side::Black)).err();
+        let link = Link::join(&key, Some(Side::White)).unwrap();
+        (wrong_key, wrong_side, link.side())
+    });
+    let link = host.accept().unwrap();
+    assert_eq!(link.side(), Some(Side::Black));
+
+    let (wrong_key, wrong_side, side) = clients.join().unwrap();
+    assert!(matches!(wrong_key, Some(NetError::Refused(_))));
+    assert!(matches!(wrong_side, Some(NetError::Refused(_))));
+    assert_eq!(side, Some(Side::White));
+}
+
+#[test]
+fn illegal_turns_are_refused() {
+    let host = host(Side::White);
+    let key = host.key();
+    // a client that doesn't check its own turns.
+    let cheat = thread::spawn(move || {
+        let stream = TcpStream::connect(key.addr).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+        writeln!(writer, "hello rotchess 2 {:016x}", key.secret).unwrap();
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert!(line.starts_with("welcome b "), "{line}");
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert!(line.starts_with("sync w "), "{line}");
+
+        // out of turn.
+        writeln!(writer, "turn 0 0 3.5,1.5>3.5,3.5").unwrap();
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        line
+    });
+    let mut link = host.accept().unwrap();
+    let mut game = link.new_emulator();
+    assert!(matches!(link.wait(&mut game), Err(NetError::Illegal(_))));
+    assert_eq!(game.turns().turn_count(), 1);
+    assert!(cheat.join().unwrap().starts_with("illegal "));
+}
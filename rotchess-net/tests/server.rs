@@ -0,0 +1,307 @@
+//! Players and spectators on a server, all on one machine.
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpStream},
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use rotchess_core::{
+    notation,
+    piece::Side,
+    record::{GameRecord, GameResult},
+    search::SearchLimits,
+};
+use rotchess_event_api::{Event, MouseButton};
+use rotchess_net::{Link, LobbyKey, NetError, Rules, server::Server};
+
+/// Start a server in the background.
+fn serve(rules: Rules, records: Option<&Path>) -> SocketAddr {
+    let mut server = Server::bind("127.0.0.1:0", rules).unwrap();
+    if let Some(dir) = records {
+        server = server.with_records(dir);
+    }
+    serve_with(server)
+}
+
+fn serve_with(server: Server) -> SocketAddr {
+    let addr = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+    addr
+}
+
+/// A client that says `hello` and then only what it's told to.
+struct Raw {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Raw {
+    fn with(addr: SocketAddr, hello: &str) -> Self {
+        let writer = TcpStream::connect(addr).unwrap();
+        let mut raw = Self {
+            reader: BufReader::new(writer.try_clone().unwrap()),
+            writer,
+        };
+        raw.send(hello);
+        raw
+    }
+
+    fn send(&mut self, line: &str) {
+        writeln!(self.writer, "{line}").unwrap();
+    }
+
+    /// The next line, or `None` once the server hangs up.
+    fn recv(&mut self) -> Option<String> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line).unwrap() {
+            0 => None,
+            _ => Some(line.trim_end().to_string()),
+        }
+    }
+}
+
+#[test]
+fn games_are_refereed_watched_and_recorded() {
+    let records = std::env::temp_dir().join(format!("rotchess-server-{}", std::process::id()));
+    // the white queen can take the black king right away, but waits a turn.
+    let rules: Rules = "w WK@4.5,7.5:0 WQ@3.5,4.5:0 BK@3.5,0.5:0".parse().unwrap();
+    let addr = serve(rules, Some(&records));
+    let key = LobbyKey { addr, secret: 0xa };
+
+    let mut white = Link::join(&key, None).unwrap();
+    let mut black = Link::join(&key, None).unwrap();
+    assert_eq!(
+        (white.side(), black.side()),
+        (Some(Side::White), Some(Side::Black))
+    );
+    assert!(matches!(Link::join(&key, None), Err(NetError::Refused(_))));
+    let mut spectator = Raw::with(addr, "watch rotchess 2 a");
+    assert!(spectator.recv().unwrap().starts_with("watching w "));
+    assert!(spectator.recv().unwrap().starts_with("sync w "));
+
+    let mut white_game = white.new_emulator();
+    let mut black_game = black.new_emulator();
+    let turns = ["4.5,7.5:0.5", "3.5,0.5:0.5", "3.5,4.5>3.5,0.5"];
+    white
+        .play(&mut white_game, &notation::parse_actions(turns[0]).unwrap())
+        .unwrap();
+    black.wait(&mut black_game).unwrap();
+    black
+        .play(&mut black_game, &notation::parse_actions(turns[1]).unwrap())
+        .unwrap();
+    white.wait(&mut white_game).unwrap();
+    white
+        .play(&mut white_game, &notation::parse_actions(turns[2]).unwrap())
+        .unwrap();
+    black.wait(&mut black_game).unwrap();
+    assert_eq!(black_game.turns().winner(), Some(Side::White));
+
+    for (number, turn) in turns.iter().enumerate() {
+        let line = spectator.recv().unwrap();
+        assert!(line.starts_with(&format!("turn {number} ")), "{line}");
+        assert!(line.ends_with(&format!(" {turn}")), "{line}");
+    }
+
+    // the record was written before the last turn was passed on.
+    let path = fs::read_dir(&records)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let record: GameRecord = fs::read_to_string(&path).unwrap().parse().unwrap();
+    assert_eq!(record.result(), Some(GameResult::WhiteWins));
+    assert_eq!(record.tag("Game"), Some("000000000000000a"));
+    assert_eq!(record.replay().ok().unwrap().winner(), Some(Side::White));
+    fs::remove_dir_all(&records).unwrap();
+}
+
+#[test]
+fn late_joiners_catch_up_and_cheats_are_sent_away() {
+    let addr = serve(Rules::default(), None);
+    let first = LobbyKey { addr, secret: 1 };
+
+    let mut black = Link::join(&first, Some(Side::Black)).unwrap();
+    assert!(matches!(
+        Link::join(&first, Some(Side::Black)),
+        Err(NetError::Refused(_))
+    ));
+    let mut black_game = black.new_emulator();
+    let mut white = Link::join(&first, None).unwrap();
+    let mut white_game = white.new_emulator();
+    white
+        .play(
+            &mut white_game,
+            &notation::parse_actions("4.5,6.5>4.5,4.5").unwrap(),
+        )
+        .unwrap();
+    black.wait(&mut black_game).unwrap();
+
+    // white leaves, and someone else sits down and is told what happened.
+    drop(white);
+    let mut spectator = Raw::with(addr, "watch rotchess 2 1");
+    spectator.recv();
+    assert!(spectator.recv().unwrap().ends_with(" | 4.5,6.5>4.5,4.5"));
+    black
+        .play(
+            &mut black_game,
+            &notation::parse_actions("3.5,1.5>3.5,3.5").unwrap(),
+        )
+        .unwrap();
+    let line = spectator.recv().unwrap();
+    assert!(line.starts_with("turn 1 ") && line.ends_with(" 3.5,1.5>3.5,3.5"));
+    let mut late = Raw::with(addr, "hello rotchess 2 1");
+    assert!(late.recv().unwrap().starts_with("welcome w "));
+    let sync = late.recv().unwrap();
+    assert!(
+        sync.ends_with(" | 4.5,6.5>4.5,4.5 | 3.5,1.5>3.5,3.5"),
+        "{sync}"
+    );
+
+    // a turn it's already had is answered with where the game's at.
+    late.send("turn 0 0 3.5,6.5>3.5,4.5");
+    assert!(late.recv().unwrap().starts_with("at 2 "));
+
+    // sent away for leaving, without saying anything else.
+    late.send("bye");
+    assert_eq!(late.recv(), None);
+
+    // pawns can't go three, turns can't hold two travels, and nobody moves twice.
+    for cheat in [
+        "turn 2 0 3.5,6.5>3.5,3.5",
+        "turn 2 0 3.5,6.5>3.5,4.5;2.5,6.5>2.5,4.5",
+        "turn 2 0 3.5,1.5>3.5,2.5",
+    ] {
+        let mut cheater = Raw::with(addr, "hello rotchess 2 1");
+        for _ in 0..2 {
+            cheater.recv();
+        }
+        cheater.send(cheat);
+        assert!(cheater.recv().unwrap().starts_with("illegal "), "{cheat}");
+        assert_eq!(cheater.recv(), None);
+    }
+}
+
+#[test]
+fn engine_moves_are_turns() {
+    let addr = serve(Rules::default(), None);
+    let key = LobbyKey { addr, secret: 5 };
+    let mut white = Link::join(&key, Some(Side::White)).unwrap();
+    let mut white_game = white.new_emulator();
+    let mut black = Link::join(&key, None).unwrap();
+    let mut black_game = black.new_emulator();
+
+    // a travel, then a rotation of another piece, like the engine plays.
+    let turn = notation::parse_actions("4.5,6.5>4.5,4.5;0.5,7.5:1").unwrap();
+    white.play(&mut white_game, &turn).unwrap();
+    assert_eq!(black.wait(&mut black_game).unwrap(), vec![turn]);
+
+    let limits = SearchLimits {
+        depth: Some(1),
+        ..SearchLimits::default()
+    };
+    for turn in 1..7 {
+        let (link, game, other, other_game) = match turn % 2 {
+            0 => (&mut white, &mut white_game, &mut black, &mut black_game),
+            _ => (&mut black, &mut black_game, &mut white, &mut white_game),
+        };
+        link.play_engine_move(game, &limits).unwrap().unwrap();
+        assert_eq!(other.wait(other_game).unwrap().len(), 1);
+    }
+    assert_eq!(
+        white_game.turns().turn_hash(7),
+        black_game.turns().turn_hash(7)
+    );
+}
+
+#[test]
+fn dropped_players_keep_their_seats_and_drifters_are_synced() {
+    let addr = serve(Rules::default(), None);
+    let mut white = Raw::with(addr, "hello rotchess 2 2 w");
+    let mut black = Raw::with(addr, "hello rotchess 2 2 b");
+    let welcome = white.recv().unwrap();
+    assert!(welcome.starts_with("welcome w "));
+    assert!(black.recv().unwrap().starts_with("welcome b "));
+    let token = welcome.split(' ').nth(2).unwrap().to_string();
+    white.recv();
+    black.recv();
+
+    // a wrong hash is still a legal turn, but its sender is put right.
+    white.send("turn 0 0 3.5,6.5>3.5,4.5");
+    let turn = black.recv().unwrap();
+    assert!(turn.starts_with("turn 0 ") && !turn.starts_with("turn 0 0000000000000000 "));
+    assert!(white.recv().unwrap().starts_with("sync "));
+
+    // white drops without a word, and nobody else may sit down.
+    drop(white);
+    black.send("at 1 0");
+    assert!(black.recv().unwrap().starts_with("sync "));
+    let mut stranger = Raw::with(addr, "hello rotchess 2 2 w");
+    assert!(stranger.recv().unwrap().starts_with("reject "));
+    let mut guesser = Raw::with(addr, "resume rotchess 2 2 1");
+    assert!(guesser.recv().unwrap().starts_with("reject "));
+
+    // but white may come back.
+    let mut white = Raw::with(addr, &format!("resume rotchess 2 2 {token}"));
+    assert_eq!(
+        white.recv().unwrap().split(' ').take(3).collect::<Vec<_>>(),
+        ["welcome", "w", &token]
+    );
+    assert!(white.recv().unwrap().ends_with(" | 3.5,6.5>3.5,4.5"));
+    black.send("turn 1 0 3.5,1.5>3.5,3.5");
+    assert!(white.recv().unwrap().starts_with("turn 1 "));
+}
+
+#[test]
+fn spectators_are_shown_turns_late_and_only_look() {
+    let delay = Duration::from_millis(300);
+    let server = Server::bind("127.0.0.1:0", Rules::default()).unwrap();
+    let addr = serve_with(server.with_delay(delay));
+    let key = LobbyKey { addr, secret: 3 };
+
+    let mut white = Link::join(&key, None).unwrap();
+    let _black = Link::join(&key, None).unwrap();
+    let mut watcher = Link::watch(&key).unwrap();
+    assert_eq!(watcher.side(), None);
+    let mut white_game = white.new_emulator();
+    let mut watched = watcher.new_emulator();
+
+    let turn = notation::parse_actions("4.5,6.5>4.5,4.5").unwrap();
+    let played = Instant::now();
+    white.play(&mut white_game, &turn).unwrap();
+    assert_eq!(watcher.wait(&mut watched).unwrap(), vec![turn.clone()]);
+    assert!(played.elapsed() >= delay);
+    assert_eq!(watcher.played(), [turn]);
+    let hash = white_game.turns().position_hash();
+    assert_eq!(watched.turns().position_hash(), hash);
+
+    // clicking does nothing, but history can still be gone through.
+    let button = MouseButton::LEFT;
+    for (x, y) in [(3.5, 1.5), (3.5, 3.5)] {
+        assert!(
+            watched
+                .handle_event(Event::ButtonDown { x, y, button })
+                .is_none()
+        );
+        assert!(
+            watched
+                .handle_event(Event::ButtonUp { x, y, button })
+                .is_none()
+        );
+    }
+    assert_eq!(watched.turns().position_hash(), hash);
+    watched.handle_event(Event::PrevTurn);
+    assert_ne!(watched.turns().position_hash(), hash);
+    assert!(
+        watcher
+            .play(
+                &mut watched,
+                &notation::parse_actions("3.5,1.5>3.5,3.5").unwrap()
+            )
+            .is_err()
+    );
+}
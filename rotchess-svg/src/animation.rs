@@ -0,0 +1,362 @@
+//! Whole games as animations.
+//!
+//! A game is a list of boards, like [`Turns::history`]. Between one board and the
+//! next, pieces slide to where they traveled and turn the short way round to their
+//! new angle. Captured pieces stay until the capturer arrives. [`animated_svg`]
+//! makes one SVG that plays itself in a browser, and [`frames`] makes a still per
+//! frame, for tools that would rather have those.
+//!
+//! [`Turns::history`]: rotchess_core::turn::Turns::history
+
+use std::{
+    f32::consts::{PI, TAU},
+    fmt::Write,
+};
+
+use rotchess_core::piece::{PIECE_RADIUS, PieceKind, Pieces, Side};
+
+use crate::{
+    HITCIRCLE_COLOR, Options, header, image_id, image_transform, render, svg_degrees, write_defs,
+    write_tiles,
+};
+
+/// How long things take, in seconds.
+#[derive(Debug, Clone)]
+pub struct Timing {
+    /// How long each board is shown still.
+    pub hold: f32,
+    /// How long getting from one board to the next takes.
+    pub travel: f32,
+    /// Frames per second, for [`frames`].
+    pub frame_rate: f32,
+    /// Whether [`animated_svg`] starts over once it ends.
+    pub repeat: bool,
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Self {
+            hold: 1.,
+            travel: 0.5,
+            frame_rate: 20.,
+            repeat: true,
+        }
+    }
+}
+
+impl Timing {
+    pub fn with_hold(mut self, hold: f32) -> Self {
+        self.hold = hold;
+        self
+    }
+
+    pub fn with_travel(mut self, travel: f32) -> Self {
+        self.travel = travel;
+        self
+    }
+
+    pub fn with_frame_rate(mut self, frame_rate: f32) -> Self {
+        self.frame_rate = frame_rate;
+        self
+    }
+
+    pub fn with_repeat(mut self, repeat: bool) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// How long a game of `boards` boards takes to play.
+    pub fn duration(&self, boards: usize) -> f32 {
+        boards as f32 * self.hold + boards.saturating_sub(1) as f32 * self.travel
+    }
+
+    /// How many frames `seconds` take.
+    fn frames(&self, seconds: f32) -> usize {
+        (seconds * self.frame_rate).round() as usize
+    }
+}
+
+/// The shortest turn from angle `from` to angle `to`.
+fn angle_delta(from: f32, to: f32) -> f32 {
+    (to - from + PI).rem_euclid(TAU) - PI
+}
+
+/// The board `t` of the way from `from` to `to`, where `t` goes from 0 to 1.
+///
+/// Pieces captured on the way are left where they were until `t` reaches 1.
+pub fn tween(from: &Pieces, to: &Pieces, t: f32) -> Pieces {
+    if t >= 1. {
+        return to.clone();
+    }
+    let mut ans = from.clone();
+    for id in from.ids() {
+        let (Some(end), Some(piece)) = (to.get(id), ans.get_mut(id)) else {
+            continue;
+        };
+        let ((x0, y0), (x1, y1)) = (piece.center(), end.center());
+        piece.set_center((x0 + (x1 - x0) * t, y0 + (y1 - y0) * t));
+        piece.set_angle(piece.angle() + angle_delta(piece.angle(), end.angle()) * t);
+    }
+    ans
+}
+
+/// A still of every frame of `history` played with `timing`, in order.
+///
+/// Each board is held for at least a frame.
+pub fn frames<'a>(
+    history: &'a [Pieces],
+    timing: &Timing,
+    options: &'a Options,
+) -> impl Iterator<Item = String> + 'a {
+    let hold = timing.frames(timing.hold).max(1);
+    let travel = timing.frames(timing.travel);
+
+    let mut boards = vec![];
+    for i in 0..history.len() {
+        if i > 0 {
+            boards.extend((1..=travel).map(|k| (i, k as f32 / (travel + 1) as f32)));
+        }
+        boards.extend((0..hold).map(|_| (i, 1.)));
+    }
+    boards.into_iter().map(move |(i, t)| match i {
+        0 => render(&history[0], None, options),
+        _ => render(&tween(&history[i - 1], &history[i], t), None, options),
+    })
+}
+
+/// Where a piece is on one board.
+#[derive(Clone, Copy)]
+struct Place {
+    alive: bool,
+    center: (f32, f32),
+    /// In svg degrees, unwrapped so that going from one board to the next is always
+    /// the short way round.
+    degrees: f32,
+    kind: PieceKind,
+    side: Side,
+}
+
+/// What [`write_animation`] animates.
+#[derive(Clone, Copy)]
+enum Animated {
+    Opacity,
+    Translate,
+    Rotate,
+}
+
+/// An animation through `values`, one per key time. Nothing if the value never
+/// changes.
+fn write_animation(ans: &mut String, animated: Animated, values: &[String], timing_attrs: &str) {
+    if values.iter().all(|value| *value == values[0]) {
+        return;
+    }
+    let element = match animated {
+        // pieces vanish when they're captured, not slowly.
+        Animated::Opacity => "animate attributeName=\"opacity\" calcMode=\"discrete\"",
+        Animated::Translate => "animateTransform attributeName=\"transform\" type=\"translate\"",
+        Animated::Rotate => "animateTransform attributeName=\"transform\" type=\"rotate\"",
+    };
+    _ = writeln!(
+        ans,
+        "<{element} values=\"{}\" {timing_attrs}/>",
+        values.join(";")
+    );
+}
+
+/// One SVG that plays `history` with `timing`, using SMIL animations.
+///
+/// Travel points and pointers in `options` aren't drawn; hit circles are.
+pub fn animated_svg(history: &[Pieces], timing: &Timing, options: &Options) -> String {
+    assert!(!history.is_empty(), "a game has at least its start");
+    let duration = timing.duration(history.len());
+
+    // every board has a key time where it's reached and one where it's left.
+    let mut key_times = vec![];
+    for i in 0..history.len() {
+        let reached = i as f32 * (timing.hold + timing.travel);
+        key_times.push(reached / duration);
+        key_times.push((reached + timing.hold) / duration);
+    }
+    let key_times = key_times
+        .iter()
+        .map(|time| format!("{time:.4}"))
+        .collect::<Vec<_>>()
+        .join(";");
+    let end = if timing.repeat {
+        "repeatCount=\"indefinite\""
+    } else {
+        "fill=\"freeze\""
+    };
+    let timing_attrs = format!("dur=\"{duration}s\" keyTimes=\"{key_times}\" {end}");
+
+    let mut ans = header(options);
+    write_defs(&mut ans, |kind, side| {
+        history.iter().any(|board| {
+            board
+                .board_pieces()
+                .any(|piece| piece.kind() == kind && piece.side() == side)
+        })
+    });
+    write_tiles(&mut ans);
+
+    let px = 1. / options.tile_size;
+    for id in history[0].ids() {
+        let Some(first) = history[0].get(id) else {
+            continue;
+        };
+        let mut place = Place {
+            alive: true,
+            center: first.center(),
+            degrees: svg_degrees(first.angle()),
+            kind: first.kind(),
+            side: first.side(),
+        };
+        let mut angle = first.angle();
+        let places: Vec<Place> = history
+            .iter()
+            .map(|board| {
+                match board.get(id) {
+                    Some(piece) => {
+                        place.degrees += svg_degrees(angle_delta(angle, piece.angle()));
+                        angle = piece.angle();
+                        place.center = piece.center();
+                        place.kind = piece.kind();
+                        place.side = piece.side();
+                    }
+                    // stay where we were captured.
+                    None => place.alive = false,
+                }
+                place
+            })
+            .collect();
+        // each value is held from the key time a board is reached to when it's left.
+        let values = |value: &dyn Fn(&Place) -> String| {
+            places
+                .iter()
+                .flat_map(|place| [value(place), value(place)])
+                .collect::<Vec<_>>()
+        };
+
+        // pieces that promote are drawn once per look, each shown only while it's right.
+        let mut looks: Vec<(PieceKind, Side)> = vec![];
+        for place in &places {
+            if !looks.contains(&(place.kind, place.side)) {
+                looks.push((place.kind, place.side));
+            }
+        }
+        for (kind, side) in looks {
+            let opacity = values(&|place| {
+                let shown = place.alive && (place.kind, place.side) == (kind, side);
+                String::from(if shown { "1" } else { "0" })
+            });
+            let translate = values(&|place| format!("{} {}", place.center.0, place.center.1));
+            let rotate = values(&|place| place.degrees.to_string());
+
+            _ = writeln!(ans, "<g opacity=\"{}\">", opacity[0]);
+            write_animation(&mut ans, Animated::Opacity, &opacity, &timing_attrs);
+            _ = writeln!(ans, "<g transform=\"translate({})\">", translate[0]);
+            write_animation(&mut ans, Animated::Translate, &translate, &timing_attrs);
+            _ = writeln!(ans, "<g transform=\"rotate({})\">", rotate[0]);
+            write_animation(&mut ans, Animated::Rotate, &rotate, &timing_attrs);
+            _ = writeln!(
+                ans,
+                "<use xlink:href=\"#{}\" transform=\"{}\"/>\n</g>",
+                image_id(kind, side),
+                image_transform()
+            );
+            if options.hit_circles {
+                _ = writeln!(
+                    ans,
+                    "<circle r=\"{PIECE_RADIUS}\" fill=\"none\" {} stroke-width=\"{px}\"/>",
+                    HITCIRCLE_COLOR.attrs("stroke")
+                );
+            }
+            ans.push_str("</g>\n</g>\n");
+        }
+    }
+
+    ans.push_str("</svg>\n");
+    ans
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{PI, TAU};
+
+    use super::{Timing, animated_svg, frames, tween};
+    use crate::Options;
+    use rotchess_core::{
+        notation,
+        piece::{Piece, PieceKind, Pieces, Side},
+        turn::Turns,
+    };
+
+    /// e4, d5, exd5.
+    fn game() -> Turns {
+        let mut turns = Turns::with(Pieces::standard_board());
+        for turn in [
+            "4.5,6.5>4.5,4.5",
+            "3.5,1.5>3.5,3.5",
+            "4.5,4.5>3.5,3.5;4.5,4.5:-0.1",
+        ] {
+            let actions = notation::parse_actions(turn).unwrap();
+            notation::play_actions(&mut turns, &actions).unwrap();
+        }
+        turns
+    }
+
+    #[test]
+    fn tweens_slide_and_turn_the_short_way() {
+        let from = Pieces::with(vec![
+            Piece::from_tile(0, (4, 6), 0.1, Side::White, PieceKind::Pawn),
+            Piece::from_tile(1, (4, 4), PI, Side::Black, PieceKind::Pawn),
+        ]);
+        let mut to = from.clone();
+        to.get_mut(0).unwrap().set_center((4.5, 4.5));
+        to.get_mut(0).unwrap().set_angle(TAU - 0.1);
+        to.capture(1);
+
+        let half = tween(&from, &to, 0.5);
+        let pawn = half.get(0).unwrap();
+        assert_eq!(pawn.center(), (4.5, 5.5));
+        assert!(pawn.angle().abs() < 1e-5, "{}", pawn.angle());
+        assert!(half.get(1).is_some(), "captured at the end, not before");
+        assert!(tween(&from, &to, 1.).get(1).is_none());
+    }
+
+    #[test]
+    fn frames_follow_the_timing() {
+        let turns = game();
+        let timing = Timing::default()
+            .with_hold(1.)
+            .with_travel(0.5)
+            .with_frame_rate(4.);
+        let options = Options::default();
+        let frames: Vec<_> = frames(turns.history(), &timing, &options).collect();
+        // four boards held for 4 frames, three travels of 2.
+        assert_eq!(frames.len(), 4 * 4 + 3 * 2);
+        assert_eq!(frames[0], frames[3]);
+        assert_ne!(frames[3], frames[4]);
+        assert_eq!(frames.last(), frames.get(frames.len() - 2));
+    }
+
+    #[test]
+    fn games_animate() {
+        let turns = game();
+        let svg = animated_svg(
+            turns.history(),
+            &Timing::default().with_repeat(false),
+            &Options::default(),
+        );
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<use").count(), 32);
+        // the two pawns that traveled, and the one that turned.
+        assert_eq!(svg.matches("type=\"translate\"").count(), 2);
+        assert_eq!(svg.matches("type=\"rotate\"").count(), 1);
+        // the pawn on d5 is taken.
+        assert_eq!(svg.matches("attributeName=\"opacity\"").count(), 1);
+        assert!(svg.contains("values=\"1;1;1;1;1;1;0;0\""));
+        assert!(svg.contains("dur=\"5.5s\""));
+        assert!(svg.contains("fill=\"freeze\""));
+    }
+}
@@ -0,0 +1,444 @@
+//! Pictures of rotchess boards as SVG, without a window or a GPU.
+//!
+//! [`render`] draws a position the way `rotchess-ui`'s game screen does: the tiles,
+//! each piece turned to its angle, and, when a piece is selected, its travel points
+//! and everyone's hit circles. The output is plain text, so it can go in docs and
+//! bug reports, or be compared in tests.
+//!
+//! Drawing happens in board units, where a tile is 1 wide, so coordinates in the
+//! SVG are the same as in [`rotchess_core`]. [`Options::tile_size`] only sets how
+//! big the image is.
+
+use std::fmt::Write;
+
+use rotchess_core::piece::{PIECE_RADIUS, Piece, PieceId, PieceKind, Pieces, Side, TravelKind};
+use rotchess_event_api::{RotchessEmulator, TravelPoint};
+
+pub mod animation;
+
+/// A color, with alpha.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgba(pub u8, pub u8, pub u8, pub u8);
+
+impl Rgba {
+    /// Fill or stroke attributes, like `fill="#ff0000" fill-opacity="0.78"`.
+    fn attrs(&self, name: &str) -> String {
+        let Rgba(r, g, b, a) = *self;
+        let mut ans = format!("{name}=\"#{r:02x}{g:02x}{b:02x}\"");
+        if a != 255 {
+            _ = write!(ans, " {name}-opacity=\"{:.3}\"", a as f32 / 255.);
+        }
+        ans
+    }
+}
+
+// the same colors as the game screen.
+pub const DARK_TILE_COLOR: Rgba = Rgba(181, 136, 99, 255);
+pub const LIGHT_TILE_COLOR: Rgba = Rgba(240, 217, 181, 255);
+pub const SELECTED_PIECE_COLOR: Rgba = Rgba(255, 255, 153, 200);
+pub const MOVE_OUTLINE_COLOR: Rgba = Rgba(173, 255, 244, 255);
+pub const MOVE_HIGHLIGHT_COLOR: Rgba = Rgba(173, 255, 244, 200);
+pub const CAPTURE_OUTLINE_COLOR: Rgba = Rgba(255, 0, 0, 255);
+pub const CAPTURE_HIGHLIGHT_COLOR: Rgba = Rgba(255, 0, 0, 200);
+pub const HITCIRCLE_COLOR: Rgba = Rgba(0, 255, 127, 255);
+
+/// Size of a piece image as a fraction of a tile.
+const PIECE_SIZE: f32 = 0.9;
+/// Width and height of the piece images in `rotchess-ui/assets/pieces`.
+const IMAGE_SIZE: f32 = 45.;
+/// Radius of the dot or triangle marking a travel point.
+const INDICATOR_SIZE: f32 = 0.12;
+
+/// A piece image, from the same files the game screen loads.
+macro_rules! image {
+    ($name:literal) => {
+        include_str!(concat!(
+            "../../rotchess-ui/assets/pieces/piece_",
+            $name,
+            "1.svg"
+        ))
+    };
+}
+
+const IMAGES: [(PieceKind, Side, &str); 12] = [
+    (PieceKind::Pawn, Side::White, image!("pawnW")),
+    (PieceKind::Rook, Side::White, image!("rookW")),
+    (PieceKind::Knight, Side::White, image!("knightW")),
+    (PieceKind::Bishop, Side::White, image!("bishopW")),
+    (PieceKind::Queen, Side::White, image!("queenW")),
+    (PieceKind::King, Side::White, image!("kingW")),
+    (PieceKind::Pawn, Side::Black, image!("pawnB")),
+    (PieceKind::Rook, Side::Black, image!("rookB")),
+    (PieceKind::Knight, Side::Black, image!("knightB")),
+    (PieceKind::Bishop, Side::Black, image!("bishopB")),
+    (PieceKind::Queen, Side::Black, image!("queenB")),
+    (PieceKind::King, Side::Black, image!("kingB")),
+];
+
+/// The id a piece image gets in `<defs>`, like `knightB`.
+pub fn image_id(kind: PieceKind, side: Side) -> String {
+    format!("{}{}", kind.to_file_desc(), side.to_file_desc())
+}
+
+/// The drawing inside a piece image file, without the `<svg>` around it.
+///
+/// Also drops any `<script>`s, which some of the files picked up on their way here.
+fn image_body(file: &str) -> String {
+    let start = file
+        .find("<svg")
+        .and_then(|svg| file[svg..].find('>').map(|end| svg + end + 1))
+        .expect("piece images are svgs");
+    let end = file.rfind("</svg>").expect("piece images are svgs");
+    let mut body = &file[start..end];
+
+    let mut ans = String::new();
+    while let Some(script) = body.find("<script") {
+        ans.push_str(&body[..script]);
+        let close = body[script..]
+            .find("/>")
+            .map(|close| script + close + 2)
+            .unwrap_or(body.len());
+        body = &body[close..];
+    }
+    ans.push_str(body);
+    ans.trim().to_string()
+}
+
+/// How to draw a board.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Pixels per tile.
+    pub tile_size: f32,
+    /// Draw every piece's hit circle.
+    ///
+    /// They're drawn anyway while a piece is selected, like the game does.
+    pub hit_circles: bool,
+    /// Where the mouse is, in board units. Travelable points under it are
+    /// highlighted, like the game does.
+    pub pointer: Option<(f32, f32)>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            tile_size: 64.,
+            hit_circles: false,
+            pointer: None,
+        }
+    }
+}
+
+impl Options {
+    pub fn with_tile_size(mut self, tile_size: f32) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    pub fn with_hit_circles(mut self, hit_circles: bool) -> Self {
+        self.hit_circles = hit_circles;
+        self
+    }
+
+    pub fn with_pointer(mut self, x: f32, y: f32) -> Self {
+        self.pointer = Some((x, y));
+        self
+    }
+}
+
+/// The travel points of piece `id`, as the emulator would show them if it were
+/// selected.
+///
+/// `None` if there's no such piece.
+pub fn travel_points(pieces: &Pieces, id: PieceId) -> Option<Vec<TravelPoint>> {
+    let mut piece = pieces.get(id)?.clone();
+    // the cached points may be stale, so always start over.
+    piece.init_auxiliary_data();
+    Some(
+        piece
+            .travel_points_unchecked()
+            .map(|(kind, x, y)| TravelPoint {
+                x,
+                y,
+                travelable: pieces.travelable(&piece, x, y, kind).is_some(),
+                kind,
+            })
+            .collect(),
+    )
+}
+
+/// Draw the emulator's board and selection.
+pub fn render_emulator(emulator: &RotchessEmulator, options: &Options) -> String {
+    render(
+        emulator.turns().working_board_ref(),
+        emulator.selected(),
+        options,
+    )
+}
+
+/// Draw `pieces`, with `selected` highlighted along with its travel points.
+pub fn render(
+    pieces: &Pieces,
+    selected: Option<(&Piece, &[TravelPoint])>,
+    options: &Options,
+) -> String {
+    // a pixel, in board units.
+    let px = 1. / options.tile_size;
+
+    let mut ans = header(options);
+    write_defs(&mut ans, |kind, side| {
+        pieces
+            .board_pieces()
+            .any(|piece| piece.kind() == kind && piece.side() == side)
+    });
+    write_tiles(&mut ans);
+
+    if let Some((piece, _)) = selected {
+        write_highlight(&mut ans, piece.x(), piece.y(), SELECTED_PIECE_COLOR, px);
+    }
+
+    let hit_circles = options.hit_circles || selected.is_some();
+    for piece in pieces.board_pieces() {
+        write_piece(&mut ans, piece);
+        if hit_circles {
+            write_outline(&mut ans, piece.x(), piece.y(), HITCIRCLE_COLOR, px);
+        }
+    }
+
+    if let Some((_, travel_points)) = selected {
+        for tp in travel_points {
+            if tp.travelable {
+                let hovered = options
+                    .pointer
+                    .is_some_and(|(x, y)| Piece::collidepoint_generic(x, y, tp.x, tp.y));
+                match (hovered, tp.kind) {
+                    (true, TravelKind::Capture) => {
+                        write_highlight(&mut ans, tp.x, tp.y, CAPTURE_HIGHLIGHT_COLOR, px)
+                    }
+                    (true, TravelKind::Move) => {
+                        write_highlight(&mut ans, tp.x, tp.y, MOVE_HIGHLIGHT_COLOR, px)
+                    }
+                    (false, TravelKind::Capture) => write_capture_indicator(&mut ans, tp.x, tp.y),
+                    (false, TravelKind::Move) => write_move_indicator(&mut ans, tp.x, tp.y),
+                }
+            }
+            let color = match tp.kind {
+                TravelKind::Capture => CAPTURE_OUTLINE_COLOR,
+                TravelKind::Move => MOVE_OUTLINE_COLOR,
+            };
+            write_outline(&mut ans, tp.x, tp.y, color, px);
+        }
+    }
+
+    ans.push_str("</svg>\n");
+    ans
+}
+
+/// The opening `<svg>` tag, sized for `options`.
+fn header(options: &Options) -> String {
+    let size = 8. * options.tile_size;
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" \
+         width=\"{size}\" height=\"{size}\" viewBox=\"0 0 8 8\">\n"
+    )
+}
+
+/// Piece images for every kind and side that's `used`, to be `<use>`d by id.
+fn write_defs(ans: &mut String, used: impl Fn(PieceKind, Side) -> bool) {
+    ans.push_str("<defs>\n");
+    for (kind, side, file) in IMAGES {
+        if used(kind, side) {
+            _ = writeln!(
+                ans,
+                "<g id=\"{}\">{}</g>",
+                image_id(kind, side),
+                image_body(file)
+            );
+        }
+    }
+    ans.push_str("</defs>\n");
+}
+
+fn write_tiles(ans: &mut String) {
+    _ = writeln!(
+        ans,
+        "<rect width=\"8\" height=\"8\" {}/>",
+        LIGHT_TILE_COLOR.attrs("fill")
+    );
+    for row in 0..8 {
+        for col in (0..8).filter(|col| (row + col) % 2 == 1) {
+            _ = writeln!(
+                ans,
+                "<rect x=\"{col}\" y=\"{row}\" width=\"1\" height=\"1\" {}/>",
+                DARK_TILE_COLOR.attrs("fill")
+            );
+        }
+    }
+}
+
+/// Centers a piece image on the origin and sizes it to the board.
+fn image_transform() -> String {
+    format!(
+        "translate({} {}) scale({})",
+        -PIECE_SIZE / 2.,
+        -PIECE_SIZE / 2.,
+        PIECE_SIZE / IMAGE_SIZE
+    )
+}
+
+/// Svg's rotation for a piece at `angle`. Angles grow counterclockwise, but svg
+/// rotates clockwise.
+fn svg_degrees(angle: f32) -> f32 {
+    -angle.to_degrees()
+}
+
+fn write_piece(ans: &mut String, piece: &Piece) {
+    _ = writeln!(
+        ans,
+        "<use xlink:href=\"#{}\" transform=\"translate({} {}) rotate({}) {}\"/>",
+        image_id(piece.kind(), piece.side()),
+        piece.x(),
+        piece.y(),
+        svg_degrees(piece.angle()),
+        image_transform(),
+    );
+}
+
+/// A filled circle around a piece or travel point.
+fn write_highlight(ans: &mut String, x: f32, y: f32, color: Rgba, px: f32) {
+    // half a pixel more, so no background shows between this and an outline.
+    _ = writeln!(
+        ans,
+        "<circle cx=\"{x}\" cy=\"{y}\" r=\"{}\" {}/>",
+        PIECE_RADIUS + 0.5 * px,
+        color.attrs("fill")
+    );
+}
+
+/// A hit circle's outline.
+fn write_outline(ans: &mut String, x: f32, y: f32, color: Rgba, px: f32) {
+    _ = writeln!(
+        ans,
+        "<circle cx=\"{x}\" cy=\"{y}\" r=\"{PIECE_RADIUS}\" fill=\"none\" {} stroke-width=\"{px}\"/>",
+        color.attrs("stroke")
+    );
+}
+
+fn write_move_indicator(ans: &mut String, x: f32, y: f32) {
+    _ = writeln!(
+        ans,
+        "<circle cx=\"{x}\" cy=\"{y}\" r=\"{INDICATOR_SIZE}\" {}/>",
+        MOVE_HIGHLIGHT_COLOR.attrs("fill")
+    );
+}
+
+fn write_capture_indicator(ans: &mut String, x: f32, y: f32) {
+    let half_width = INDICATOR_SIZE / 2. * f32::sqrt(3.);
+    let bottom = y + INDICATOR_SIZE / 2.;
+    _ = writeln!(
+        ans,
+        "<polygon points=\"{x},{} {},{bottom} {},{bottom}\" {}/>",
+        y - INDICATOR_SIZE,
+        x - half_width,
+        x + half_width,
+        CAPTURE_HIGHLIGHT_COLOR.attrs("fill")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IMAGES, Options, image_body, render, render_emulator, travel_points};
+    use rotchess_core::piece::{Piece, PieceKind, Pieces, Side, TravelKind};
+    use rotchess_event_api::{Event, MouseButton, RotchessEmulator};
+
+    /// How many times `needle` appears in the drawing, past the piece images.
+    fn count(svg: &str, needle: &str) -> usize {
+        let (_, drawing) = svg.split_once("</defs>").unwrap();
+        drawing.matches(needle).count()
+    }
+
+    #[test]
+    fn images_are_unwrapped() {
+        for (kind, side, file) in IMAGES {
+            let body = image_body(file);
+            assert!(!body.contains("<svg"), "{kind:?} {side:?}");
+            assert!(!body.contains("<script"), "{kind:?} {side:?}");
+            assert!(body.starts_with('<') && body.ends_with('>'));
+        }
+    }
+
+    #[test]
+    fn positions_are_drawn() {
+        let svg = render(&Pieces::standard_board(), None, &Options::default());
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        assert!(svg.contains("width=\"512\""));
+        // the light background, and the dark tiles over it.
+        assert_eq!(count(&svg, "<rect"), 1 + 32);
+        assert_eq!(count(&svg, "<use"), 32);
+        assert_eq!(svg.matches("<g id=").count(), 12);
+        assert_eq!(count(&svg, "<circle"), 0);
+        // black faces down.
+        assert!(svg.contains("#rookB\" transform=\"translate(0.5 0.5) rotate(180)"));
+
+        let svg = render(
+            &Pieces::standard_board(),
+            None,
+            &Options::default().with_hit_circles(true),
+        );
+        assert_eq!(count(&svg, "<circle"), 32);
+    }
+
+    #[test]
+    fn selections_show_travel_points() {
+        let pieces = Pieces::with(vec![
+            Piece::from_tile(0, (4, 7), 0., Side::White, PieceKind::King),
+            Piece::from_tile(1, (3, 6), 0., Side::Black, PieceKind::Pawn),
+        ]);
+        let points = travel_points(&pieces, 0).unwrap();
+        let captures = points
+            .iter()
+            .filter(|tp| tp.kind == TravelKind::Capture)
+            .count();
+        let travelable = points.iter().filter(|tp| tp.travelable).count();
+        let capturable = points
+            .iter()
+            .filter(|tp| tp.travelable && tp.kind == TravelKind::Capture)
+            .count();
+        assert_eq!(capturable, 1);
+
+        let king = pieces.get(0).unwrap();
+        let svg = render(&pieces, Some((king, &points)), &Options::default());
+        assert_eq!(count(&svg, "<polygon"), capturable);
+        // the highlight, two hit circles, an outline per travel point and a dot per
+        // travelable move.
+        assert_eq!(
+            count(&svg, "<circle"),
+            1 + 2 + points.len() + travelable - capturable
+        );
+        assert_eq!(count(&svg, "stroke=\"#ff0000\""), captures);
+
+        // pointing at the capture highlights it instead.
+        let svg = render(
+            &pieces,
+            Some((king, &points)),
+            &Options::default().with_pointer(3.5, 6.5),
+        );
+        assert_eq!(count(&svg, "<polygon"), 0);
+        assert_eq!(count(&svg, "fill=\"#ff0000\""), 1);
+    }
+
+    #[test]
+    fn emulators_are_drawn_with_their_selection() {
+        let mut emulator = RotchessEmulator::with(Pieces::standard_board());
+        let plain = render_emulator(&emulator, &Options::default());
+        assert_eq!(count(&plain, "<circle"), 0);
+
+        emulator.handle_event(Event::ButtonDown {
+            x: 1.5,
+            y: 7.5,
+            button: MouseButton::LEFT,
+        });
+        let selected = render_emulator(&emulator, &Options::default());
+        assert!(count(&selected, "<circle") > 32);
+        assert!(selected.contains("fill=\"#ffff99\""));
+    }
+}
@@ -4,18 +4,25 @@ use macroquad::audio::play_sound_once;
 use macroquad::prelude::*;
 use macroquad::ui::root_ui;
 use macroquad::{
-    rand::{self, ChooseRandom},
     time,
     window::{screen_height, screen_width},
 };
+use rotchess_core::annotation::{Annotation, MarkColor};
+use rotchess_core::engine::Engine;
+use rotchess_core::notation;
+use rotchess_core::piece::PIECE_RADIUS;
 use rotchess_core::piece::Pieces;
+use rotchess_core::piece::Side;
 use rotchess_core::piece::TravelKind;
-use rotchess_core::piece::{PIECE_RADIUS, Piece};
-use rotchess_event_api::RotchessEmulator;
+use rotchess_core::search::SearchLimits;
+use rotchess_event_api::view::View;
 use rotchess_event_api::{self, Event, ThingHappened};
+use rotchess_event_api::{Control, Modifiers, RotchessEmulator, TravelPoint};
+use rotchess_net::Link;
 
 use crate::common::move_sound;
 
+use super::lobby::PlayerKind;
 use super::{GlobalData, Screen, ScreenId};
 
 const DARK_TILE_COLOR: Color = Color::from_rgba(181, 136, 99, 255);
@@ -32,24 +39,42 @@ const CAPTURE_OUTLINE_COLOR: Color = Color::from_rgba(255, 0, 0, 255);
 const CAPTURE_HIGHLIGHT_COLOR: Color = Color::from_rgba(255, 0, 0, 200);
 /// springgreen
 const HITCIRCLE_COLOR: Color = Color::from_rgba(0, 255, 127, 255);
+/// purplish
+const PREMOVE_COLOR: Color = Color::from_rgba(160, 90, 255, 220);
+
+/// What annotations are drawn in, by the color they were given.
+fn mark_color(color: MarkColor) -> Color {
+    match color {
+        MarkColor::Green => Color::from_rgba(21, 120, 27, 170),
+        MarkColor::Red => Color::from_rgba(136, 32, 32, 170),
+        MarkColor::Blue => Color::from_rgba(0, 48, 136, 170),
+        MarkColor::Yellow => Color::from_rgba(230, 143, 0, 170),
+    }
+}
+
+/// How far a scroll wheel notch, or Q or E, turns the selected piece.
+const ROTATE_STEP: f32 = TAU / 32.;
+
+/// How many of an online game's latest turns are listed beside the board.
+const MOVE_LIST_LEN: usize = 15;
 
 enum ChessLayout {
     Standard,
-    Chess960,
+    /// A Chess960 position, by number. Kept so that resetting replays the same setup.
+    Chess960(u16),
 }
 
 impl ChessLayout {
+    /// A Chess960 layout picked by the time.
+    fn random_chess960() -> Self {
+        let seed = u64::from_be_bytes(time::get_time().to_be_bytes());
+        ChessLayout::Chess960(Pieces::seeded_chess960_board(seed).1)
+    }
+
     fn get_layout(&self) -> Pieces {
         match self {
             ChessLayout::Standard => Pieces::standard_board(),
-            ChessLayout::Chess960 => Pieces::chess960_board(|| {
-                let r = rand::RandGenerator::new();
-                r.srand(u64::from_be_bytes(time::get_time().to_be_bytes()));
-
-                let mut ordering: [usize; 8] = std::array::from_fn(|i| i);
-                ordering.shuffle_with_state(&r);
-                ordering
-            }),
+            ChessLayout::Chess960(number) => Pieces::chess960_board(*number),
         }
     }
 }
@@ -58,6 +83,22 @@ pub struct Game {
     chess: RotchessEmulator,
     runit_to_world_multiplier: f32,
     chess_layout: ChessLayout,
+    /// The engines playing white and black, respectively. `None` for humans.
+    engines: [Option<Box<dyn Engine>>; 2],
+    /// The side whose engine is thinking, which the emulator has borrowed meanwhile.
+    thinking_for: Option<usize>,
+    /// Our end of an online game, if we're playing one.
+    online: Option<Link>,
+    /// How the online game is going, or how it ended.
+    online_status: String,
+    /// Whether black is at the bottom.
+    flipped: bool,
+    /// Why our last premove was dropped, if it was.
+    premove_status: String,
+    /// The modifier keys the emulator last heard were held.
+    modifiers: Modifiers,
+    /// The degrees being typed to turn the selected piece to, while they're typed.
+    angle_entry: Option<String>,
 }
 
 impl Game {
@@ -72,11 +113,9 @@ impl Game {
         a * self.runit_to_world_multiplier
     }
 
-    /// Converts from a world unit (pixel) to rotchess unit.
-    ///
-    /// Must be run after we update the ratio after any screen resize, lest the value be outdated.
-    fn cnv_w(&self, a: f32) -> f32 {
-        a / self.runit_to_world_multiplier
+    /// Converts a point on the board to where it's drawn, flipped if need be.
+    fn cnv_p(&self, point: (f32, f32)) -> (f32, f32) {
+        self.chess.view().to_screen(point)
     }
 
     pub fn new() -> Self {
@@ -84,6 +123,228 @@ impl Game {
             chess: RotchessEmulator::with(Pieces::standard_board()),
             runit_to_world_multiplier: 0.,
             chess_layout: ChessLayout::Standard,
+            engines: [None, None],
+            thinking_for: None,
+            online: None,
+            online_status: String::new(),
+            flipped: false,
+            premove_status: String::new(),
+            modifiers: Modifiers::default(),
+            angle_entry: None,
+        }
+    }
+
+    /// Handle an event, sending it to the other player if we're online.
+    fn handle_event(&mut self, event: Event) -> Option<ThingHappened> {
+        let Some(link) = self.online.as_mut() else {
+            return self.chess.handle_event(event);
+        };
+        match link.handle_event(&mut self.chess, event) {
+            Ok(happened) => happened,
+            Err(e) => {
+                self.end_online(e);
+                None
+            }
+        }
+    }
+
+    /// Play whatever the other player sent. Returns whether they played anything.
+    fn play_online_turns(&mut self) -> bool {
+        let Some(link) = self.online.as_mut() else {
+            return false;
+        };
+        match link.poll(&mut self.chess) {
+            Ok(turns) => !turns.is_empty(),
+            Err(e) => {
+                self.end_online(e);
+                false
+            }
+        }
+    }
+
+    /// Drop the online game, leaving the board as it was. A dropped connection is
+    /// tried again first.
+    fn end_online(&mut self, why: rotchess_net::NetError) {
+        if let (rotchess_net::NetError::Dropped, Some(link)) = (&why, self.online.as_mut())
+            && link.reconnect(&mut self.chess).is_ok()
+        {
+            return;
+        }
+        self.online = None;
+        self.online_status = format!("game over: {why}");
+    }
+
+    /// Let the engine think if it's an AI's turn, and move once it's done, or once
+    /// the move asked for with M is. Returns whether it moved.
+    ///
+    /// Nothing happens while an earlier turn is looked at, or once a king's taken.
+    fn play_ai_turn(&mut self) -> bool {
+        let turns = self.chess.turns();
+        if turns.curr_turn() + 1 != turns.turn_count() || turns.winner().is_some() {
+            return false;
+        }
+        if self.chess.is_thinking() || self.thinking_for.is_some() {
+            let happened = self.chess.poll_thinking();
+            if !self.chess.is_thinking()
+                && let Some(side_idx) = self.thinking_for.take()
+            {
+                self.engines[side_idx] = Some(self.chess.take_engine());
+            }
+            return happened.is_some();
+        }
+
+        let side_idx = match self.chess.to_move() {
+            Side::White => 0,
+            Side::Black => 1,
+        };
+        let Some(engine) = self.engines[side_idx].take() else {
+            return false;
+        };
+        // the emulator borrows the engine until it's done thinking.
+        self.chess.set_engine(engine);
+        self.chess
+            .start_thinking(SearchLimits::default().with_clock(time::get_time));
+        self.thinking_for = Some(side_idx);
+        false
+    }
+
+    /// Start the layout over. Against an engine, the human's moves made while it
+    /// thinks are premoves.
+    fn restart(&mut self) {
+        self.stop_ai_turn();
+        self.chess = RotchessEmulator::with(self.chess_layout.get_layout());
+        match self.engines {
+            [None, Some(_)] => self.chess.set_control(Control::Side(Side::White)),
+            [Some(_), None] => self.chess.set_control(Control::Side(Side::Black)),
+            _ => {}
+        }
+        self.premove_status.clear();
+    }
+
+    /// Play our first premove, if it's our turn. Returns whether it was played.
+    fn play_premove(&mut self) -> bool {
+        let happened = match self.online.as_mut() {
+            Some(link) => match link.play_premove(&mut self.chess) {
+                Ok(happened) => happened,
+                Err(e) => {
+                    self.end_online(e);
+                    None
+                }
+            },
+            None => self.chess.play_premove(),
+        };
+        match happened {
+            Some(ThingHappened::PremovePlayed { .. }) => {
+                self.premove_status.clear();
+                true
+            }
+            Some(ThingHappened::PremoveDiscarded { reason, .. }) => {
+                self.premove_status = format!("premove dropped: {reason}");
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Take the keys typed into the angle being entered, turning the selected piece
+    /// once enter is hit. Returns whether an angle is still being typed.
+    fn type_angle(&mut self) -> bool {
+        let Some(entry) = self.angle_entry.as_mut() else {
+            return false;
+        };
+        while let Some(c) = get_char_pressed() {
+            if c.is_ascii_digit() || c == '.' || c == '-' {
+                entry.push(c);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            entry.pop();
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.angle_entry = None;
+        } else if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            if let Ok(degrees) = entry.parse::<f32>() {
+                self.handle_event(Event::RotateTo(degrees.to_radians()));
+                self.confirm();
+            }
+            self.angle_entry = None;
+        }
+        true
+    }
+
+    /// Play the turn picked with the keyboard.
+    fn confirm(&mut self) {
+        if let Some(ThingHappened::Move { .. } | ThingHappened::Rotate { .. }) =
+            self.handle_event(Event::Confirm)
+            && let Some(sound) = move_sound()
+        {
+            play_sound_once(sound);
+        }
+    }
+
+    /// Select, turn and travel pieces without the mouse.
+    fn play_by_keyboard(&mut self) {
+        let shift = self.modifiers.shift;
+        if is_key_pressed(KeyCode::Tab) {
+            self.handle_event(match shift {
+                true => Event::SelectPrevPiece,
+                false => Event::SelectNextPiece,
+            });
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.handle_event(Event::NextTravelPoint);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.handle_event(Event::PrevTravelPoint);
+        }
+        // counterclockwise is positive.
+        if is_key_pressed(KeyCode::Q) {
+            self.handle_event(Event::RotateBy(ROTATE_STEP));
+        }
+        if is_key_pressed(KeyCode::E) {
+            self.handle_event(Event::RotateBy(-ROTATE_STEP));
+        }
+        let (_, wheel) = mouse_wheel();
+        if wheel != 0. {
+            self.handle_event(Event::RotateBy(wheel.signum() * ROTATE_STEP));
+        }
+        if is_key_pressed(KeyCode::A) && self.chess.selected().is_some() {
+            // the a itself is waiting to be read, along with anything typed before.
+            while get_char_pressed().is_some() {}
+            self.angle_entry = Some(String::new());
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            self.confirm();
+        }
+    }
+
+    /// Hand the emulator what fingers on the screen did. Returns whether any are down,
+    /// in which case the mouse macroquad makes of them should be left alone.
+    fn play_by_touch(&mut self) -> bool {
+        let touches = touches();
+        for touch in &touches {
+            let (id, x, y) = (touch.id, touch.position.x, touch.position.y);
+            let event = match touch.phase {
+                TouchPhase::Started => Event::TouchStart { id, x, y },
+                TouchPhase::Moved => Event::TouchMove { id, x, y },
+                TouchPhase::Ended | TouchPhase::Cancelled => Event::TouchEnd { id, x, y },
+                TouchPhase::Stationary => continue,
+            };
+            if let Some(ThingHappened::Move { .. } | ThingHappened::Rotate { .. }) =
+                self.handle_event(event)
+                && let Some(sound) = move_sound()
+            {
+                play_sound_once(sound);
+            }
+        }
+        !touches.is_empty()
+    }
+
+    /// Stop any engine thinking and give it back to its side, before the emulator's
+    /// replaced.
+    fn stop_ai_turn(&mut self) {
+        if let Some(side_idx) = self.thinking_for.take() {
+            self.engines[side_idx] = Some(self.chess.take_engine());
         }
     }
 }
@@ -91,6 +352,7 @@ impl Game {
 /// Draw helpers.
 impl Game {
     fn draw_board(&self) {
+        // the board looks the same flipped, so the view doesn't come into it.
         draw_rectangle(0., 0., self.cnv_r(8.), self.cnv_r(8.), LIGHT_TILE_COLOR);
 
         let mut top = 0;
@@ -119,13 +381,8 @@ impl Game {
     }
 
     fn draw_piece_outline(&self, x: f32, y: f32, color: Color) {
-        draw_circle_lines(
-            self.cnv_r(x),
-            self.cnv_r(y),
-            self.cnv_r(PIECE_RADIUS),
-            1.,
-            color,
-        );
+        let (x, y) = self.cnv_p((x, y));
+        draw_circle_lines(x, y, self.cnv_r(PIECE_RADIUS), 1., color);
     }
 
     fn draw_piece_highlight(&self, x: f32, y: f32, color: Color) {
@@ -135,26 +392,17 @@ impl Game {
         /// extra tolerance, there will be background poking in between the highlight
         /// and outline.
         const TOLERANCE: f32 = 0.5;
-        draw_circle(
-            self.cnv_r(x),
-            self.cnv_r(y),
-            self.cnv_r(PIECE_RADIUS) + TOLERANCE,
-            color,
-        );
+        let (x, y) = self.cnv_p((x, y));
+        draw_circle(x, y, self.cnv_r(PIECE_RADIUS) + TOLERANCE, color);
     }
 
     fn draw_movablepoint_indicator(&self, x: f32, y: f32) {
-        draw_circle(
-            self.cnv_r(x),
-            self.cnv_r(y),
-            self.cnv_r(0.12),
-            MOVE_HIGHLIGHT_COLOR,
-        );
+        let (x, y) = self.cnv_p((x, y));
+        draw_circle(x, y, self.cnv_r(0.12), MOVE_HIGHLIGHT_COLOR);
     }
 
     fn draw_capturablepoint_indicator(&self, x: f32, y: f32) {
-        let x = self.cnv_r(x);
-        let y = self.cnv_r(y);
+        let (x, y) = self.cnv_p((x, y));
         let dist = self.cnv_r(0.12);
         // draw_circle(x, y, 5., MOVE_HIGHLIGHT_COLOR);
 
@@ -175,25 +423,95 @@ impl Game {
         );
     }
 
+    /// Where each queued premove goes, and which way it'll face.
+    fn draw_premoves(&self) {
+        for premove in self.chess.premoves() {
+            let Some(piece) = self.chess.turns().working_board_ref().get(premove.piece) else {
+                continue;
+            };
+            let mut at = piece.center();
+            if let Some((from, to)) = premove.travel {
+                let (from_x, from_y) = self.cnv_p(from);
+                let (to_x, to_y) = self.cnv_p(to);
+                draw_line(from_x, from_y, to_x, to_y, 3., PREMOVE_COLOR);
+                self.draw_piece_outline(to.0, to.1, PREMOVE_COLOR);
+                at = to;
+            }
+            if let Some(angle) = premove.rotation {
+                // angles go counterclockwise from up.
+                let facing = (
+                    at.0 - PIECE_RADIUS * angle.sin(),
+                    at.1 - PIECE_RADIUS * angle.cos(),
+                );
+                let (at_x, at_y) = self.cnv_p(at);
+                let (facing_x, facing_y) = self.cnv_p(facing);
+                draw_line(at_x, at_y, facing_x, facing_y, 3., PREMOVE_COLOR);
+            }
+        }
+    }
+
+    fn draw_annotation(&self, annotation: Annotation) {
+        match annotation {
+            Annotation::Circle { at, color } => {
+                let (x, y) = self.cnv_p(at);
+                draw_circle_lines(
+                    x,
+                    y,
+                    self.cnv_r(PIECE_RADIUS),
+                    self.cnv_r(0.06),
+                    mark_color(color),
+                );
+            }
+            Annotation::Arrow { from, to, color } => {
+                let from = Vec2::from(self.cnv_p(from));
+                let to = Vec2::from(self.cnv_p(to));
+                let head = self.cnv_r(0.35);
+                let along = (to - from).normalize_or_zero();
+                let across = along.perp() * head / 2.;
+                let base = to - along * head;
+                draw_line(
+                    from.x,
+                    from.y,
+                    base.x,
+                    base.y,
+                    self.cnv_r(0.12),
+                    mark_color(color),
+                );
+                draw_triangle(to, base + across, base - across, mark_color(color));
+            }
+        }
+    }
+
+    /// Every annotation on the turn being looked at, and the one being drawn.
+    fn draw_annotations(&self) {
+        for &annotation in self.chess.annotations() {
+            self.draw_annotation(annotation);
+        }
+        if let Some(annotation) = self.chess.drawing_arrow() {
+            self.draw_annotation(annotation);
+        }
+    }
+
     fn draw_pieces(&self, show_hitcircles: bool) {
         /// Size as fraction of 1.
         const PIECE_SIZE: f32 = 0.9;
         for piece in self.chess.pieces() {
+            let (x, y) = self.cnv_p(piece.center());
             draw_texture_ex(
                 crate::common::get_image_unchecked(&format!(
                     "piece_{}{}1",
                     piece.kind().to_file_desc(),
                     piece.side().to_file_desc()
                 )),
-                self.cnv_r(piece.x() - PIECE_SIZE / 2.),
-                self.cnv_r(piece.y() - PIECE_SIZE / 2.),
+                x - self.cnv_r(PIECE_SIZE / 2.),
+                y - self.cnv_r(PIECE_SIZE / 2.),
                 WHITE,
                 DrawTextureParams {
                     dest_size: Some(Vec2 {
                         x: self.cnv_r(PIECE_SIZE),
                         y: self.cnv_r(PIECE_SIZE),
                     }),
-                    rotation: TAU - piece.angle(),
+                    rotation: TAU - self.chess.view().angle_to_screen(piece.angle()),
                     ..Default::default()
                 },
             );
@@ -210,9 +528,32 @@ impl Screen for Game {
         ScreenId::Game
     }
 
-    fn enter(&mut self, _global_data: &mut GlobalData) {}
+    fn enter(&mut self, global_data: &mut GlobalData) {
+        self.stop_ai_turn();
+        self.premove_status.clear();
+        if let Some(link) = global_data.online.take() {
+            self.chess = link.new_emulator();
+            self.engines = [None, None];
+            self.flipped = link.side() == Some(Side::Black);
+            self.online_status = match link.side() {
+                Some(side) => format!("playing online as {side:?}"),
+                None => String::from("watching online"),
+            };
+            self.online = Some(link);
+        } else if let Some(players) = global_data.local_players.take() {
+            let seed = u64::from_be_bytes(time::get_time().to_be_bytes());
+            self.engines = players.map(|player| match player {
+                PlayerKind::Human => None,
+                PlayerKind::Ai(kind) => Some(kind.build(seed)),
+            });
+            self.restart();
+        }
+    }
 
-    fn exit(&mut self, _global_data: &mut GlobalData) {}
+    fn exit(&mut self, _global_data: &mut GlobalData) {
+        // says bye.
+        self.online = None;
+    }
 
     fn update(&mut self, _global_data: &mut GlobalData) -> Option<ScreenId> {
         self.update_runit_to_world_multiplier();
@@ -220,90 +561,168 @@ impl Screen for Game {
         if root_ui().button(vec2(self.cnv_r(8.) + 10., 11.), "back") {
             return Some(ScreenId::Splash);
         }
+        if let ChessLayout::Chess960(number) = self.chess_layout {
+            root_ui().label(
+                vec2(self.cnv_r(8.) + 10., 41.),
+                &format!("chess960 #{number}"),
+            );
+        }
+        if !self.premove_status.is_empty() {
+            root_ui().label(
+                vec2(self.cnv_r(8.) + 10., self.cnv_r(8.) - 30.),
+                &self.premove_status,
+            );
+        }
+        if let Some(entry) = &self.angle_entry {
+            root_ui().label(
+                vec2(self.cnv_r(8.) + 10., self.cnv_r(8.) - 60.),
+                &format!("turn to {entry}_ degrees"),
+            );
+        }
+        if !self.online_status.is_empty() {
+            root_ui().label(vec2(self.cnv_r(8.) + 10., 71.), &self.online_status);
+        }
+        if let Some(link) = &self.online {
+            // the latest turns, to follow along with.
+            let played = link.played();
+            let first = played.len().saturating_sub(MOVE_LIST_LEN);
+            for (row, (number, turn)) in played.iter().enumerate().skip(first).enumerate() {
+                root_ui().label(
+                    vec2(self.cnv_r(8.) + 10., 101. + 20. * row as f32),
+                    &format!("{}. {}", number + 1, notation::write_actions(turn)),
+                );
+            }
+        }
 
-        let (pixel_mouse_x, pixel_mouse_y) = mouse_position();
-        let (mouse_x, mouse_y) = (self.cnv_w(pixel_mouse_x), self.cnv_w(pixel_mouse_y));
+        // the emulator reads the mouse through the view.
+        let (mouse_x, mouse_y) = mouse_position();
 
-        if is_key_pressed(KeyCode::M) {
-            self.chess.make_best_move();
+        // an online game can't be reset, or played by the engine.
+        let offline = self.online.is_none();
+        // while an angle's typed, the keys are its.
+        let typing = self.type_angle();
+        let hotkeys = !typing;
+
+        // played by play_ai_turn once it's thought of, unless an AI's thinking already.
+        if offline && hotkeys && is_key_pressed(KeyCode::M) && !self.chess.is_thinking() {
+            self.chess
+                .start_thinking(SearchLimits::default().with_clock(time::get_time));
         }
 
-        if is_key_pressed(KeyCode::Key9) || is_key_pressed(KeyCode::Kp9) {
-            self.chess_layout = ChessLayout::Chess960;
-            self.chess = RotchessEmulator::with(self.chess_layout.get_layout());
+        if offline && hotkeys && (is_key_pressed(KeyCode::Key9) || is_key_pressed(KeyCode::Kp9)) {
+            self.chess_layout = ChessLayout::random_chess960();
+            self.restart();
         }
 
-        if is_key_pressed(KeyCode::Key0) || is_key_pressed(KeyCode::Kp0) {
+        if offline && hotkeys && (is_key_pressed(KeyCode::Key0) || is_key_pressed(KeyCode::Kp0)) {
             self.chess_layout = ChessLayout::Standard;
-            self.chess = RotchessEmulator::with(self.chess_layout.get_layout());
+            self.restart();
+        }
+
+        if offline && hotkeys && is_key_pressed(KeyCode::R) {
+            self.restart();
+        }
+
+        if hotkeys && is_key_pressed(KeyCode::Escape) {
+            self.chess.clear_premoves();
+            self.handle_event(Event::Cancel);
+        }
+
+        if hotkeys && is_key_pressed(KeyCode::F) {
+            self.flipped = !self.flipped;
+        }
+        // after the keys, which may have replaced the emulator.
+        self.chess.set_view(
+            View::default()
+                .with_flipped(self.flipped)
+                .with_scale(self.runit_to_world_multiplier),
+        );
+
+        let modifiers = Modifiers {
+            shift: is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift),
+            ctrl: is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl),
+            alt: is_key_down(KeyCode::LeftAlt) || is_key_down(KeyCode::RightAlt),
+        };
+        if modifiers != self.modifiers {
+            self.modifiers = modifiers;
+            self.handle_event(Event::Modifiers(modifiers));
         }
 
-        if is_key_pressed(KeyCode::R) {
-            self.chess = RotchessEmulator::with(self.chess_layout.get_layout());
+        if hotkeys {
+            self.play_by_keyboard();
         }
 
-        if is_key_pressed(KeyCode::Left) {
+        if hotkeys && is_key_pressed(KeyCode::Left) {
             if is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) {
-                self.chess.handle_event(Event::FirstTurn);
+                self.handle_event(Event::FirstTurn);
             } else {
-                self.chess.handle_event(Event::PrevTurn);
+                self.handle_event(Event::PrevTurn);
             }
         }
 
-        if is_key_pressed(KeyCode::Right) {
+        if hotkeys && is_key_pressed(KeyCode::Right) {
             if is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) {
-                self.chess.handle_event(Event::LastTurn);
+                self.handle_event(Event::LastTurn);
             } else {
-                self.chess.handle_event(Event::NextTurn);
+                self.handle_event(Event::NextTurn);
             }
         }
 
-        if is_mouse_button_pressed(MouseButton::Left) {
-            self.chess.handle_event(Event::ButtonDown {
+        // macroquad turns fingers into the mouse too, which the buttons need, so the
+        // mouse is ignored while they're down.
+        let touching = self.play_by_touch();
+
+        if !touching && is_mouse_button_pressed(MouseButton::Left) {
+            self.handle_event(Event::ButtonDown {
                 x: mouse_x,
                 y: mouse_y,
                 button: rotchess_event_api::MouseButton::LEFT,
             });
         }
 
-        if is_mouse_button_released(MouseButton::Left) {
-            let thing_happened = self.chess.handle_event(Event::ButtonUp {
+        if !touching && is_mouse_button_released(MouseButton::Left) {
+            let thing_happened = self.handle_event(Event::ButtonUp {
                 x: mouse_x,
                 y: mouse_y,
                 button: rotchess_event_api::MouseButton::LEFT,
             });
 
-            if let Some(ThingHappened::Move(_, _, _)) | Some(ThingHappened::Rotate(_, _)) =
-                thing_happened
+            if let Some(ThingHappened::Move { .. } | ThingHappened::Rotate { .. }) = thing_happened
                 && move_sound().is_some()
             {
                 play_sound_once(&move_sound().unwrap());
             };
         }
 
-        if is_mouse_button_pressed(MouseButton::Right) {
-            self.chess.handle_event(Event::ButtonDown {
+        if !touching && is_mouse_button_pressed(MouseButton::Right) {
+            self.handle_event(Event::ButtonDown {
                 x: mouse_x,
                 y: mouse_y,
                 button: rotchess_event_api::MouseButton::RIGHT,
             });
         }
 
-        if is_mouse_button_released(MouseButton::Right) {
-            self.chess.handle_event(Event::ButtonUp {
+        if !touching && is_mouse_button_released(MouseButton::Right) {
+            self.handle_event(Event::ButtonUp {
                 x: mouse_x,
                 y: mouse_y,
                 button: rotchess_event_api::MouseButton::RIGHT,
             });
         }
 
-        if mouse_delta_position() != Vec2::ZERO {
-            self.chess.handle_event(Event::MouseMotion {
+        if !touching && mouse_delta_position() != Vec2::ZERO {
+            self.handle_event(Event::MouseMotion {
                 x: mouse_x,
                 y: mouse_y,
             });
         }
 
+        // premoves go once the other side's move is in.
+        let played = self.play_ai_turn() || self.play_online_turns();
+        if (self.play_premove() || played) && move_sound().is_some() {
+            play_sound_once(move_sound().unwrap());
+        }
+
         None
     }
 
@@ -319,25 +738,28 @@ impl Screen for Game {
 
         // egui_macroquad::draw();
         self.draw_pieces(selected.is_some());
+        self.draw_premoves();
+        self.draw_annotations();
 
         if let Some((_, travelpoints)) = selected {
+            let hovered = self.chess.hovered_travelpoint();
+            // picked with the keyboard, which may be a premove that isn't travelable yet.
+            let focused = self.chess.focused_travelpoint();
             for tp in travelpoints {
-                if tp.travelable {
-                    let (xpix, ypix) = mouse_position();
-                    if Piece::collidepoint_generic(self.cnv_w(xpix), self.cnv_w(ypix), tp.x, tp.y) {
-                        self.draw_piece_highlight(
-                            tp.x,
-                            tp.y,
-                            match tp.kind {
-                                TravelKind::Capture => CAPTURE_HIGHLIGHT_COLOR,
-                                TravelKind::Move => MOVE_HIGHLIGHT_COLOR,
-                            },
-                        );
-                    } else {
+                let is = |other: Option<&TravelPoint>| other.is_some_and(|o| std::ptr::eq(o, tp));
+                if is(focused) || tp.travelable && is(hovered) {
+                    self.draw_piece_highlight(
+                        tp.x,
+                        tp.y,
                         match tp.kind {
-                            TravelKind::Capture => self.draw_capturablepoint_indicator(tp.x, tp.y),
-                            TravelKind::Move => self.draw_movablepoint_indicator(tp.x, tp.y),
-                        }
+                            TravelKind::Capture => CAPTURE_HIGHLIGHT_COLOR,
+                            TravelKind::Move => MOVE_HIGHLIGHT_COLOR,
+                        },
+                    );
+                } else if tp.travelable {
+                    match tp.kind {
+                        TravelKind::Capture => self.draw_capturablepoint_indicator(tp.x, tp.y),
+                        TravelKind::Move => self.draw_movablepoint_indicator(tp.x, tp.y),
                     }
                 }
                 self.draw_piece_outline(
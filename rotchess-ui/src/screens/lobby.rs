@@ -4,15 +4,16 @@ use macroquad::{
     ui::{hash, root_ui, widgets},
     window::clear_background,
 };
-use rotchess_core::piece;
+use rotchess_core::{engine::EngineKind, piece};
+use rotchess_net::{Host, Link, LobbyKey, Rules};
 
 use super::{GlobalData, Screen, ScreenId};
 
 /// Settings for the authority of the player, i.e. whether they're host or client.
 enum AuthoritySettings {
     Unselected,
-    /// Host may choose which side they start on.
-    Host(piece::Side),
+    /// Host may choose which side they start on, and waits here for a client.
+    Host(Host),
     /// Client must supply a lobby key from the host.
     ///
     /// See [`Lobby::lobby_key_buf`]. We cannot keep it here because macroquad ui issue.
@@ -21,19 +22,53 @@ enum AuthoritySettings {
 
 struct OnlineSettings {
     authority: AuthoritySettings,
+    /// What's going on, or what went wrong, for the player to read.
+    status: String,
+}
+
+impl OnlineSettings {
+    fn with(authority: AuthoritySettings, status: String) -> Self {
+        Self { authority, status }
+    }
+}
+
+/// The sides in the online combo box, indexed as chosen.
+const SIDE_CHOICES: &[&str] = &["as black", "as white"];
+
+fn side_from_choice(idx: usize) -> piece::Side {
+    match idx {
+        0 => piece::Side::Black,
+        _ => piece::Side::White,
+    }
 }
 
 /// Whether this local player should be a human or AI.
-enum PlayerKind {
+#[derive(Clone, Copy)]
+pub enum PlayerKind {
     Human,
-    Ai,
+    /// An AI, played by the given engine. Weaker engines are our difficulty levels.
+    Ai(EngineKind),
+}
+
+/// The choices in the player kind combo boxes, indexed as in [`PlayerKind::from_choice`].
+const PLAYER_KIND_CHOICES: &[&str] = &["Human", "AI (random)", "AI (greedy)", "AI (alpha-beta)"];
+
+impl PlayerKind {
+    /// Convert an index into [`PLAYER_KIND_CHOICES`].
+    fn from_choice(idx: usize) -> Self {
+        match idx {
+            0 => PlayerKind::Human,
+            i => PlayerKind::Ai(EngineKind::ALL[i - 1]),
+        }
+    }
 }
 
 /// We hold the settings that the user can change.
 enum LobbySettings {
     Online(OnlineSettings),
-    /// Settings for the player kinds for P1 and P2.
-    Local(PlayerKind, PlayerKind),
+    /// Starting a local game. The player kinds are handed to the game screen in
+    /// [`GlobalData::local_players`].
+    Local,
     Unselected,
 }
 
@@ -62,7 +97,7 @@ impl Screen for Lobby {
 
     fn exit(&mut self, _global_data: &mut GlobalData) {}
 
-    fn update(&mut self, _global_data: &mut GlobalData) -> Option<ScreenId> {
+    fn update(&mut self, global_data: &mut GlobalData) -> Option<ScreenId> {
         clear_background(macroquad::color::WHITE);
         macroquad::text::draw_text_ex(
             "ROTATING CHESS",
@@ -95,30 +130,104 @@ impl Screen for Lobby {
             return Some(ScreenId::Splash);
         }
 
+        let mut start_local = false;
+        let (mut p1_choice, mut p2_choice) = (0, 0);
+        let (mut host, mut join, mut watch, mut side_choice) = (false, false, false, 0);
         root_ui().window(hash!(), vec2(10., 100.), vec2(300., 200.), |ui| {
             ui.label(vec2(10., 10.), "play local");
             widgets::Group::new(hash!(), vec2(280., 65.))
                 .position(vec2(10., 10. + 20.))
                 .ui(ui, |ui| {
-                    ui.button(vec2(200., 0.), "start game");
+                    start_local = ui.button(vec2(200., 0.), "start game");
 
-                    ui.combo_box(hash!(), "   P1", &["Human", "AI"], None);
+                    p1_choice = ui.combo_box(hash!(), "   P1", PLAYER_KIND_CHOICES, None);
                     ui.separator();
-                    ui.combo_box(hash!(), "   P2", &["Human", "AI"], None);
+                    p2_choice = ui.combo_box(hash!(), "   P2", PLAYER_KIND_CHOICES, None);
                 });
             ui.label(vec2(10., 10. + 10. + 85.), "play online");
             widgets::Group::new(hash!(), vec2(280., 65.))
                 .position(vec2(10., 10. + 10. + 20. + 85.))
                 .ui(ui, |ui| {
-                    ui.combo_box(hash!(), "            ", &["as black", "as white"], None);
-                    ui.button(vec2(140., 2.), "host game");
+                    side_choice = ui.combo_box(hash!(), "            ", SIDE_CHOICES, None);
+                    host = ui.button(vec2(140., 2.), "host game");
                     ui.separator();
                     widgets::InputText::new(hash!())
                         .size(vec2(122., 20.))
                         .ui(ui, &mut self.lobby_key_buf);
-                    ui.button(vec2(140., 27.), "join game");
+                    join = ui.button(vec2(140., 27.), "join game");
+                    watch = ui.button(vec2(215., 27.), "watch");
                 });
         });
+        if let LobbySettings::Online(online) = &self.settings {
+            root_ui().label(vec2(10., 310.), &online.status);
+        }
+
+        if host {
+            let side = side_from_choice(side_choice);
+            self.settings =
+                LobbySettings::Online(match Host::listen("0.0.0.0:0", side, Rules::default()) {
+                    Ok(host) => {
+                        // shown in the key box, so it can be copied from there.
+                        self.lobby_key_buf = host.key().to_string();
+                        OnlineSettings::with(
+                            AuthoritySettings::Host(host),
+                            String::from("waiting for someone to join with this key"),
+                        )
+                    }
+                    Err(e) => OnlineSettings::with(
+                        AuthoritySettings::Unselected,
+                        format!("can't host: {e}"),
+                    ),
+                });
+        }
+
+        if join || watch {
+            let joined = self.lobby_key_buf.parse::<LobbyKey>().and_then(|key| {
+                match watch {
+                    true => Link::watch(&key),
+                    false => Link::join(&key, None),
+                }
+                .map_err(|e| e.to_string())
+            });
+            match joined {
+                Ok(link) => {
+                    global_data.online = Some(link);
+                    return Some(ScreenId::Game);
+                }
+                Err(why) => {
+                    self.settings = LobbySettings::Online(OnlineSettings::with(
+                        AuthoritySettings::Client,
+                        format!("can't join: {why}"),
+                    ));
+                }
+            }
+        }
+
+        if let LobbySettings::Online(online) = &mut self.settings
+            && let AuthoritySettings::Host(host) = &online.authority
+        {
+            match host.poll() {
+                Ok(Some(link)) => {
+                    global_data.online = Some(link);
+                    return Some(ScreenId::Game);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    online.status = format!("stopped hosting: {e}");
+                    online.authority = AuthoritySettings::Unselected;
+                }
+            }
+        }
+
+        if start_local {
+            let (p1, p2) = (
+                PlayerKind::from_choice(p1_choice),
+                PlayerKind::from_choice(p2_choice),
+            );
+            self.settings = LobbySettings::Local;
+            global_data.local_players = Some([p1, p2]);
+            return Some(ScreenId::Game);
+        }
         None
     }
 
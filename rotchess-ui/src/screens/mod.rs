@@ -27,11 +27,24 @@ impl ScreenId {
 }
 
 /// Data that could be used throughout any game screen.
-pub struct GlobalData {}
+pub struct GlobalData {
+    /// Who plays white and black in the next local game, set by the lobby.
+    ///
+    /// Taken by the game screen when it's entered. If `None`, the game screen is a
+    /// playground where humans move both sides.
+    pub local_players: Option<[lobby::PlayerKind; 2]>,
+    /// Our end of an online game the lobby connected, if any.
+    ///
+    /// Taken by the game screen when it's entered, over `local_players`.
+    pub online: Option<rotchess_net::Link>,
+}
 
 impl Default for GlobalData {
     fn default() -> Self {
-        Self {}
+        Self {
+            local_players: None,
+            online: None,
+        }
     }
 }
 